@@ -1,16 +1,18 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use fernet::Fernet;
+use rs::encoding::{BitOrder, Encoding, Value};
 use rs::evaluator::Evaluator;
 use rs::garbler::{GarbledGate, Garbler};
-use rs::optimizer::optimize;
+use rs::optimizer::{optimize_with_config, OptimizerConfig, Partition, SaturationMode};
 use rs::parser;
-use rs::utils::{topo_sort_wires, wire_values_str, Circuit, GateType};
+use rs::utils::{Circuit, GateType};
 use std::collections::BTreeMap;
 use std::fs::read_to_string;
 
 fn garble_and_evaluate_prepared(
     circuit: &Circuit,
     ins: &BTreeMap<String, Vec<String>>,
+    outputs: &[String],
     wire_to_keys: &BTreeMap<String, (String, String)>,
     garbled_gates: &BTreeMap<String, GarbledGate>,
 ) {
@@ -20,9 +22,17 @@ fn garble_and_evaluate_prepared(
     let alice_input = "Hello, world!";
     let chaining_value = "This is a chaining value";
 
-    // Assuming wire_values and other necessary functions are accessible
-    let alice_input_values = wire_values_str(alice_input_keys, alice_input.to_string()); // Example values
-    let bob_input_values = wire_values_str(bob_input_keys, chaining_value.to_string());
+    // Strings go on the wires most significant bit first
+    let encoding = Encoding {
+        bit_order: BitOrder::MsbFirst,
+        ..Default::default()
+    };
+    let alice_input_values = encoding
+        .encode(alice_input_keys, &Value::from(alice_input))
+        .expect("The message fits the first input");
+    let bob_input_values = encoding
+        .encode(bob_input_keys, &Value::from(chaining_value))
+        .expect("The chaining value fits the second input");
 
     let mut alice_input_labels = BTreeMap::new();
     let mut bob_input_labels = BTreeMap::new();
@@ -47,21 +57,9 @@ fn garble_and_evaluate_prepared(
         bob_input_labels.insert(wire_id.clone(), label.to_string());
     }
 
-    // Assuming the setup for evaluator is similar to the one in your main function
-    let sorted_wires = topo_sort_wires(&circuit);
-
-    let mut output_wires = Vec::new();
-    for wire in sorted_wires.iter() {
-        if !garbled_gates
-            .values()
-            .any(|gate| gate.input_wire_ids.contains(&wire))
-        {
-            output_wires.push(wire.clone());
-        }
-    }
     let mut evaluator = Evaluator::new(
         circuit.clone(),
-        output_wires,
+        outputs.to_vec(),
         wire_to_keys.clone(),
         garbled_gates.clone(),
     );
@@ -75,42 +73,47 @@ fn criterion_benchmark(c: &mut Criterion) {
     let contents = read_to_string(file_path).expect("Couldn't find or load that file.");
 
     // Move circuit creation outside of the benchmark loop
-    let (circuit, ins, outs) = parser::parse_bristol_fashion(&contents);
+    let (circuit, ins, outs) =
+        parser::parse_bristol_fashion(&contents).expect("Couldn't parse circuit.");
     let normal_circuit = circuit.clone();
 
-    let out_keys = outs
-        .iter()
-        .map(|(_wire_name, wire_id)| {
-            wire_id
-                .iter()
-                .map(|id| id.to_string())
-                .collect::<Vec<String>>()
-        })
-        .flatten()
-        .collect::<Vec<String>>();
-
-    let xor_optimized_circuit = optimize(circuit.clone(), out_keys);
-
-    let number_of_xor_gates = normal_circuit
-        .values()
-        .map(|(gate, _)| if gate == &GateType::XOR { 1 } else { 0 })
-        .sum::<i32>();
-
-    let optim_number_of_xor_gates = xor_optimized_circuit
-        .values()
-        .map(|(gate, _)| if gate == &GateType::XOR { 1 } else { 0 })
-        .sum::<i32>();
-
-    println!("Normal Number of XOR gates: {}", number_of_xor_gates);
-    println!("Optim Number of XOR gates: {}", optim_number_of_xor_gates);
+    let out_keys = outs.values().flatten().cloned().collect::<Vec<String>>();
+
+    // Windows keep the optimization time predictable on a circuit this size, and the
+    // cache skips it entirely on later runs
+    let config = OptimizerConfig {
+        mode: SaturationMode::Shared,
+        partition: Partition::Gates(2000),
+        cache: Some("target/optimizer-cache".into()),
+        ..Default::default()
+    };
+    let (xor_optimized_circuit, report) =
+        optimize_with_config(circuit.clone(), out_keys.clone(), &config)
+            .expect("Built-in rules are sound");
+
+    let xor_gates =
+        |counts: &BTreeMap<GateType, usize>| counts.get(&GateType::XOR).copied().unwrap_or(0);
+    println!(
+        "Normal Number of XOR gates: {}",
+        xor_gates(&report.original_gate_counts)
+    );
+    println!(
+        "Optim Number of XOR gates: {}",
+        xor_gates(&report.optimized_gate_counts)
+    );
+    println!(
+        "Non-free gates: {} -> {}",
+        report.original_nonfree, report.optimized_nonfree
+    );
+    println!("Phases: {:?}", report.phases);
 
     let delta = Fernet::generate_key();
     let delta2 = Fernet::generate_key();
 
-    let mut garbler = Garbler::new(delta, circuit.clone(), ins.clone(), outs.clone());
+    let garbler = Garbler::new(delta, circuit.clone(), ins.clone(), outs.clone());
     let (wire_to_keys, garbled_gates) = garbler.build();
 
-    let mut xor_garbler = Garbler::new(
+    let xor_garbler = Garbler::new(
         delta2,
         xor_optimized_circuit.clone(),
         ins.clone(),
@@ -123,7 +126,13 @@ fn criterion_benchmark(c: &mut Criterion) {
 
     group.bench_function("normal_circuit", |b| {
         b.iter(|| {
-            garble_and_evaluate_prepared(&normal_circuit, &ins, &wire_to_keys, &garbled_gates)
+            garble_and_evaluate_prepared(
+                &normal_circuit,
+                &ins,
+                &out_keys,
+                &wire_to_keys,
+                &garbled_gates,
+            )
         })
     });
     group.bench_function("xor_optimized_circuit", |b| {
@@ -131,6 +140,7 @@ fn criterion_benchmark(c: &mut Criterion) {
             garble_and_evaluate_prepared(
                 &xor_optimized_circuit,
                 &ins,
+                &out_keys,
                 &xor_wire_to_keys,
                 &xor_garbled_gates,
             )
@@ -4,13 +4,14 @@ use rs::evaluator::Evaluator;
 use rs::garbler::{GarbledGate, Garbler};
 use rs::optimizer::optimize;
 use rs::parser;
-use rs::utils::{topo_sort_wires, wire_values, Circuit, GateType};
+use rs::utils::{wire_values, Circuit, GateType};
 use std::collections::BTreeMap;
 use std::fs::read_to_string;
 
 fn garble_and_evaluate_prepared(
     circuit: &Circuit,
     ins: &BTreeMap<String, Vec<String>>,
+    outputs: &[String],
     wire_to_keys: &BTreeMap<String, (String, String)>,
     garbled_gates: &BTreeMap<String, GarbledGate>,
 ) {
@@ -44,21 +45,9 @@ fn garble_and_evaluate_prepared(
         bob_input_labels.insert(wire_id.clone(), label.to_string());
     }
 
-    // Assuming the setup for evaluator is similar to the one in your main function
-    let sorted_wires = topo_sort_wires(&circuit);
-
-    let mut output_wires = Vec::new();
-    for wire in sorted_wires.iter() {
-        if !garbled_gates
-            .values()
-            .any(|gate| gate.input_wire_ids.contains(&wire))
-        {
-            output_wires.push(wire.clone());
-        }
-    }
     let mut evaluator = Evaluator::new(
         circuit.clone(),
-        output_wires,
+        outputs.to_vec(),
         wire_to_keys.clone(),
         garbled_gates.clone(),
     );
@@ -72,21 +61,13 @@ fn criterion_benchmark(c: &mut Criterion) {
     let contents = read_to_string(file_path).expect("Couldn't find or load that file.");
 
     // Move circuit creation outside of the benchmark loop
-    let (circuit, ins, outs) = parser::parse_yosys_json(&contents);
+    let (circuit, ins, outs) =
+        parser::parse_yosys_json(&contents).expect("Couldn't parse circuit.");
     let normal_circuit = circuit.clone();
 
-    let out_keys = outs
-        .iter()
-        .map(|(_wire_name, wire_id)| {
-            wire_id
-                .iter()
-                .map(|id| id.to_string())
-                .collect::<Vec<String>>()
-        })
-        .flatten()
-        .collect::<Vec<String>>();
+    let out_keys = outs.values().flatten().cloned().collect::<Vec<String>>();
 
-    let xor_optimized_circuit = optimize(circuit.clone(), out_keys);
+    let xor_optimized_circuit = optimize(circuit.clone(), out_keys.clone());
 
     let number_of_xor_gates = normal_circuit
         .values()
@@ -104,10 +85,10 @@ fn criterion_benchmark(c: &mut Criterion) {
     let delta = Fernet::generate_key();
     let delta2 = Fernet::generate_key();
 
-    let mut garbler = Garbler::new(delta, circuit.clone(), ins.clone(), outs.clone());
+    let garbler = Garbler::new(delta, circuit.clone(), ins.clone(), outs.clone());
     let (wire_to_keys, garbled_gates) = garbler.build();
 
-    let mut xor_garbler = Garbler::new(
+    let xor_garbler = Garbler::new(
         delta2,
         xor_optimized_circuit.clone(),
         ins.clone(),
@@ -119,7 +100,13 @@ fn criterion_benchmark(c: &mut Criterion) {
 
     group.bench_function("normal_circuit", |b| {
         b.iter(|| {
-            garble_and_evaluate_prepared(&normal_circuit, &ins, &wire_to_keys, &garbled_gates)
+            garble_and_evaluate_prepared(
+                &normal_circuit,
+                &ins,
+                &out_keys,
+                &wire_to_keys,
+                &garbled_gates,
+            )
         })
     });
     group.bench_function("xor_optimized_circuit", |b| {
@@ -127,6 +114,7 @@ fn criterion_benchmark(c: &mut Criterion) {
             garble_and_evaluate_prepared(
                 &xor_optimized_circuit,
                 &ins,
+                &out_keys,
                 &xor_wire_to_keys,
                 &xor_garbled_gates,
             )
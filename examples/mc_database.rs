@@ -0,0 +1,106 @@
+//! Regenerates `src/optimizer/mc_database.rs`, the minimum-AND implementation of every
+//! four-input NPN class.
+//!
+//! ```text
+//! cargo run --release --example mc_database > src/optimizer/mc_database.rs
+//! ```
+//!
+//! Classes are found by canonicalizing all 2^16 functions. Each representative is then
+//! matched against every XAG with 0, 1, 2, ... AND gates whose gate inputs are affine
+//! in the variables and earlier gates, so the first match uses the fewest ANDs.
+
+use std::collections::BTreeSet;
+
+use rs::optimizer::{eval_affine, npn_canonical, Affine, TruthTable, XagImpl, VARIABLES};
+
+/// The affine combination of `signals` equal to `table`, if there is one.
+fn affine_output(table: TruthTable, signals: &[TruthTable]) -> Option<Affine> {
+    let constant = (table & 1) as Affine;
+    let affine = (0..4)
+        .filter(|&i| table >> (1 << i) & 1 != table & 1)
+        .fold(constant, |acc, i| acc | 1 << (i + 1));
+    (eval_affine(affine, signals) == table).then_some(affine)
+}
+
+/// Searches for an implementation with exactly `remaining` more AND gates.
+fn search(
+    target: TruthTable,
+    signals: &mut Vec<TruthTable>,
+    ands: &mut Vec<(Affine, Affine)>,
+    remaining: usize,
+) -> Option<XagImpl> {
+    if remaining == 0 {
+        // Every AND gate except the last one may or may not feed the output.
+        let last = signals.len() - 1;
+        let gates = ands.len();
+        for used in 0..1u16 << gates.saturating_sub(1) {
+            let mut nonlinear: Affine = 0;
+            for k in 0..gates.saturating_sub(1) {
+                nonlinear |= (used >> k & 1) << (5 + k);
+            }
+            if gates > 0 {
+                nonlinear |= 1 << last;
+            }
+            let rest = target ^ eval_affine(nonlinear, signals);
+            if let Some(affine) = affine_output(rest, signals) {
+                return Some(XagImpl {
+                    ands: ands.clone(),
+                    output: affine | nonlinear,
+                });
+            }
+        }
+        return None;
+    }
+    // Gate inputs leave out the constant, which can be moved to the output instead.
+    let masks: Vec<Affine> = (1..1 << (signals.len() - 1))
+        .map(|mask| mask << 1)
+        .collect();
+    for (i, &a) in masks.iter().enumerate() {
+        for &b in masks[i + 1..].iter() {
+            let and = eval_affine(a, signals) & eval_affine(b, signals);
+            signals.push(and);
+            ands.push((a, b));
+            let found = search(target, signals, ands, remaining - 1);
+            signals.pop();
+            ands.pop();
+            if found.is_some() {
+                return found;
+            }
+        }
+    }
+    None
+}
+
+fn main() {
+    let classes: BTreeSet<TruthTable> =
+        (0..=u16::MAX).map(|table| npn_canonical(table).0).collect();
+    eprintln!("{} NPN classes", classes.len());
+
+    println!("//! Generated by `cargo run --release --example mc_database`, do not edit.");
+    println!();
+    println!("use crate::optimizer::McEntry;");
+    println!();
+    println!("/// Minimum-AND XAG of each four-input NPN class representative.");
+    println!("#[rustfmt::skip]");
+    println!("pub static MC_DATABASE: &[McEntry] = &[");
+    for &class in classes.iter() {
+        let mut signals = vec![0xFFFF];
+        signals.extend(VARIABLES);
+        let implementation = (0..)
+            .find_map(|gates| search(class, &mut signals, &mut Vec::new(), gates))
+            .unwrap();
+        assert_eq!(implementation.eval(), class);
+        let ands: Vec<String> = implementation
+            .ands
+            .iter()
+            .map(|(a, b)| format!("({:#09b}, {:#09b})", a, b))
+            .collect();
+        println!(
+            "    ({:#06x}, &[{}], {:#010b}),",
+            class,
+            ands.join(", "),
+            implementation.output
+        );
+    }
+    println!("];");
+}
@@ -0,0 +1,91 @@
+//! Synthesizes rewrite rules with `synthesize_rules` and compares them against the
+//! built-in rules on the bundled 64-bit adders.
+//!
+//! ```text
+//! cargo run --release --example synthesize_rules
+//! ```
+//!
+//! Each rule set saturates both circuits in `SaturationMode::Shared` with DAG
+//! extraction, and every result is checked against the original with `check_sat`. The
+//! rules are printed first in the format `parse_rules` reads.
+
+use std::{fs::read_to_string, time::Instant};
+
+use rs::{
+    equivalence::check_sat,
+    optimizer::{
+        format_rules, optimize_with_config, synthesize_rules, Extraction, OptimizerConfig, RuleSet,
+        SaturationMode, SynthesisConfig,
+    },
+    parser::{parse_bristol_fashion, parse_yosys_json},
+    utils::Circuit,
+};
+
+fn nonlinear(circuit: &Circuit) -> usize {
+    circuit.values().filter(|(op, _)| op.is_nonlinear()).count()
+}
+
+fn main() {
+    let start = Instant::now();
+    let synthesized = synthesize_rules(&SynthesisConfig::default());
+    println!(
+        "# Synthesized {} rules in {:.2?}",
+        synthesized.len(),
+        start.elapsed()
+    );
+    println!("{}", format_rules(&synthesized));
+
+    let (bristol, _, bristol_outputs) =
+        parse_bristol_fashion(&read_to_string("circuits/adder64.txt").unwrap()).unwrap();
+    let (yosys, _, yosys_outputs) =
+        parse_yosys_json(&read_to_string("circuits/synth_add64.json").unwrap()).unwrap();
+    let circuits = [
+        ("adder64.txt", bristol, bristol_outputs["out"].clone()),
+        (
+            "synth_add64.json",
+            yosys,
+            yosys_outputs.values().flatten().cloned().collect(),
+        ),
+    ];
+    let rule_sets = [
+        ("built-in", RuleSet::Builtin),
+        ("synthesized", RuleSet::Only(synthesized.clone())),
+        ("both", RuleSet::Extended(synthesized)),
+    ];
+
+    println!(
+        "{:<18} {:<12} {:>10} {:>10} {:>10}  equivalent",
+        "circuit", "rules", "nonlinear", "gates", "time"
+    );
+    for (name, circuit, outputs) in circuits.iter() {
+        println!(
+            "{:<18} {:<12} {:>10} {:>10} {:>10}",
+            name,
+            "original",
+            nonlinear(circuit),
+            circuit.len(),
+            "-"
+        );
+        for (rules_name, rules) in rule_sets.iter() {
+            let config = OptimizerConfig {
+                rules: rules.clone(),
+                mode: SaturationMode::Shared,
+                extraction: Extraction::Dag,
+                ..Default::default()
+            };
+            let start = Instant::now();
+            let (optimized, _) =
+                optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+            let time = start.elapsed();
+            println!(
+                "{:<18} {:<12} {:>10} {:>10} {:>10.2?}  {:?}",
+                name,
+                rules_name,
+                nonlinear(&optimized),
+                optimized.len(),
+                time,
+                check_sat(circuit, &optimized, outputs)
+            );
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+use num_bigint::{BigInt, BigUint, Sign};
+
+/// Order of the bits of a byte on its eight wires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitOrder {
+    /// The least significant bit on the first wire, as Bristol circuits expect.
+    #[default]
+    LsbFirst,
+    MsbFirst,
+}
+
+/// Order of the bytes of an integer on its wires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    /// The least significant byte first.
+    #[default]
+    Little,
+    Big,
+}
+
+/// A value to place on, or read from, a group of wires such as the `a` input of a circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Unsigned(BigUint),
+    /// Two's complement over the wires.
+    Signed(BigInt),
+    /// Byte `k` goes on wires `8k..8k + 8`, and shorter strings are padded with zeros.
+    Bytes(Vec<u8>),
+}
+
+/// Which kind of `Value` to decode wires into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Unsigned,
+    Signed,
+    Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingError {
+    /// A value that needs more bits than there are wires.
+    TooWide { bits: u64, wires: usize },
+    /// A layout of whole bytes on a number of wires that isn't a multiple of eight.
+    PartialByte { wires: usize },
+    /// A wire the values to decode don't include.
+    MissingWire(String),
+}
+
+impl Display for EncodingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodingError::TooWide { bits, wires } => {
+                write!(f, "value needs {} bits but only {} wires", bits, wires)
+            }
+            EncodingError::PartialByte { wires } => {
+                write!(f, "{} wires do not hold whole bytes", wires)
+            }
+            EncodingError::MissingWire(wire) => write!(f, "no value for wire {}", wire),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// How values are laid out on wires.
+///
+/// An integer is written in `wires.len()` bits, which `endianness` splits into bytes and
+/// `bit_order` orders within each byte. The default puts bit `i` of the value on wire
+/// `i` for any number of wires, like `wire_values`; every other layout needs whole
+/// bytes. Byte strings keep their own order and only follow `bit_order`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Encoding {
+    pub bit_order: BitOrder,
+    pub endianness: Endianness,
+}
+
+impl Encoding {
+    /// The bit of `value` for every wire of `wires`, in the map form `simulate` and the
+    /// garbler's labels are looked up with.
+    pub fn encode(
+        &self,
+        wires: &[String],
+        value: &Value,
+    ) -> Result<BTreeMap<String, i32>, EncodingError> {
+        let width = wires.len();
+        let bits = match value {
+            Value::Unsigned(value) => {
+                if value.bits() > width as u64 {
+                    return Err(EncodingError::TooWide {
+                        bits: value.bits(),
+                        wires: width,
+                    });
+                }
+                self.place((0..width as u64).map(|i| value.bit(i)).collect())?
+            }
+            Value::Signed(value) => {
+                // A negative value needs one bit more than its magnitude, unless it is
+                // the most negative value of its width
+                let needed = match value.sign() {
+                    Sign::NoSign => 0,
+                    Sign::Plus => value.bits() + 1,
+                    Sign::Minus => (value + 1u32).bits() + 1,
+                };
+                if needed > width as u64 {
+                    return Err(EncodingError::TooWide {
+                        bits: needed,
+                        wires: width,
+                    });
+                }
+                let modulus = BigInt::from(1u32) << width;
+                let unsigned = ((value % &modulus + &modulus) % &modulus)
+                    .to_biguint()
+                    .expect("Reduced values are not negative");
+                self.place((0..width as u64).map(|i| unsigned.bit(i)).collect())?
+            }
+            Value::Bytes(bytes) => {
+                let needed = bytes.len() * 8;
+                if needed > width {
+                    return Err(EncodingError::TooWide {
+                        bits: needed as u64,
+                        wires: width,
+                    });
+                }
+                let mut bits: Vec<bool> = bytes
+                    .iter()
+                    .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+                    .collect();
+                if self.bit_order == BitOrder::MsbFirst {
+                    reverse_bytes(&mut bits, width)?;
+                }
+                bits.resize(width, false);
+                bits
+            }
+        };
+        Ok(wires
+            .iter()
+            .zip(bits)
+            .map(|(wire, bit)| (wire.clone(), bit as i32))
+            .collect())
+    }
+
+    /// Reads a value of `kind` back from the bits of `wires` in `values`, e.g. the
+    /// outputs returned by `Evaluator::run`.
+    pub fn decode(
+        &self,
+        wires: &[String],
+        values: &BTreeMap<String, i32>,
+        kind: ValueKind,
+    ) -> Result<Value, EncodingError> {
+        let mut bits: Vec<bool> = wires
+            .iter()
+            .map(|wire| {
+                values
+                    .get(wire)
+                    .map(|bit| bit & 1 == 1)
+                    .ok_or_else(|| EncodingError::MissingWire(wire.clone()))
+            })
+            .collect::<Result<_, _>>()?;
+        if kind == ValueKind::Bytes {
+            if self.bit_order == BitOrder::MsbFirst {
+                reverse_bytes(&mut bits, wires.len())?;
+            }
+            return Ok(Value::Bytes(
+                bits.chunks(8)
+                    .map(|byte| {
+                        byte.iter()
+                            .enumerate()
+                            .fold(0u8, |acc, (i, &bit)| acc | (bit as u8) << i)
+                    })
+                    .collect(),
+            ));
+        }
+
+        // Placing is its own inverse
+        let bits = self.place(bits)?;
+        let mut unsigned = BigUint::default();
+        for (i, &bit) in bits.iter().enumerate() {
+            unsigned.set_bit(i as u64, bit);
+        }
+        Ok(match kind {
+            ValueKind::Unsigned => Value::Unsigned(unsigned),
+            _ if bits.last() == Some(&true) => {
+                Value::Signed(BigInt::from(unsigned) - (BigInt::from(1u32) << bits.len()))
+            }
+            _ => Value::Signed(BigInt::from(unsigned)),
+        })
+    }
+
+    /// Moves the bits of an integer, least significant first, to their wires.
+    fn place(&self, mut bits: Vec<bool>) -> Result<Vec<bool>, EncodingError> {
+        let width = bits.len();
+        if self.endianness == Endianness::Big {
+            if !width.is_multiple_of(8) {
+                return Err(EncodingError::PartialByte { wires: width });
+            }
+            let bytes: Vec<&[bool]> = bits.chunks(8).rev().collect();
+            bits = bytes.concat();
+        }
+        if self.bit_order == BitOrder::MsbFirst {
+            reverse_bytes(&mut bits, width)?;
+        }
+        Ok(bits)
+    }
+}
+
+/// Reverses the bits within every byte of `bits`, which are laid out on `wires` wires.
+fn reverse_bytes(bits: &mut [bool], wires: usize) -> Result<(), EncodingError> {
+    if !wires.is_multiple_of(8) {
+        return Err(EncodingError::PartialByte { wires });
+    }
+    bits.chunks_mut(8).for_each(|byte| byte.reverse());
+    Ok(())
+}
+
+impl Value {
+    /// The integer, if this is an `Unsigned` value.
+    pub fn into_unsigned(self) -> Option<BigUint> {
+        match self {
+            Value::Unsigned(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The integer, if this is a `Signed` value.
+    pub fn into_signed(self) -> Option<BigInt> {
+        match self {
+            Value::Signed(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The bytes, if this is a `Bytes` value.
+    pub fn into_bytes(self) -> Option<Vec<u8>> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::Unsigned(value.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Signed(value.into())
+    }
+}
+
+impl From<BigUint> for Value {
+    fn from(value: BigUint) -> Self {
+        Value::Unsigned(value)
+    }
+}
+
+impl From<BigInt> for Value {
+    fn from(value: BigInt) -> Self {
+        Value::Signed(value)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(bytes: &[u8]) -> Self {
+        Value::Bytes(bytes.to_vec())
+    }
+}
+
+/// The UTF-8 bytes of the string.
+impl From<&str> for Value {
+    fn from(string: &str) -> Self {
+        Value::Bytes(string.as_bytes().to_vec())
+    }
+}
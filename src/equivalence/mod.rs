@@ -0,0 +1,273 @@
+mod sat;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter},
+};
+
+pub use self::sat::*;
+
+use crate::{
+    ir::IndexedCircuit,
+    simulator::{enumeration_batches, enumeration_mask, enumeration_word, simulate_indexed},
+    utils::{Circuit, GateType},
+};
+
+/// Largest number of primary inputs `check_exhaustive` will enumerate.
+pub const EXHAUSTIVE_INPUT_LIMIT: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    Equivalent,
+    /// An input assignment on which at least one output differs.
+    Counterexample(BTreeMap<String, i32>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EquivalenceError {
+    TooManyInputs { found: usize, limit: usize },
+    MissingOutput(String),
+}
+
+impl Display for EquivalenceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EquivalenceError::TooManyInputs { found, limit } => write!(
+                f,
+                "{} inputs is too many for exhaustive checking (limit {})",
+                found, limit
+            ),
+            EquivalenceError::MissingOutput(wire) => {
+                write!(f, "output {} is missing from one of the circuits", wire)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EquivalenceError {}
+
+/// The two circuits of a check, indexed, together with the union of their inputs.
+struct Pair {
+    circuits: [IndexedCircuit; 2],
+    inputs: Vec<String>,
+    /// Wire ids of every output in each circuit.
+    outputs: [Vec<usize>; 2],
+}
+
+impl Pair {
+    fn new(
+        original: &Circuit,
+        optimized: &Circuit,
+        outputs: &[String],
+    ) -> Result<Self, EquivalenceError> {
+        let circuits = [
+            IndexedCircuit::from_circuit(original),
+            IndexedCircuit::from_circuit(optimized),
+        ];
+        let mut inputs: BTreeSet<String> = BTreeSet::new();
+        for circuit in circuits.iter() {
+            for &wire in circuit.inputs.iter() {
+                inputs.insert(circuit.name(wire));
+            }
+        }
+
+        let lookup = |circuit: &IndexedCircuit| -> Result<Vec<usize>, EquivalenceError> {
+            let table = circuit.name_table().unwrap();
+            outputs
+                .iter()
+                .map(|wire| {
+                    table
+                        .get(wire.as_str())
+                        .map(|id| id.index())
+                        .ok_or_else(|| EquivalenceError::MissingOutput(wire.clone()))
+                })
+                .collect()
+        };
+        let outputs = [lookup(&circuits[0])?, lookup(&circuits[1])?];
+
+        Ok(Self {
+            circuits,
+            inputs: inputs.into_iter().collect(),
+            outputs,
+        })
+    }
+
+    /// Position of every input of `circuit` within `self.inputs`.
+    fn input_positions(&self, circuit: &IndexedCircuit) -> Vec<usize> {
+        circuit
+            .inputs
+            .iter()
+            .map(|&wire| {
+                let name = circuit.name(wire);
+                self.inputs.binary_search(&name).unwrap()
+            })
+            .collect()
+    }
+
+    fn assignment(&self, bit: impl Fn(usize) -> bool) -> BTreeMap<String, i32> {
+        self.inputs
+            .iter()
+            .enumerate()
+            .map(|(i, wire)| (wire.clone(), bit(i) as i32))
+            .collect()
+    }
+}
+
+/// Checks equivalence of `outputs` by simulating every input assignment, 64 at a time.
+pub fn check_exhaustive(
+    original: &Circuit,
+    optimized: &Circuit,
+    outputs: &[String],
+) -> Result<Equivalence, EquivalenceError> {
+    let pair = Pair::new(original, optimized, outputs)?;
+    let n = pair.inputs.len();
+    if n > EXHAUSTIVE_INPUT_LIMIT {
+        return Err(EquivalenceError::TooManyInputs {
+            found: n,
+            limit: EXHAUSTIVE_INPUT_LIMIT,
+        });
+    }
+
+    let positions = [
+        pair.input_positions(&pair.circuits[0]),
+        pair.input_positions(&pair.circuits[1]),
+    ];
+    let lane_mask = enumeration_mask(n);
+
+    for batch in 0..enumeration_batches(n) {
+        let word = |input: usize| enumeration_word(input, batch);
+
+        let values: Vec<Vec<u64>> = (0..2)
+            .map(|side| {
+                let words: Vec<u64> = positions[side].iter().map(|&p| word(p)).collect();
+                simulate_indexed(&pair.circuits[side], &words)
+            })
+            .collect();
+
+        let diff = pair.outputs[0]
+            .iter()
+            .zip(pair.outputs[1].iter())
+            .fold(0u64, |acc, (&a, &b)| acc | (values[0][a] ^ values[1][b]))
+            & lane_mask;
+        if diff != 0 {
+            let lane = diff.trailing_zeros() as usize;
+            let index = (batch << 6) | lane;
+            return Ok(Equivalence::Counterexample(
+                pair.assignment(|input| (index >> input) & 1 == 1),
+            ));
+        }
+    }
+    Ok(Equivalence::Equivalent)
+}
+
+/// Tseitin-encodes a gate, returning the literal of its output.
+pub fn encode_gate(solver: &mut Solver, op: GateType, inputs: &[Lit]) -> Lit {
+    fn and(solver: &mut Solver, a: Lit, b: Lit) -> Lit {
+        let c = solver.new_lit();
+        solver.add_clause(&[!c, a]);
+        solver.add_clause(&[!c, b]);
+        solver.add_clause(&[c, !a, !b]);
+        c
+    }
+    fn xor(solver: &mut Solver, a: Lit, b: Lit) -> Lit {
+        let c = solver.new_lit();
+        solver.add_clause(&[!c, a, b]);
+        solver.add_clause(&[!c, !a, !b]);
+        solver.add_clause(&[c, !a, b]);
+        solver.add_clause(&[c, a, !b]);
+        c
+    }
+
+    if let GateType::TRUE | GateType::FALSE = op {
+        let one = solver.new_lit();
+        solver.add_clause(&[one]);
+        return if op == GateType::TRUE { one } else { !one };
+    }
+    let a = inputs[0];
+    match op {
+        GateType::AND => and(solver, a, inputs[1]),
+        GateType::NAND => !and(solver, a, inputs[1]),
+        GateType::OR => !and(solver, !a, !inputs[1]),
+        GateType::NOR => and(solver, !a, !inputs[1]),
+        GateType::ANDNOT => and(solver, a, !inputs[1]),
+        GateType::ORNOT => !and(solver, !a, inputs[1]),
+        GateType::XOR => xor(solver, a, inputs[1]),
+        GateType::XNOR => !xor(solver, a, inputs[1]),
+        GateType::MUX => {
+            // b ^ (s & (a ^ b))
+            let flip = xor(solver, inputs[1], inputs[2]);
+            let taken = and(solver, a, flip);
+            xor(solver, inputs[2], taken)
+        }
+        GateType::MAJ => {
+            // a ^ ((a ^ b) & (a ^ c))
+            let ab = xor(solver, a, inputs[1]);
+            let ac = xor(solver, a, inputs[2]);
+            let both = and(solver, ab, ac);
+            xor(solver, a, both)
+        }
+        GateType::NOT => !a,
+        GateType::CONST | GateType::INPUT => a,
+        GateType::TRUE | GateType::FALSE => unreachable!("Constants are encoded above"),
+    }
+}
+
+/// Encodes every gate of `circuit`, given one literal per input wire, and returns one
+/// literal per wire.
+pub fn encode_circuit(solver: &mut Solver, circuit: &IndexedCircuit, inputs: &[Lit]) -> Vec<Lit> {
+    let mut lits: Vec<Option<Lit>> = vec![None; circuit.num_wires()];
+    for (wire, lit) in circuit.inputs.iter().zip(inputs.iter()) {
+        lits[wire.index()] = Some(*lit);
+    }
+    for gate in circuit.gates.iter() {
+        let operands: Vec<Lit> = gate
+            .inputs
+            .iter()
+            .map(|input| lits[input.index()].unwrap())
+            .collect();
+        lits[gate.output.index()] = Some(encode_gate(solver, gate.op, &operands));
+    }
+    lits.into_iter().map(Option::unwrap).collect()
+}
+
+/// Checks equivalence of `outputs` by building a miter of both circuits and proving it
+/// unsatisfiable with the built-in SAT solver.
+pub fn check_sat(
+    original: &Circuit,
+    optimized: &Circuit,
+    outputs: &[String],
+) -> Result<Equivalence, EquivalenceError> {
+    let pair = Pair::new(original, optimized, outputs)?;
+    let mut solver = Solver::new();
+    let input_lits: Vec<Lit> = pair.inputs.iter().map(|_| solver.new_lit()).collect();
+
+    let mut wire_lits: Vec<Vec<Lit>> = Vec::with_capacity(2);
+    for circuit in pair.circuits.iter() {
+        let lits: Vec<Lit> = pair
+            .input_positions(circuit)
+            .iter()
+            .map(|&p| input_lits[p])
+            .collect();
+        wire_lits.push(encode_circuit(&mut solver, circuit, &lits));
+    }
+
+    let differences: Vec<Lit> = pair.outputs[0]
+        .iter()
+        .zip(pair.outputs[1].iter())
+        .map(|(&a, &b)| {
+            encode_gate(
+                &mut solver,
+                GateType::XOR,
+                &[wire_lits[0][a], wire_lits[1][b]],
+            )
+        })
+        .collect();
+    solver.add_clause(&differences);
+
+    Ok(match solver.solve() {
+        SatResult::Unsat => Equivalence::Equivalent,
+        SatResult::Sat(model) => {
+            Equivalence::Counterexample(pair.assignment(|input| model[input_lits[input].var()]))
+        }
+    })
+}
@@ -0,0 +1,420 @@
+use std::{cmp::Ordering, collections::BinaryHeap, ops::Not};
+
+/// A literal: a variable index together with a polarity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Lit(u32);
+
+impl Lit {
+    pub fn new(var: usize, negated: bool) -> Self {
+        Lit(((var as u32) << 1) | negated as u32)
+    }
+
+    pub fn var(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    pub fn is_negated(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SatResult {
+    /// A satisfying assignment, indexed by variable.
+    Sat(Vec<bool>),
+    Unsat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Activity(f64, usize);
+
+impl Eq for Activity {}
+
+impl PartialOrd for Activity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Activity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(other.1.cmp(&self.1))
+    }
+}
+
+fn value(assigns: &[Option<bool>], lit: Lit) -> Option<bool> {
+    assigns[lit.var()].map(|v| v ^ lit.is_negated())
+}
+
+/// Conflict-driven clause-learning SAT solver with two watched literals, first-UIP
+/// learning, VSIDS branching, phase saving and Luby restarts.
+#[derive(Debug, Default)]
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    /// Clauses watching each literal, indexed by `Lit::index`.
+    watches: Vec<Vec<usize>>,
+    assigns: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    polarity: Vec<bool>,
+    activity: Vec<f64>,
+    seen: Vec<bool>,
+    order: BinaryHeap<Activity>,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    qhead: usize,
+    var_inc: f64,
+    unsat: bool,
+    conflicts: usize,
+}
+
+const VAR_DECAY: f64 = 0.95;
+const RESTART_UNIT: usize = 100;
+
+fn luby(mut i: usize) -> usize {
+    // Finds the i-th element (0-based) of the Luby sequence 1, 1, 2, 1, 1, 2, 4, ...
+    let (mut size, mut seq) = (1usize, 0u32);
+    while size < i + 1 {
+        seq += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != i {
+        size = (size - 1) >> 1;
+        seq -= 1;
+        i %= size;
+    }
+    1 << seq
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self {
+            var_inc: 1.0,
+            ..Default::default()
+        }
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.assigns.len()
+    }
+
+    /// Number of conflicts encountered by all `solve` calls so far.
+    pub fn conflicts(&self) -> usize {
+        self.conflicts
+    }
+
+    pub fn new_var(&mut self) -> usize {
+        let var = self.assigns.len();
+        self.assigns.push(None);
+        self.level.push(0);
+        self.reason.push(None);
+        self.polarity.push(true);
+        self.activity.push(0.0);
+        self.seen.push(false);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.order.push(Activity(0.0, var));
+        var
+    }
+
+    pub fn new_lit(&mut self) -> Lit {
+        Lit::new(self.new_var(), false)
+    }
+
+    /// Adds a clause. Must be called before `solve` or between `solve` calls.
+    /// Returns `false` if the formula became trivially unsatisfiable.
+    pub fn add_clause(&mut self, lits: &[Lit]) -> bool {
+        if self.unsat {
+            return false;
+        }
+        self.cancel_until(0);
+
+        let mut clause: Vec<Lit> = Vec::with_capacity(lits.len());
+        for &lit in lits {
+            match value(&self.assigns, lit) {
+                Some(true) => return true,
+                Some(false) => continue,
+                None => {}
+            }
+            if clause.contains(&!lit) {
+                return true;
+            }
+            if !clause.contains(&lit) {
+                clause.push(lit);
+            }
+        }
+
+        match clause.len() {
+            0 => {
+                self.unsat = true;
+                false
+            }
+            1 => {
+                self.enqueue(clause[0], None);
+                if self.propagate().is_some() {
+                    self.unsat = true;
+                }
+                !self.unsat
+            }
+            _ => {
+                self.attach(clause);
+                true
+            }
+        }
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0].index()].push(index);
+        self.watches[clause[1].index()].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.assigns[var] = Some(!lit.is_negated());
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn cancel_until(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let start = self.trail_lim[level];
+        for &lit in self.trail[start..].iter().rev() {
+            let var = lit.var();
+            self.polarity[var] = !lit.is_negated();
+            self.assigns[var] = None;
+            self.reason[var] = None;
+            self.order.push(Activity(self.activity[var], var));
+        }
+        self.trail.truncate(start);
+        self.trail_lim.truncate(level);
+        self.qhead = start;
+    }
+
+    /// Unit propagation. Returns the index of a conflicting clause, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.qhead < self.trail.len() {
+            let false_lit = !self.trail[self.qhead];
+            self.qhead += 1;
+
+            let mut watchers = std::mem::take(&mut self.watches[false_lit.index()]);
+            let mut i = 0;
+            while i < watchers.len() {
+                let ci = watchers[i];
+                let clause = &mut self.clauses[ci];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                if value(&self.assigns, clause[0]) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+
+                let replacement =
+                    (2..clause.len()).find(|&k| value(&self.assigns, clause[k]) != Some(false));
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    let new_watch = clause[1];
+                    self.watches[new_watch.index()].push(ci);
+                    watchers.swap_remove(i);
+                    continue;
+                }
+
+                let first = clause[0];
+                if value(&self.assigns, first) == Some(false) {
+                    self.watches[false_lit.index()] = watchers;
+                    self.qhead = self.trail.len();
+                    return Some(ci);
+                }
+                self.enqueue(first, Some(ci));
+                i += 1;
+            }
+            self.watches[false_lit.index()] = watchers;
+        }
+        None
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.var_inc;
+        if self.activity[var] > 1e100 {
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+            self.var_inc *= 1e-100;
+            self.order = self
+                .activity
+                .iter()
+                .enumerate()
+                .filter(|(v, _)| self.assigns[*v].is_none())
+                .map(|(v, a)| Activity(*a, v))
+                .collect();
+        }
+        if self.assigns[var].is_none() {
+            self.order.push(Activity(self.activity[var], var));
+        }
+    }
+
+    /// First-UIP conflict analysis. Returns the learnt clause, asserting literal first,
+    /// and the level to backjump to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+        let mut skip_first = false;
+        let current = self.decision_level();
+
+        let p = loop {
+            let lits = self.clauses[clause].clone();
+            for &q in lits.iter().skip(skip_first as usize) {
+                let var = q.var();
+                if !self.seen[var] && self.level[var] > 0 {
+                    self.bump(var);
+                    self.seen[var] = true;
+                    if self.level[var] >= current {
+                        pending += 1;
+                    } else {
+                        learnt.push(q);
+                    }
+                }
+            }
+
+            loop {
+                index -= 1;
+                if self.seen[self.trail[index].var()] {
+                    break;
+                }
+            }
+            let p = self.trail[index];
+            self.seen[p.var()] = false;
+            pending -= 1;
+            if pending == 0 {
+                break p;
+            }
+            clause = self.reason[p.var()].expect("Implied literal without a reason");
+            skip_first = true;
+        };
+        learnt[0] = !p;
+
+        for lit in learnt.iter().skip(1) {
+            self.seen[lit.var()] = false;
+        }
+
+        let mut backjump = 0;
+        if learnt.len() > 1 {
+            let (max_i, _) = learnt
+                .iter()
+                .enumerate()
+                .skip(1)
+                .max_by_key(|(_, lit)| self.level[lit.var()])
+                .unwrap();
+            learnt.swap(1, max_i);
+            backjump = self.level[learnt[1].var()];
+        }
+        (learnt, backjump)
+    }
+
+    fn pick_branch(&mut self) -> Option<Lit> {
+        while let Some(Activity(_, var)) = self.order.pop() {
+            if self.assigns[var].is_none() {
+                return Some(Lit::new(var, !self.polarity[var]));
+            }
+        }
+        None
+    }
+
+    pub fn solve(&mut self) -> SatResult {
+        self.solve_with_assumptions(&[])
+    }
+
+    /// Solves under temporary unit assumptions, which are dropped again afterwards.
+    pub fn solve_with_assumptions(&mut self, assumptions: &[Lit]) -> SatResult {
+        if self.unsat {
+            return SatResult::Unsat;
+        }
+        self.cancel_until(0);
+        if self.propagate().is_some() {
+            self.unsat = true;
+            return SatResult::Unsat;
+        }
+
+        let mut restarts = 0;
+        let mut budget = luby(restarts) * RESTART_UNIT;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.conflicts += 1;
+                if self.decision_level() == 0 {
+                    self.unsat = true;
+                    return SatResult::Unsat;
+                }
+                let (learnt, backjump) = self.analyze(conflict);
+                self.cancel_until(backjump);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let ci = self.attach(learnt);
+                    self.enqueue(asserting, Some(ci));
+                }
+                self.var_inc /= VAR_DECAY;
+
+                budget = budget.saturating_sub(1);
+                if budget == 0 {
+                    restarts += 1;
+                    budget = luby(restarts) * RESTART_UNIT;
+                    self.cancel_until(0);
+                }
+                continue;
+            }
+
+            // Re-establish any assumption that was undone by a backjump or restart.
+            let mut next = None;
+            while self.decision_level() < assumptions.len() {
+                let lit = assumptions[self.decision_level()];
+                match value(&self.assigns, lit) {
+                    Some(true) => self.trail_lim.push(self.trail.len()),
+                    Some(false) => {
+                        self.cancel_until(0);
+                        return SatResult::Unsat;
+                    }
+                    None => {
+                        next = Some(lit);
+                        break;
+                    }
+                }
+            }
+
+            let decision = match next.or_else(|| self.pick_branch()) {
+                Some(lit) => lit,
+                None => {
+                    let model = self.assigns.iter().map(|v| v.unwrap_or(false)).collect();
+                    self.cancel_until(0);
+                    return SatResult::Sat(model);
+                }
+            };
+            self.trail_lim.push(self.trail.len());
+            self.enqueue(decision, None);
+        }
+    }
+}
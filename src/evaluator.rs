@@ -3,16 +3,15 @@ use std::{collections::BTreeMap, error::Error, str::from_utf8};
 use base64::{engine::general_purpose::URL_SAFE, Engine as _};
 
 use crate::{
-    garbler::GarbledGate,
-    utils::{bytes_xor, decrypt, generate_encryption_key, topo_sort_wires, Circuit, GateType},
+    garbler::{GarbledCircuit, GarbledGate},
+    ir::{IndexedCircuit, WireId},
+    utils::{bytes_xor, decrypt, generate_encryption_key, Circuit, GateType},
 };
 
 pub struct Evaluator {
-    circuit: Circuit,
-    outputs: Vec<String>,
-    wire_to_keys: BTreeMap<String, (String, String)>,
-    gates: BTreeMap<String, GarbledGate>,
-    computed: BTreeMap<String, String>,
+    circuit: IndexedCircuit,
+    outputs: Vec<WireId>,
+    garbled: GarbledCircuit,
 }
 
 impl Evaluator {
@@ -20,91 +19,149 @@ impl Evaluator {
         circuit: Circuit,
         outputs: Vec<String>,
         wire_to_keys: BTreeMap<String, (String, String)>,
-        gates: BTreeMap<String, GarbledGate>,
+        mut gates: BTreeMap<String, GarbledGate>,
+    ) -> Self {
+        let circuit = IndexedCircuit::from_circuit(&circuit);
+        let names = circuit.names.as_ref().unwrap();
+        let keys = names
+            .iter()
+            .map(|wire| {
+                wire_to_keys
+                    .get(wire)
+                    .unwrap_or_else(|| panic!("No keys for wire {}", wire))
+                    .clone()
+            })
+            .collect();
+        let tables = circuit
+            .gates
+            .iter()
+            .map(|gate| {
+                let wire = &names[gate.output.index()];
+                gates
+                    .remove(wire)
+                    .unwrap_or_else(|| panic!("No garbled gate for wire {}", wire))
+                    .table
+            })
+            .collect();
+        let outputs = {
+            let ids = circuit.name_table().unwrap();
+            outputs
+                .iter()
+                .map(|wire| {
+                    *ids.get(wire.as_str())
+                        .unwrap_or_else(|| panic!("Unknown output wire {}", wire))
+                })
+                .collect()
+        };
+        Self::from_garbled(circuit, outputs, GarbledCircuit { keys, tables })
+    }
+
+    /// An evaluator for `garbled`, as `Garbler::garble` returns it for `circuit`.
+    pub fn from_garbled(
+        circuit: IndexedCircuit,
+        outputs: Vec<WireId>,
+        garbled: GarbledCircuit,
     ) -> Self {
         Self {
             circuit,
             outputs,
-            wire_to_keys,
-            gates,
-            computed: BTreeMap::new(),
+            garbled,
         }
     }
 
     pub fn run(&mut self, inputs: Vec<BTreeMap<String, String>>) -> BTreeMap<String, i32> {
-        for party in inputs.iter() {
-            for (wire_id, value) in party.iter() {
-                assert!(
-                    &self.wire_to_keys[wire_id].0 == value
-                        || &self.wire_to_keys[wire_id].1 == value,
-                    "Input value does not match keys"
-                );
-                self.computed.insert(wire_id.to_string(), value.to_string());
-            }
+        let labels: Vec<(WireId, &str)> = {
+            let ids = self.circuit.name_table().unwrap();
+            inputs
+                .iter()
+                .flatten()
+                .filter_map(|(wire, label)| Some((*ids.get(wire.as_str())?, label.as_str())))
+                .collect()
+        };
+        let values = self.evaluate(&labels);
+        self.outputs
+            .iter()
+            .map(|&output| (self.circuit.name(output), values[output.index()] as i32))
+            .collect()
+    }
+
+    /// Evaluates the circuit from the labels of its input wires and decodes the value of
+    /// every wire, indexed by `WireId`.
+    pub fn evaluate(&self, inputs: &[(WireId, &str)]) -> Vec<bool> {
+        let keys = &self.garbled.keys;
+        let mut computed: Vec<Option<String>> = vec![None; self.circuit.num_wires()];
+
+        for &(wire, label) in inputs {
+            assert!(
+                keys[wire.index()].0 == label || keys[wire.index()].1 == label,
+                "Input value does not match keys"
+            );
+            computed[wire.index()] = Some(label.to_string());
         }
 
-        let mut wire_to_value = BTreeMap::new();
-        for wire in topo_sort_wires(&self.circuit) {
-            if let Some(garbled_gate) = self.gates.get(&wire) {
-                let gate_inputs: Vec<String> = garbled_gate
-                    .input_wire_ids
-                    .iter()
-                    .map(|wire_id| self.computed[wire_id].clone())
-                    .collect();
-
-                let result = self
-                    .evaluate_garbled_gate(garbled_gate, gate_inputs)
-                    .unwrap_or_else(|e| panic!("Error: {:?}", e));
-
-                assert!(
-                    garbled_gate.output_keys.0 == result || garbled_gate.output_keys.1 == result,
-                    "Output value does not match the keys"
-                );
-
-                self.computed.insert(wire.clone(), result.clone());
-                wire_to_value.insert(
-                    wire.clone(),
-                    if result == garbled_gate.output_keys.0 {
-                        0
-                    } else {
-                        1
-                    },
-                );
-            }
+        for (gate, table) in self.circuit.gates.iter().zip(&self.garbled.tables) {
+            let gate_inputs: Vec<&str> = gate
+                .inputs
+                .iter()
+                .map(|input| {
+                    computed[input.index()].as_deref().unwrap_or_else(|| {
+                        panic!("No label for wire {}", self.circuit.name(*input))
+                    })
+                })
+                .collect();
+
+            let output_keys = &keys[gate.output.index()];
+            let result = self
+                .evaluate_garbled_gate(gate.op, table, output_keys, &gate_inputs)
+                .unwrap_or_else(|e| panic!("Error: {:?}", e));
+
+            assert!(
+                output_keys.0 == result || output_keys.1 == result,
+                "Output value does not match the keys"
+            );
+            computed[gate.output.index()] = Some(result);
         }
-        self.outputs
+
+        computed
             .iter()
-            .map(|output_wire| (output_wire.to_string(), wire_to_value[output_wire]))
+            .zip(keys)
+            .map(|(label, keys)| label.as_ref().is_some_and(|label| *label == keys.1))
             .collect()
     }
 
     fn evaluate_garbled_gate(
         &self,
-        garbled_gate: &GarbledGate,
-        inputs: Vec<String>,
+        operation: GateType,
+        table: &[String],
+        output_keys: &(String, String),
+        inputs: &[&str],
     ) -> Result<String, Box<dyn Error>> {
-        if garbled_gate.operation == GateType::XOR {
+        match operation {
+            GateType::TRUE => return Ok(output_keys.1.clone()),
+            GateType::FALSE => return Ok(output_keys.0.clone()),
+            _ => {}
+        }
+        if let GateType::NOT | GateType::CONST = operation {
+            return Ok(inputs[0].to_string());
+        }
+        if let GateType::XOR | GateType::XNOR = operation {
             let result_bytes = bytes_xor(
-                &URL_SAFE.decode(&inputs[0]).unwrap(),
-                &URL_SAFE.decode(&inputs[1]).unwrap(),
+                &URL_SAFE.decode(inputs[0]).unwrap(),
+                &URL_SAFE.decode(inputs[1]).unwrap(),
             );
             return Ok(URL_SAFE.encode(&result_bytes));
         }
 
-        for garbled_output in &garbled_gate.table {
-            if let Some(decrypted_output) = self.try_decrypt(&inputs, garbled_output) {
+        for garbled_output in table {
+            if let Some(decrypted_output) = self.try_decrypt(inputs, garbled_output) {
                 return Ok(decrypted_output);
             }
         }
 
-        Err(format!(
-            "No match found for output labels, {:?}",
-            garbled_gate.output_keys
-        )
-        .into())
+        Err(format!("No match found for output labels, {:?}", output_keys).into())
     }
 
-    fn try_decrypt(&self, inputs: &[String], garbled_output: &str) -> Option<String> {
+    fn try_decrypt(&self, inputs: &[&str], garbled_output: &str) -> Option<String> {
         let key = generate_encryption_key(&inputs.iter().map(|i| i.as_bytes()).collect::<Vec<_>>());
         decrypt(URL_SAFE.encode(&key), garbled_output.to_string())
             .ok()
@@ -3,9 +3,8 @@ use std::option::Option;
 
 use base64::{engine::general_purpose::URL_SAFE, Engine as _};
 
-use crate::utils::{
-    bytes_xor, encrypt, generate_encryption_key, generate_keys, topo_sort_wires, Circuit, GateType,
-};
+use crate::ir::{IndexedCircuit, WireId};
+use crate::utils::{bytes_xor, encrypt, generate_encryption_key, generate_keys, Circuit, GateType};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 
@@ -17,182 +16,208 @@ pub struct GarbledGate {
     pub output_keys: (String, String),
 }
 
+/// Garbling schemes whose gate costs the optimizer knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GarblingScheme {
+    /// Yao's scheme with four-row tables, two rows for unary gates and eight for MUX and
+    /// MAJ. With `free_xor`, XOR and XNOR gates are computed locally from a global offset,
+    /// NOT and buffers swap or copy labels, and none of them needs a table.
+    Classic { free_xor: bool },
+    /// Garbled row reduction: the first row of every table is implicit, leaving three.
+    Grr3,
+    /// Half-gates (Zahur, Rosulek and Evans): two ciphertexts per AND-like gate.
+    HalfGates,
+    /// Three-halves (Rosulek and Roy): 1.5 ciphertexts per AND-like gate.
+    ThreeHalves,
+}
+
+/// A garbled circuit, indexed like the `IndexedCircuit` it was garbled from.
+#[derive(Debug, Clone)]
+pub struct GarbledCircuit {
+    /// Labels for 0 and 1 of every wire, indexed by `WireId`.
+    pub keys: Vec<(String, String)>,
+    /// Table of every gate in the order of `IndexedCircuit::gates`, empty for gates
+    /// evaluated without one.
+    pub tables: Vec<Vec<String>>,
+}
+
 #[derive(Debug)]
 pub struct Garbler {
     delta: String,
-    circuit: Circuit,
-    wire_to_keys: BTreeMap<String, (String, String)>,
-    garbled_gates: BTreeMap<String, GarbledGate>,
+    circuit: IndexedCircuit,
+    /// Labels chosen up front for the input and output wires, indexed by `WireId`.
+    keys: Vec<Option<(String, String)>>,
 }
 
 impl Garbler {
+    /// The scheme `build` implements: a full table for every gate except XOR, XNOR, NOT
+    /// and buffers.
+    pub const SCHEME: GarblingScheme = GarblingScheme::Classic { free_xor: true };
+
     pub fn new(
         delta: String,
         circuit: Circuit,
         ins: BTreeMap<String, Vec<String>>,
         outs: BTreeMap<String, Vec<String>>,
     ) -> Self {
-        let mut wire_to_keys: BTreeMap<String, (String, String)> = BTreeMap::new();
-
-        for (_wire_name, wire_id) in ins.iter() {
-            for i in wire_id {
-                wire_to_keys.insert(i.to_string(), generate_keys(&delta));
-            }
-        }
-
-        for (_wire_name, wire_id) in outs.iter() {
-            for i in wire_id {
-                wire_to_keys.insert(i.to_string(), generate_keys(&delta));
+        let circuit = IndexedCircuit::from_circuit(&circuit);
+        let mut keys: Vec<Option<(String, String)>> = vec![None; circuit.num_wires()];
+        {
+            let ids = circuit
+                .name_table()
+                .expect("Lowered circuits keep their names");
+            for wire in ins.values().chain(outs.values()).flatten() {
+                if let Some(id) = ids.get(wire.as_str()) {
+                    keys[id.index()] = Some(generate_keys(&delta));
+                }
             }
         }
 
         Self {
             delta,
             circuit,
-            wire_to_keys,
-            garbled_gates: BTreeMap::new(),
+            keys,
         }
     }
 
-    pub fn build(
-        &mut self,
-    ) -> (
-        BTreeMap<String, (String, String)>,
-        BTreeMap<String, GarbledGate>,
-    ) {
-        let sorted_wires = topo_sort_wires(&self.circuit);
-        for wire in sorted_wires {
-            let (gate_type, gate_inputs) = self.circuit.get(&wire).unwrap();
-            if gate_type == &GateType::INPUT {
-                continue;
-            }
-
-            let gate_wire_to_keys_cloned: BTreeMap<String, (String, String)> = gate_inputs
-                .iter()
-                .map(|input_wire| {
-                    let key_pair = self.wire_to_keys.get(input_wire).unwrap().clone();
-                    (input_wire.clone(), key_pair)
-                })
-                .collect();
+    /// The circuit being garbled, whose wire ids `garble` indexes its result by.
+    pub fn circuit(&self) -> &IndexedCircuit {
+        &self.circuit
+    }
 
-            let gate_wire_to_keys: BTreeMap<&String, &(String, String)> = gate_wire_to_keys_cloned
+    /// Garbles every gate in topological order, without looking up any wire by name.
+    pub fn garble(&self) -> GarbledCircuit {
+        let mut keys = self.keys.clone();
+        let mut tables = Vec::with_capacity(self.circuit.gates.len());
+        for gate in self.circuit.gates.iter() {
+            let output = keys[gate.output.index()].take();
+            let in_keys: Vec<&(String, String)> = gate
+                .inputs
                 .iter()
-                .map(|(k, v)| (k, v))
+                .map(|input| keys[input.index()].as_ref().expect("Inputs come first"))
                 .collect();
-
-            let output_wire_keys = self.wire_to_keys.get(&wire).cloned();
-            let gg = self.garble_gate(
-                *gate_type,
-                gate_wire_to_keys,
-                output_wire_keys,
-                gate_inputs.to_vec(),
-            );
-
-            self.wire_to_keys
-                .insert(wire.clone(), gg.output_keys.clone());
-            self.garbled_gates.insert(wire, gg);
+            let (table, output_keys) = self.garble_gate(gate.op, &in_keys, output);
+            keys[gate.output.index()] = Some(output_keys);
+            tables.push(table);
+        }
+        GarbledCircuit {
+            keys: keys
+                .into_iter()
+                .map(|keys| keys.expect("Every wire is an input or a gate"))
+                .collect(),
+            tables,
         }
-        (self.wire_to_keys.clone(), self.garbled_gates.clone())
     }
 
-    fn switch_gate(&self, gate_type: GateType, a_val: bool, b_val: bool) -> bool {
-        match gate_type {
-            GateType::AND => a_val & b_val,
-            GateType::OR => a_val | b_val,
-            GateType::NOR => !(a_val | b_val),
-            GateType::ORNOT => a_val | !b_val,
-            GateType::NAND => !(a_val & b_val),
-            GateType::ANDNOT => a_val & !b_val,
-            GateType::XNOR => a_val == b_val,
-            GateType::XOR => a_val ^ b_val,
-            GateType::NOT => !a_val,
-            GateType::CONST => a_val,
-            GateType::INPUT => a_val,
-        }
+    /// `garble`, with the labels and gates keyed by wire name.
+    pub fn build(
+        &self,
+    ) -> (
+        BTreeMap<String, (String, String)>,
+        BTreeMap<String, GarbledGate>,
+    ) {
+        let GarbledCircuit { keys, tables } = self.garble();
+        let garbled_gates = self
+            .circuit
+            .gates
+            .iter()
+            .zip(tables)
+            .map(|(gate, table)| {
+                let garbled = GarbledGate {
+                    operation: gate.op,
+                    table,
+                    input_wire_ids: gate
+                        .inputs
+                        .iter()
+                        .map(|&input| self.circuit.name(input))
+                        .collect(),
+                    output_keys: keys[gate.output.index()].clone(),
+                };
+                (self.circuit.name(gate.output), garbled)
+            })
+            .collect();
+        let wire_to_keys = keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, keys)| (self.circuit.name(WireId(i as u32)), keys))
+            .collect();
+        (wire_to_keys, garbled_gates)
     }
 
+    /// The table of a gate reading `in_keys` and its output labels, which are `output`
+    /// if given and the gate needs a table.
     pub fn garble_gate(
-        &mut self,
+        &self,
         gate_op: GateType,
-        gate_wire_to_keys: BTreeMap<&String, &(String, String)>,
+        in_keys: &[&(String, String)],
         output: Option<(String, String)>,
-        gate_input_names: Vec<String>,
-    ) -> GarbledGate {
+    ) -> (Vec<String>, (String, String)) {
+        if let GateType::TRUE | GateType::FALSE = gate_op {
+            // The value of a constant is public, so it needs no table
+            return (vec![], output.unwrap_or_else(|| generate_keys(&self.delta)));
+        }
         assert!(
-            (gate_op == GateType::NOT || gate_op == GateType::CONST)
-                && gate_wire_to_keys.len() == 1
-                || gate_wire_to_keys.len() == 2,
+            in_keys.len() == gate_op.arity(),
             "Invalid number of inputs for gate type"
         );
 
-        let (in_keys_a, in_keys_b) = (
-            &gate_wire_to_keys[&gate_input_names[0]],
-            if gate_input_names.len() > 1 {
-                gate_wire_to_keys.get(&gate_input_names[1])
-            } else {
-                None
-            },
-        );
-
-        if gate_op == GateType::XOR {
-            let safe_in_keys_a = URL_SAFE.decode(&in_keys_a.0).unwrap();
-            let safe_in_keys_b = URL_SAFE.decode(&in_keys_b.unwrap().0).unwrap();
-            let output_0_bytes = bytes_xor(&safe_in_keys_a, &&safe_in_keys_b);
-
-            let safe_delta = URL_SAFE.decode(&self.delta).unwrap();
-            let output_1_bytes = bytes_xor(&output_0_bytes, &safe_delta);
-
-            let base64_output_0 = URL_SAFE.encode(&output_0_bytes);
-            let base64_output_1 = URL_SAFE.encode(&output_1_bytes);
-
-            return GarbledGate {
-                operation: gate_op,
-                table: vec![], // free xor gate, no table
-                input_wire_ids: gate_input_names,
-                output_keys: (base64_output_0, base64_output_1),
-            };
+        // Inversions and buffers swap or copy the labels of their input, and XNOR is an
+        // XOR with its labels swapped, so under free-XOR none of them needs a table
+        match gate_op {
+            GateType::CONST => return (vec![], in_keys[0].clone()),
+            GateType::NOT => return (vec![], (in_keys[0].1.clone(), in_keys[0].0.clone())),
+            GateType::XOR | GateType::XNOR => {
+                let safe_in_keys_a = URL_SAFE.decode(&in_keys[0].0).unwrap();
+                let safe_in_keys_b = URL_SAFE.decode(&in_keys[1].0).unwrap();
+                let output_0_bytes = bytes_xor(&safe_in_keys_a, &safe_in_keys_b);
+
+                let safe_delta = URL_SAFE.decode(&self.delta).unwrap();
+                let output_1_bytes = bytes_xor(&output_0_bytes, &safe_delta);
+
+                let base64_output_0 = URL_SAFE.encode(&output_0_bytes);
+                let base64_output_1 = URL_SAFE.encode(&output_1_bytes);
+
+                // free xor gate, no table
+                return if gate_op == GateType::XOR {
+                    (vec![], (base64_output_0, base64_output_1))
+                } else {
+                    (vec![], (base64_output_1, base64_output_0))
+                };
+            }
+            _ => {}
         }
 
         let output_labels = output.unwrap_or_else(|| generate_keys(&self.delta));
         let mut garbled_table: Vec<String> = vec![];
 
-        for (a_val, a_key) in [(0, &in_keys_a.0), (1, &in_keys_a.1)] {
-            if let Some(in_keys_b) = in_keys_b {
-                for (b_val, b_key) in [(0, &in_keys_b.0), (1, &in_keys_b.1)] {
-                    let out_val = self.switch_gate(gate_op, a_val == 1, b_val == 1);
-                    let out_bytes_val = if out_val {
-                        &output_labels.1
+        // One row per assignment to the inputs, encrypted under their labels
+        for row in 0..1usize << in_keys.len() {
+            let values: Vec<bool> = (0..in_keys.len()).map(|i| (row >> i) & 1 == 1).collect();
+            let labels: Vec<&[u8]> = in_keys
+                .iter()
+                .zip(values.iter())
+                .map(|(keys, &value)| {
+                    if value {
+                        keys.1.as_bytes()
                     } else {
-                        &output_labels.0
-                    };
-
-                    let key = generate_encryption_key(&[a_key.as_bytes(), b_key.as_bytes()]);
-                    let encoded_key = URL_SAFE.encode(&key);
-
-                    garbled_table.push(encrypt(&encoded_key, out_bytes_val.as_bytes().to_vec()));
-                }
+                        keys.0.as_bytes()
+                    }
+                })
+                .collect();
+            let out_bytes_val = if gate_op.eval(&values) {
+                &output_labels.1
             } else {
-                let out_val = if a_val == 1 { 0 } else { 1 };
-                let out_bytes_val = if out_val == 1 {
-                    &output_labels.1
-                } else {
-                    &output_labels.0
-                };
+                &output_labels.0
+            };
 
-                let key = generate_encryption_key(&[a_key.as_bytes()]);
-                let encoded_key = URL_SAFE.encode(&key);
+            let key = generate_encryption_key(&labels);
+            let encoded_key = URL_SAFE.encode(&key);
 
-                garbled_table.push(encrypt(&encoded_key, out_bytes_val.as_bytes().to_vec()));
-            }
+            garbled_table.push(encrypt(&encoded_key, out_bytes_val.as_bytes().to_vec()));
         }
 
         garbled_table.shuffle(&mut thread_rng());
 
-        GarbledGate {
-            operation: gate_op,
-            table: garbled_table,
-            input_wire_ids: gate_input_names,
-            output_keys: (output_labels.0, output_labels.1),
-        }
+        (garbled_table, output_labels)
     }
 }
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{Circuit, GateType};
+
+/// Index of a wire in an [`IndexedCircuit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct WireId(pub u32);
+
+impl WireId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedGate {
+    pub op: GateType,
+    pub inputs: Vec<WireId>,
+    pub output: WireId,
+}
+
+/// Arena-based circuit representation.
+///
+/// Input wires are numbered first, followed by one wire per gate. Gates are stored in
+/// topological order, so every gate only reads wires with a smaller id than its output
+/// and a single forward pass over `gates` evaluates the whole circuit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedCircuit {
+    pub inputs: Vec<WireId>,
+    pub gates: Vec<IndexedGate>,
+    /// Optional wire names, indexed by `WireId`.
+    pub names: Option<Vec<String>>,
+}
+
+impl IndexedCircuit {
+    pub fn num_wires(&self) -> usize {
+        self.inputs.len() + self.gates.len()
+    }
+
+    pub fn name(&self, wire: WireId) -> String {
+        match &self.names {
+            Some(names) => names[wire.index()].clone(),
+            None => format!("w_{}", wire.0),
+        }
+    }
+
+    /// Maps wire names back to their ids. Only available when the name table is kept.
+    pub fn name_table(&self) -> Option<HashMap<&str, WireId>> {
+        self.names.as_ref().map(|names| {
+            names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (name.as_str(), WireId(i as u32)))
+                .collect()
+        })
+    }
+
+    /// Drops the name table, leaving a purely index-based circuit.
+    pub fn without_names(mut self) -> Self {
+        self.names = None;
+        self
+    }
+
+    /// Largest number of nonlinear gates on any path from an input to a wire.
+    pub fn and_depth(&self) -> usize {
+        let mut depth = vec![0usize; self.num_wires()];
+        for gate in self.gates.iter() {
+            let inputs = gate.inputs.iter().map(|input| depth[input.index()]);
+            depth[gate.output.index()] =
+                inputs.max().unwrap_or(0) + gate.op.is_nonlinear() as usize;
+        }
+        depth.into_iter().max().unwrap_or(0)
+    }
+
+    /// Lowers a string-keyed circuit into the indexed form.
+    ///
+    /// Wires that are read but never defined are treated as inputs. Panics if the
+    /// circuit has a cycle.
+    pub fn from_circuit(circuit: &Circuit) -> Self {
+        let order = topo_order(circuit);
+
+        let mut ids: HashMap<&str, WireId> = HashMap::with_capacity(order.len());
+        let mut names: Vec<String> = Vec::with_capacity(order.len());
+        let mut inputs = Vec::new();
+
+        for &wire in order.iter() {
+            if is_input(circuit, wire) {
+                let id = WireId(names.len() as u32);
+                ids.insert(wire, id);
+                names.push(wire.to_string());
+                inputs.push(id);
+            }
+        }
+
+        let mut gates = Vec::with_capacity(order.len() - inputs.len());
+        for &wire in order.iter() {
+            if is_input(circuit, wire) {
+                continue;
+            }
+            let (op, gate_inputs) = &circuit[wire];
+            let id = WireId(names.len() as u32);
+            gates.push(IndexedGate {
+                op: *op,
+                inputs: gate_inputs
+                    .iter()
+                    .map(|input| ids[input.as_str()])
+                    .collect(),
+                output: id,
+            });
+            ids.insert(wire, id);
+            names.push(wire.to_string());
+        }
+
+        Self {
+            inputs,
+            gates,
+            names: Some(names),
+        }
+    }
+
+    /// Lifts the indexed circuit back into the string-keyed form, using `w_<id>` for
+    /// wires without a name.
+    pub fn to_circuit(&self) -> Circuit {
+        let mut circuit = Circuit::new();
+        for &input in self.inputs.iter() {
+            circuit.insert(self.name(input), (GateType::INPUT, vec![]));
+        }
+        for gate in self.gates.iter() {
+            circuit.insert(
+                self.name(gate.output),
+                (
+                    gate.op,
+                    gate.inputs.iter().map(|&input| self.name(input)).collect(),
+                ),
+            );
+        }
+        circuit
+    }
+}
+
+impl From<&Circuit> for IndexedCircuit {
+    fn from(circuit: &Circuit) -> Self {
+        Self::from_circuit(circuit)
+    }
+}
+
+impl From<&IndexedCircuit> for Circuit {
+    fn from(circuit: &IndexedCircuit) -> Self {
+        circuit.to_circuit()
+    }
+}
+
+fn is_input(circuit: &Circuit, wire: &str) -> bool {
+    match circuit.get(wire) {
+        None => true,
+        Some((gate_type, _)) => *gate_type == GateType::INPUT,
+    }
+}
+
+/// Iterative depth-first topological sort, visiting roots in key order without
+/// recursing on long gate chains.
+fn topo_order(circuit: &Circuit) -> Vec<&str> {
+    // missing = unvisited, 1 = on the stack, 2 = done
+    let mut state: HashMap<&str, u8> = HashMap::with_capacity(circuit.len());
+    let mut order = Vec::with_capacity(circuit.len());
+
+    for root in circuit.keys() {
+        if state.contains_key(root.as_str()) {
+            continue;
+        }
+        let mut stack: Vec<(&str, usize)> = vec![(root.as_str(), 0)];
+        state.insert(root.as_str(), 1);
+        while let Some((wire, next)) = stack.pop() {
+            let inputs = circuit
+                .get(wire)
+                .map(|(_, inputs)| inputs.as_slice())
+                .unwrap_or(&[]);
+            if let Some(input) = inputs.get(next) {
+                stack.push((wire, next + 1));
+                match state.get(input.as_str()) {
+                    None => {
+                        state.insert(input.as_str(), 1);
+                        stack.push((input.as_str(), 0));
+                    }
+                    Some(1) => panic!("Circuit has a cycle"),
+                    Some(_) => {}
+                }
+            } else {
+                state.insert(wire, 2);
+                order.push(wire);
+            }
+        }
+    }
+    order
+}
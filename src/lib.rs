@@ -1,6 +1,10 @@
+pub mod encoding;
+pub mod equivalence;
 pub mod evaluator;
 pub mod garbler;
+pub mod ir;
 pub mod optimizer;
 pub mod ot;
 pub mod parser;
+pub mod simulator;
 pub mod utils;
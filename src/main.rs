@@ -3,35 +3,40 @@ use std::{collections::BTreeMap, fs::read_to_string};
 use base64::{engine::general_purpose::URL_SAFE, Engine as _};
 use fernet::Fernet;
 use rs::{
+    encoding::{Encoding, Value, ValueKind},
     evaluator::Evaluator,
     garbler::{GarbledGate, Garbler},
-    optimizer::optimize,
+    optimizer::{load_rules, optimize_with_config, OptimizerConfig, RuleSet},
     ot::{Receiver, Sender},
     parser::parse_bristol_fashion,
-    utils::{wire_values, GateType},
+    utils::GateType,
 };
 
-fn alice_setup(
-    input: i32,
-    circuit: BTreeMap<String, (GateType, Vec<String>)>,
-    ins: BTreeMap<String, Vec<String>>,
-    outs: BTreeMap<String, Vec<String>>,
-    delta: String,
-) -> (
+type AliceSetup = (
     Sender,
     Vec<String>,
     BTreeMap<String, (String, String)>,
     BTreeMap<String, GarbledGate>,
     BTreeMap<String, String>,
-) {
+);
+
+fn alice_setup(
+    input: &Value,
+    circuit: BTreeMap<String, (GateType, Vec<String>)>,
+    ins: BTreeMap<String, Vec<String>>,
+    outs: BTreeMap<String, Vec<String>>,
+    delta: String,
+) -> AliceSetup {
     let alice = Sender::new();
 
     let alice_input_keys = &ins["a"];
 
-    let alice_input_values = wire_values(alice_input_keys, input);
+    let alice_input_values = Encoding::default()
+        .encode(alice_input_keys, input)
+        .unwrap_or_else(|e| panic!("Couldn't encode Alice's input: {}", e));
     let mut alice_input_labels = BTreeMap::new();
 
-    let mut garbler = Garbler::new(delta, circuit, ins.clone(), outs);
+    let garbler = Garbler::new(delta, circuit, ins.clone(), outs);
 
     let (wire_to_keys, garbled_gates) = garbler.build();
     for (wire_id, value) in alice_input_values.iter() {
@@ -55,29 +60,44 @@ fn alice_setup(
 }
 
 fn main() {
+    env_logger::init();
+
     let delta = Fernet::generate_key();
     let file_path = "./circuits/add64.txt".to_owned();
     let contents = read_to_string(file_path).expect("Couldn't find or load file.");
-    let (circuit, ins, outs) = parse_bristol_fashion(&contents);
-
-    let out_keys = outs
-        .iter()
-        .map(|(_wire_name, wire_id)| {
-            wire_id
-                .iter()
-                .map(|id| id.to_string())
-                .collect::<Vec<String>>()
-        })
-        .flatten()
-        .collect::<Vec<String>>();
-
-    let xor_optimized_circuit = optimize(circuit.clone(), out_keys.clone());
-
-    let alice_input_raw = 999;
-    let bob_input_raw = 77;
+    let (circuit, ins, outs) = parse_bristol_fashion(&contents)
+        .unwrap_or_else(|e| panic!("Couldn't parse circuit: {}", e));
+
+    let out_keys = outs.values().flatten().cloned().collect::<Vec<String>>();
+
+    // `--rules FILE` adds the rules in FILE to the built-in ones, `--rules-only FILE`
+    // replaces them, see `parse_rules` for the format
+    let args: Vec<String> = std::env::args().collect();
+    let rules = match args.get(1..3) {
+        Some([flag, path]) if flag == "--rules" || flag == "--rules-only" => {
+            let rules = load_rules(path).unwrap_or_else(|e| panic!("Couldn't load rules: {}", e));
+            if flag == "--rules" {
+                RuleSet::Extended(rules)
+            } else {
+                RuleSet::Only(rules)
+            }
+        }
+        _ => RuleSet::Builtin,
+    };
+    let config = OptimizerConfig {
+        rules,
+        cache: Some("target/optimizer-cache".into()),
+        ..Default::default()
+    };
+    let (xor_optimized_circuit, _report) =
+        optimize_with_config(circuit.clone(), out_keys.clone(), &config)
+            .expect("Loaded rules are checked for soundness");
+
+    let alice_input_raw = Value::from(999u64);
+    let bob_input_raw = Value::from(77u64);
 
     let (alice, bob_input_keys, wire_to_keys, garbled_gates, alice_input_labels) = alice_setup(
-        alice_input_raw,
+        &alice_input_raw,
         xor_optimized_circuit.clone(),
         ins,
         outs,
@@ -85,7 +105,9 @@ fn main() {
     );
     let mut bob = Receiver::new();
 
-    let bob_input_values = wire_values(&bob_input_keys, bob_input_raw);
+    let bob_input_values = Encoding::default()
+        .encode(&bob_input_keys, &bob_input_raw)
+        .unwrap_or_else(|e| panic!("Couldn't encode Bob's input: {}", e));
     let mut bob_input_labels = BTreeMap::new();
     for (wire_id, value) in bob_input_values.iter() {
         let choice = bob.choose(&alice.s, *value as u8);
@@ -111,10 +133,10 @@ fn main() {
     );
 
     let result = evaluator.run(vec![alice_input_labels, bob_input_labels]);
-    let mut res: i64 = 0;
-    for (i, wire) in out_keys.iter().enumerate() {
-        let wire_value = *result.get(wire).unwrap() as i64;
-        res |= wire_value << i;
-    }
+    let res = Encoding::default()
+        .decode(&out_keys, &result, ValueKind::Unsigned)
+        .expect("The evaluator returns every output")
+        .into_unsigned()
+        .expect("Unsigned values decode as unsigned");
     println!("RESULT: {}", res);
 }
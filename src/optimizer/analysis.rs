@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use egg::{
+    Analysis, ConditionalApplier, DidMerge, EGraph, Id, Language, Pattern, Rewrite, Subst, Var,
+};
+
+use crate::{
+    optimizer::{CircuitLang, Sweeping, TruthTable, VARIABLES},
+    utils::GateType,
+};
+
+/// Most wires a class may read for `CircuitAnalysis` to know its truth table.
+pub const WINDOW: usize = VARIABLES.len();
+
+/// Function of an e-class as a truth table over the wires it reads.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassFunction {
+    /// Wires the function depends on, sorted, variable `i` being `support[i]`. Empty for
+    /// constants.
+    pub support: Vec<String>,
+    pub table: TruthTable,
+}
+
+impl ClassFunction {
+    /// The function `table` computes over `support`, without the wires it ignores.
+    fn new(mut support: Vec<String>, mut table: TruthTable) -> Self {
+        for i in (0..support.len()).rev() {
+            let high = table & VARIABLES[i];
+            if high >> (1 << i) == table & !VARIABLES[i] {
+                let mut reduced = support.clone();
+                reduced.remove(i);
+                table = remap(table, &support, &reduced);
+                support = reduced;
+            }
+        }
+        Self { support, table }
+    }
+
+    fn constant_function(value: bool) -> Self {
+        Self {
+            support: Vec::new(),
+            table: if value { TruthTable::MAX } else { 0 },
+        }
+    }
+
+    /// The value of the function if it reads no wires.
+    pub fn constant(&self) -> Option<bool> {
+        self.support.is_empty().then_some(self.table != 0)
+    }
+}
+
+/// Re-expresses a table over `from` in terms of `to`. Wires of `from` that are missing
+/// from `to` must not matter to the table.
+fn remap(table: TruthTable, from: &[String], to: &[String]) -> TruthTable {
+    let positions: Vec<Option<usize>> = from
+        .iter()
+        .map(|wire| to.iter().position(|other| other == wire))
+        .collect();
+    (0..16).fold(0, |acc, x| {
+        let y = positions
+            .iter()
+            .enumerate()
+            .fold(0, |y, (i, position)| match position {
+                Some(position) => y | (x >> position & 1) << i,
+                None => y,
+            });
+        acc | (table >> y & 1) << x
+    })
+}
+
+/// Tracks the function of every e-class that reads at most `WINDOW` wires, and of every
+/// class that constant inputs decide, and merges classes with the same function.
+///
+/// The tables enumerate every assignment to the support, so two classes with the same
+/// function are proven equal, and merging them is SAT sweeping done by exhaustive
+/// simulation. Classes reading more wires get no function unless they are constant.
+/// Constant classes also get a `true` or `false` node, so extraction can pick it.
+/// With `Sweeping::Off` nothing is tracked and the e-graph behaves as with `()`.
+#[derive(Debug, Default)]
+pub struct CircuitAnalysis {
+    sweeping: Sweeping,
+    /// A class for every function seen so far.
+    functions: HashMap<ClassFunction, Id>,
+}
+
+impl CircuitAnalysis {
+    pub fn new(sweeping: Sweeping) -> Self {
+        Self {
+            sweeping,
+            functions: HashMap::new(),
+        }
+    }
+}
+
+impl Analysis<CircuitLang> for CircuitAnalysis {
+    type Data = Option<ClassFunction>;
+
+    fn make(egraph: &EGraph<CircuitLang, Self>, enode: &CircuitLang) -> Self::Data {
+        if egraph.analysis.sweeping == Sweeping::Off {
+            return None;
+        }
+        let op = match enode {
+            CircuitLang::Wire(wire) => {
+                return Some(ClassFunction {
+                    support: vec![wire.clone()],
+                    table: VARIABLES[0],
+                })
+            }
+            _ => enode.gate_type().expect("Only wires have no gate type"),
+        };
+        let children: Vec<&Option<ClassFunction>> = enode
+            .children()
+            .iter()
+            .map(|child| &egraph[*child].data)
+            .collect();
+        if let Some(children) = children
+            .iter()
+            .map(|child| child.as_ref())
+            .collect::<Option<Vec<&ClassFunction>>>()
+        {
+            let mut support: Vec<String> = children
+                .iter()
+                .flat_map(|child| child.support.iter().cloned())
+                .collect();
+            support.sort();
+            support.dedup();
+            if support.len() <= WINDOW {
+                let tables: Vec<u64> = children
+                    .iter()
+                    .map(|child| remap(child.table, &child.support, &support) as u64)
+                    .collect();
+                return Some(ClassFunction::new(
+                    support,
+                    op.eval_word(&tables) as TruthTable,
+                ));
+            }
+        }
+
+        // Too many wires for a table, but constant inputs may still decide the output
+        constant_output(egraph, enode, op).map(ClassFunction::constant_function)
+    }
+
+    fn merge(&mut self, a: &mut Self::Data, b: Self::Data) -> DidMerge {
+        // Merged classes compute the same function, so only a missing one can change
+        match (a.is_some(), b) {
+            (false, Some(b)) => {
+                *a = Some(b);
+                DidMerge(true, false)
+            }
+            (true, None) => DidMerge(false, true),
+            _ => DidMerge(false, false),
+        }
+    }
+
+    fn modify(egraph: &mut EGraph<CircuitLang, Self>, id: Id) {
+        let Some(function) = egraph[id].data.clone() else {
+            return;
+        };
+        if let Some(value) = function.constant() {
+            let constant = egraph.add(if value {
+                CircuitLang::True
+            } else {
+                CircuitLang::False
+            });
+            egraph.union(id, constant);
+        }
+        match egraph.analysis.functions.get(&function) {
+            Some(&other) => {
+                egraph.union(id, other);
+            }
+            None => {
+                egraph.analysis.functions.insert(function, id);
+            }
+        }
+    }
+}
+
+/// The value of `node` if its constant inputs decide it. Every other input is a
+/// variable of its own, shared by inputs in the same class.
+fn constant_output(
+    egraph: &EGraph<CircuitLang, CircuitAnalysis>,
+    node: &CircuitLang,
+    op: GateType,
+) -> Option<bool> {
+    let mut unknown: Vec<Id> = Vec::new();
+    let mut tables: Vec<u64> = Vec::new();
+    for child in node.children() {
+        let constant = egraph[*child]
+            .data
+            .as_ref()
+            .and_then(ClassFunction::constant);
+        let table = match constant {
+            Some(value) => ClassFunction::constant_function(value).table,
+            None => {
+                let class = egraph.find(*child);
+                if !unknown.contains(&class) {
+                    unknown.push(class);
+                }
+                VARIABLES[unknown.iter().position(|id| *id == class).unwrap()]
+            }
+        };
+        tables.push(table as u64);
+    }
+    match op.eval_word(&tables) as TruthTable {
+        0 => Some(false),
+        TruthTable::MAX => Some(true),
+        _ => None,
+    }
+}
+
+/// Conditional rules that fold a binary gate with an input `CircuitAnalysis` knows to be
+/// constant, so that logic reading a class proven constant simplifies even when the
+/// class holds no `true` or `false` node.
+pub fn constant_rules() -> Vec<Rewrite<CircuitLang, CircuitAnalysis>> {
+    let binary = [
+        GateType::AND,
+        GateType::OR,
+        GateType::XOR,
+        GateType::NAND,
+        GateType::NOR,
+        GateType::XNOR,
+        GateType::ANDNOT,
+        GateType::ORNOT,
+    ];
+    let x = VARIABLES[0] as u64;
+    let mut rules = Vec::new();
+    for op in binary {
+        let name = CircuitLang::gate(op, &[Id::from(0), Id::from(1)])
+            .expect("Binary gates have nodes")
+            .to_string();
+        for position in 0..2 {
+            for value in [false, true] {
+                let constant = ClassFunction::constant_function(value).table as u64;
+                let (inputs, lhs) = match position {
+                    0 => ([constant, x], format!("({} ?c ?x)", name)),
+                    _ => ([x, constant], format!("({} ?x ?c)", name)),
+                };
+                let rhs = match op.eval_word(&inputs) as TruthTable {
+                    0 => "false",
+                    TruthTable::MAX => "true",
+                    table if table == VARIABLES[0] => "?x",
+                    _ => "(not ?x)",
+                };
+                let var: Var = "?c".parse().unwrap();
+                let is_constant = move |egraph: &mut EGraph<CircuitLang, CircuitAnalysis>,
+                                        _: Id,
+                                        subst: &Subst| {
+                    egraph[subst[var]]
+                        .data
+                        .as_ref()
+                        .and_then(ClassFunction::constant)
+                        == Some(value)
+                };
+                rules.push(
+                    Rewrite::new(
+                        format!("{}-{}-{}", name, position, value),
+                        lhs.parse::<Pattern<CircuitLang>>().unwrap(),
+                        ConditionalApplier {
+                            condition: is_constant,
+                            applier: rhs.parse::<Pattern<CircuitLang>>().unwrap(),
+                        },
+                    )
+                    .expect("Constant rules bind every variable they use"),
+                );
+            }
+        }
+    }
+    rules
+}
+
+/// Plain pattern rules for e-graphs with `CircuitAnalysis`. `check_rules` only accepts
+/// pattern rules, so every checked rule carries over.
+pub fn with_analysis(
+    rules: &[Rewrite<CircuitLang, ()>],
+) -> Vec<Rewrite<CircuitLang, CircuitAnalysis>> {
+    rules
+        .iter()
+        .map(|rule| {
+            let (Some(lhs), Some(rhs)) = (
+                rule.searcher.get_pattern_ast(),
+                rule.applier.get_pattern_ast(),
+            ) else {
+                panic!("Rule {} is not a plain pattern rewrite", rule.name);
+            };
+            Rewrite::new(
+                rule.name,
+                Pattern::new(lhs.clone()),
+                Pattern::new(rhs.clone()),
+            )
+            .expect("Rules that were valid stay valid")
+        })
+        .collect()
+}
@@ -0,0 +1,118 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use blake3::Hasher;
+use egg::Rewrite;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    optimizer::{CircuitLang, OptimizationReport, OptimizerConfig},
+    utils::Circuit,
+};
+
+/// Bumped whenever the optimizer changes what it produces for the same key, so that
+/// stale entries are never read back.
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    circuit: Circuit,
+    report: OptimizationReport,
+}
+
+/// BLAKE3 hash, in hex, of everything the result of `optimize_with_config` depends on:
+/// the circuit's gates in wire order, the outputs, every rule's patterns and the
+/// configuration. `config.cache` and `config.threads` don't change the result and are
+/// left out.
+pub fn cache_key(
+    circuit: &Circuit,
+    outputs: &[String],
+    rules: &[Rewrite<CircuitLang, ()>],
+    config: &OptimizerConfig,
+) -> String {
+    let mut hasher = Hasher::new();
+    // Every field is length-prefixed so that adjacent fields can't run into each other
+    let mut field = |bytes: &[u8]| {
+        hasher.update(&(bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    field(&CACHE_VERSION.to_le_bytes());
+    for (wire, (op, inputs)) in circuit.iter() {
+        field(wire.as_bytes());
+        field(op.to_string().as_bytes());
+        for input in inputs.iter() {
+            field(input.as_bytes());
+        }
+    }
+    field(b"outputs");
+    for output in outputs.iter() {
+        field(output.as_bytes());
+    }
+    field(b"rules");
+    for rule in rules.iter() {
+        field(rule.name.as_str().as_bytes());
+        let lhs = rule.searcher.get_pattern_ast();
+        let rhs = rule.applier.get_pattern_ast();
+        field(format!("{:?} => {:?}", lhs, rhs).as_bytes());
+    }
+    field(b"config");
+    let settings = format!(
+        "{} {} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+        config.iter_limit,
+        config.node_limit,
+        config.time_limit,
+        config.scheduler,
+        config.normalization,
+        config.sweeping,
+        config.mode,
+        config.extraction,
+        config.partition,
+        config.keep_best,
+        config.scheme,
+    );
+    field(settings.as_bytes());
+    hasher.finalize().to_hex().to_string()
+}
+
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.json", key))
+}
+
+/// The cached result for `key`, if there is a readable one.
+pub fn load_cached(dir: &Path, key: &str) -> Option<(Circuit, OptimizationReport)> {
+    let path = entry_path(dir, key);
+    let contents = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str::<Entry>(&contents) {
+        Ok(entry) => Some((entry.circuit, entry.report)),
+        Err(e) => {
+            warn!("Ignoring unreadable cache entry {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Stores a result under `key`. The cache only saves time, so failures are logged
+/// rather than returned.
+pub fn store_cached(dir: &Path, key: &str, circuit: &Circuit, report: &OptimizationReport) {
+    let entry = Entry {
+        circuit: circuit.clone(),
+        report: report.clone(),
+    };
+    let path = entry_path(dir, key);
+    // Written to a temporary file first so that readers never see a partial entry
+    let temporary = dir.join(format!("{}.tmp", key));
+    let result = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&temporary, serde_json::to_vec(&entry)?))
+        .and_then(|_| fs::rename(&temporary, &path));
+    match result {
+        Ok(()) => debug!("Cached optimization result at {}", path.display()),
+        Err(e) => warn!(
+            "Couldn't cache optimization result at {}: {}",
+            path.display(),
+            e
+        ),
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ir::IndexedCircuit,
+    utils::{Circuit, GateType},
+};
+
+/// What `cleanup` simplified or removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CleanupStats {
+    /// Gates that depend on at most one wire, replaced by a constant, a buffer or a NOT.
+    pub folded_gates: usize,
+    /// Buffers removed after their readers were pointed at the buffer's source.
+    pub collapsed_buffers: usize,
+    /// Gates that no output depends on.
+    pub dead_gates: usize,
+}
+
+/// Runs `fold_constants`, `collapse_buffers` and `eliminate_dead_gates`, in that order.
+/// One round reaches a fixpoint: folding only leaves behind buffers and unread gates.
+pub fn cleanup(circuit: &mut Circuit, outputs: &[String]) -> CleanupStats {
+    CleanupStats {
+        folded_gates: fold_constants(circuit),
+        collapsed_buffers: collapse_buffers(circuit, outputs),
+        dead_gates: eliminate_dead_gates(circuit, outputs),
+    }
+}
+
+/// Propagates `TRUE` and `FALSE` wires through the circuit. A gate whose value then
+/// depends on at most one wire, like `AND(x, TRUE)` or `XOR(x, x)`, becomes a constant, a
+/// `CONST` buffer or a `NOT` of that wire. Returns the number of gates replaced.
+pub fn fold_constants(circuit: &mut Circuit) -> usize {
+    let indexed = IndexedCircuit::from_circuit(circuit);
+    let mut constants: HashMap<String, bool> = HashMap::new();
+    let mut folded = 0;
+    for gate in indexed.gates.iter() {
+        let wire = indexed.name(gate.output);
+        let (op, inputs) = circuit[&wire].clone();
+        let replacement = match op {
+            GateType::TRUE => Some(true),
+            GateType::FALSE => Some(false),
+            _ => None,
+        };
+        if let Some(value) = replacement {
+            constants.insert(wire, value);
+            continue;
+        }
+
+        let mut free: Vec<&String> = inputs
+            .iter()
+            .filter(|input| !constants.contains_key(*input))
+            .collect();
+        free.dedup();
+        if free.len() > 1 {
+            continue;
+        }
+        let eval = |x: bool| {
+            let bits: Vec<bool> = inputs
+                .iter()
+                .map(|input| constants.get(input).copied().unwrap_or(x))
+                .collect();
+            op.eval(&bits)
+        };
+        let replacement = match (eval(false), eval(true), free.first()) {
+            (false, false, _) => (GateType::FALSE, vec![]),
+            (true, true, _) => (GateType::TRUE, vec![]),
+            (false, true, Some(x)) => (GateType::CONST, vec![x.to_string()]),
+            (true, false, Some(x)) => (GateType::NOT, vec![x.to_string()]),
+            (_, _, None) => unreachable!("A gate without free inputs is constant"),
+        };
+        if replacement.0 == GateType::TRUE || replacement.0 == GateType::FALSE {
+            constants.insert(wire.clone(), replacement.0 == GateType::TRUE);
+        }
+        if circuit[&wire] != replacement {
+            circuit.insert(wire, replacement);
+            folded += 1;
+        }
+    }
+    folded
+}
+
+/// Points every reader of a `CONST` buffer at the end of the buffer chain, and removes
+/// buffers that are not outputs. Output buffers are kept so the output keeps its name.
+/// Returns the number of buffers removed.
+///
+/// Panics if buffers read each other in a cycle, like `IndexedCircuit::from_circuit`.
+pub fn collapse_buffers(circuit: &mut Circuit, outputs: &[String]) -> usize {
+    let source = |wire: &String| {
+        let mut seen: HashSet<&String> = HashSet::new();
+        let mut wire = wire;
+        while let Some((GateType::CONST, inputs)) = circuit.get(wire) {
+            assert!(
+                seen.insert(wire),
+                "Circuit has a cycle of buffers through {}",
+                wire
+            );
+            wire = &inputs[0];
+        }
+        wire.clone()
+    };
+    let sources: HashMap<String, String> = circuit
+        .iter()
+        .filter(|(_, (op, _))| *op == GateType::CONST)
+        .map(|(wire, _)| (wire.clone(), source(wire)))
+        .collect();
+    if sources.is_empty() {
+        return 0;
+    }
+
+    for (_, inputs) in circuit.values_mut() {
+        for input in inputs.iter_mut() {
+            if let Some(source) = sources.get(input) {
+                *input = source.clone();
+            }
+        }
+    }
+
+    let outputs: HashSet<&String> = outputs.iter().collect();
+    let removed: Vec<String> = sources
+        .into_keys()
+        .filter(|wire| !outputs.contains(wire))
+        .collect();
+    for wire in removed.iter() {
+        circuit.remove(wire);
+    }
+    removed.len()
+}
+
+/// Removes every gate that no output depends on. Inputs are part of the circuit's
+/// interface and are kept even when unread. Returns the number of gates removed.
+pub fn eliminate_dead_gates(circuit: &mut Circuit, outputs: &[String]) -> usize {
+    let mut live: HashSet<&String> = HashSet::new();
+    let mut stack: Vec<&String> = outputs.iter().collect();
+    while let Some(wire) = stack.pop() {
+        if live.insert(wire) {
+            if let Some((_, inputs)) = circuit.get(wire) {
+                stack.extend(inputs.iter());
+            }
+        }
+    }
+    let dead: Vec<String> = circuit
+        .iter()
+        .filter(|(wire, (op, _))| *op != GateType::INPUT && !live.contains(wire))
+        .map(|(wire, _)| wire.clone())
+        .collect();
+    for wire in dead.iter() {
+        circuit.remove(wire);
+    }
+    dead.len()
+}
@@ -0,0 +1,266 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use egg::{BackoffScheduler, Rewrite, Runner, SimpleScheduler};
+
+use crate::{
+    garbler::{Garbler, GarblingScheme},
+    optimizer::{circuit_rules, xag_rules, CircuitLang, NoProgress, Progress},
+};
+
+/// Which `egg` rewrite scheduler the runner uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheduler {
+    /// Applies every rule on every iteration.
+    Simple,
+    /// Temporarily bans rules that match too often, see `egg::BackoffScheduler`.
+    Backoff {
+        match_limit: usize,
+        ban_length: usize,
+    },
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        // Same values as `BackoffScheduler::default()`
+        Scheduler::Backoff {
+            match_limit: 1_000,
+            ban_length: 5,
+        }
+    }
+}
+
+/// How outputs are mapped onto e-graphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaturationMode {
+    /// One runner per output, saturated in parallel. Shared logic is optimized once per
+    /// output and only merged again when the circuit is rebuilt.
+    #[default]
+    PerOutput,
+    /// Every output is a root of a single e-graph, so logic shared between outputs is
+    /// represented, rewritten and extracted once. `node_limit` then bounds the whole
+    /// circuit rather than a single output cone.
+    Shared,
+}
+
+/// How the cheapest circuit is read back out of a saturated e-graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Extraction {
+    /// `egg::Extractor` with the tree cost `GarbleCost`, which pays for a shared
+    /// subexpression once per use.
+    #[default]
+    Tree,
+    /// `DagExtractor`, which pays for every unique gate once.
+    Dag,
+    /// The shallowest circuit in nonlinear gates (AND depth) the e-graph holds, and the
+    /// cheapest DAG at that depth.
+    MinDepth,
+    /// The cheapest DAG whose outputs are at most this many nonlinear gates deep. Outputs
+    /// that cannot be made that shallow get their minimum depth.
+    BoundedDepth(usize),
+}
+
+impl Extraction {
+    /// Whether extraction pays for every unique gate once rather than once per use.
+    pub fn is_dag(self) -> bool {
+        !matches!(self, Extraction::Tree)
+    }
+
+    /// The depth bound `DagExtractor::limit_depth` enforces, if any.
+    pub(crate) fn depth_bound(self) -> Option<Option<usize>> {
+        match self {
+            Extraction::Tree | Extraction::Dag => None,
+            Extraction::MinDepth => Some(None),
+            Extraction::BoundedDepth(bound) => Some(Some(bound)),
+        }
+    }
+}
+
+/// The gate set outputs are rewritten into before saturation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Normalization {
+    /// Saturate the gates as they are.
+    #[default]
+    None,
+    /// Lower every gate to AND, XOR and NOT with `to_xag`, saturate with `xag_rules`,
+    /// and fold inversions back into the other gates with `xag_lift_rules` before
+    /// extraction.
+    ///
+    /// Meant for `SaturationMode::Shared`: with the associativity rules every output
+    /// of `SaturationMode::PerOutput` picks its own grouping of shared terms, and the
+    /// merged circuit no longer shares them.
+    Xag,
+}
+
+/// What the e-graph proves about classes on its own, besides what the rules rewrite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sweeping {
+    /// Classes only merge through rules.
+    #[default]
+    Off,
+    /// Track the truth table of every class reading at most `WINDOW` wires with
+    /// `CircuitAnalysis`, merge classes with the same function, and fold gates with a
+    /// constant input through `constant_rules`.
+    TruthTables,
+}
+
+/// How the circuit is cut into windows that are saturated independently, see
+/// `partition_circuit`. Each window's cut wires become its inputs and outputs, and the
+/// optimized windows are stitched back together by wire name.
+///
+/// Rewrites can't cross a window boundary, so partitioning gives up some optimization
+/// for runtime and memory that grow with the window size rather than the circuit. In
+/// `SaturationMode::PerOutput` an output's cone is otherwise most of the circuit for
+/// something like SHA-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Partition {
+    /// Saturate the whole circuit at once.
+    #[default]
+    None,
+    /// Windows of this many consecutive topological levels, a gate being one level
+    /// deeper than its deepest input.
+    Levels(usize),
+    /// Windows of at most this many gates, taken in topological order.
+    Gates(usize),
+    /// Whole output cones, packed into windows of at most this many gates in output
+    /// order. A cone only claims gates no earlier cone did, and cones larger than the
+    /// budget are split like `Gates`.
+    Cones(usize),
+}
+
+/// What `optimize_with_config` does when the optimized circuit costs more to garble than
+/// the input, as measured by `circuit_cost` under the configured scheme. Renaming and
+/// extraction heuristics can make the rebuilt circuit worse, for example when tree
+/// extraction duplicates logic that the input shared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeepBest {
+    /// Always return the optimized circuit.
+    Off,
+    /// Return whichever of the input and the optimized circuit is cheaper.
+    #[default]
+    Circuit,
+    /// Rebuild every output from whichever circuit has the cheaper cone for it, then
+    /// return the cheapest of that mix, the input and the optimized circuit. Cones are
+    /// compared on their own, so logic an output shares with others counts in full.
+    PerOutput,
+}
+
+/// The rewrite rules to saturate with.
+#[derive(Clone, Default)]
+pub enum RuleSet {
+    /// The hand-written rules for the normalized gate set, `circuit_rules` or
+    /// `xag_rules`.
+    #[default]
+    Builtin,
+    /// Only the given rules.
+    Only(Vec<Rewrite<CircuitLang, ()>>),
+    /// The built-in rules followed by the given ones.
+    Extended(Vec<Rewrite<CircuitLang, ()>>),
+}
+
+impl RuleSet {
+    pub fn rules(&self, normalization: Normalization) -> Vec<Rewrite<CircuitLang, ()>> {
+        let builtin = || match normalization {
+            Normalization::None => circuit_rules(),
+            Normalization::Xag => xag_rules(),
+        };
+        match self {
+            RuleSet::Builtin => builtin(),
+            RuleSet::Only(rules) => rules.clone(),
+            RuleSet::Extended(rules) => {
+                let mut all = builtin();
+                all.extend(rules.iter().cloned());
+                all
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for RuleSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleSet::Builtin => write!(f, "Builtin"),
+            RuleSet::Only(rules) => write!(f, "Only({} rules)", rules.len()),
+            RuleSet::Extended(rules) => write!(f, "Extended({} rules)", rules.len()),
+        }
+    }
+}
+
+/// Settings for `optimize_with_config`. The defaults match `egg::Runner::default()`.
+#[derive(Debug, Clone)]
+pub struct OptimizerConfig {
+    /// Maximum number of saturation iterations per runner.
+    pub iter_limit: usize,
+    /// Maximum number of e-nodes per runner.
+    pub node_limit: usize,
+    /// Wall-clock limit per runner.
+    pub time_limit: Duration,
+    pub scheduler: Scheduler,
+    pub rules: RuleSet,
+    pub normalization: Normalization,
+    pub sweeping: Sweeping,
+    pub mode: SaturationMode,
+    pub extraction: Extraction,
+    pub partition: Partition,
+    pub keep_best: KeepBest,
+    /// Scheme the circuit will be garbled with, which decides the cost model.
+    pub scheme: GarblingScheme,
+    /// Number of threads to saturate outputs on. `None` uses rayon's global pool.
+    pub threads: Option<usize>,
+    /// Directory of cached results, see `cache_key`. A circuit optimized before with the
+    /// same rules and settings is read back from here without saturating it again.
+    pub cache: Option<PathBuf>,
+    /// Told about every phase and output as the optimization goes. Nothing is reported
+    /// by default; `IndicatifProgress`, with the `indicatif` feature, draws progress bars.
+    pub progress: Arc<dyn Progress>,
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        Self {
+            iter_limit: 30,
+            node_limit: 10_000,
+            time_limit: Duration::from_secs(5),
+            scheduler: Scheduler::default(),
+            rules: RuleSet::default(),
+            normalization: Normalization::default(),
+            sweeping: Sweeping::default(),
+            mode: SaturationMode::default(),
+            extraction: Extraction::default(),
+            partition: Partition::default(),
+            keep_best: KeepBest::default(),
+            scheme: Garbler::SCHEME,
+            threads: None,
+            cache: None,
+            progress: Arc::new(NoProgress),
+        }
+    }
+}
+
+impl OptimizerConfig {
+    /// Builds a runner with this configuration's limits and scheduler.
+    pub fn runner<N: egg::Analysis<CircuitLang> + Default>(&self) -> Runner<CircuitLang, N> {
+        self.runner_with(N::default())
+    }
+
+    /// `runner`, with an e-graph that runs `analysis`.
+    pub fn runner_with<N: egg::Analysis<CircuitLang>>(
+        &self,
+        analysis: N,
+    ) -> Runner<CircuitLang, N> {
+        let runner = Runner::<CircuitLang, N>::new(analysis)
+            .with_iter_limit(self.iter_limit)
+            .with_node_limit(self.node_limit)
+            .with_time_limit(self.time_limit);
+        match self.scheduler {
+            Scheduler::Simple => runner.with_scheduler(SimpleScheduler),
+            Scheduler::Backoff {
+                match_limit,
+                ban_length,
+            } => runner.with_scheduler(
+                BackoffScheduler::default()
+                    .with_initial_match_limit(match_limit)
+                    .with_ban_length(ban_length),
+            ),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::{collections::HashSet, fmt::Debug};
+
+use egg::{CostFunction, Id, Language};
+
+use crate::{
+    garbler::GarblingScheme,
+    optimizer::CircuitLang,
+    utils::{Circuit, GateType},
+};
+
+/// Extra cost of every gate on top of its model cost. Small enough that a million gates
+/// still weigh less than a ciphertext, so among equally expensive circuits the one with
+/// fewer (free) gates wins.
+pub const FREE_GATE_COST: f64 = 1e-6;
+
+/// Price of a gate in a garbled circuit, in ciphertexts the garbler has to send.
+pub trait CostModel: Debug + Send + Sync {
+    fn gate_cost(&self, op: GateType) -> f64;
+
+    /// Cost of an e-node as an extractor sees it: its gate cost plus `FREE_GATE_COST`,
+    /// and nothing for a wire.
+    fn node_cost(&self, node: &CircuitLang) -> f64 {
+        node.gate_type()
+            .map_or(0.0, |op| self.gate_cost(op) + FREE_GATE_COST)
+    }
+}
+
+/// Gates that free-XOR evaluates without a table: XOR itself, and NOT, XNOR and buffers,
+/// which are XOR with a constant.
+fn free_under_free_xor(op: GateType) -> bool {
+    matches!(
+        op,
+        GateType::XOR
+            | GateType::XNOR
+            | GateType::NOT
+            | GateType::CONST
+            | GateType::INPUT
+            | GateType::TRUE
+            | GateType::FALSE
+    )
+}
+
+/// Yao's scheme as `Garbler` implements it: eight rows per three-input gate, four per
+/// binary gate and two per unary gate. With `free_xor` the garbler folds inversions into
+/// the offset, so XNOR, NOT and buffers are as free as XOR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassicYao {
+    pub free_xor: bool,
+}
+
+impl CostModel for ClassicYao {
+    fn gate_cost(&self, op: GateType) -> f64 {
+        match op {
+            _ if self.free_xor && free_under_free_xor(op) => 0.0,
+            GateType::INPUT | GateType::TRUE | GateType::FALSE => 0.0,
+            GateType::NOT | GateType::CONST => 2.0,
+            GateType::MUX | GateType::MAJ => 8.0,
+            _ => 4.0,
+        }
+    }
+}
+
+/// Garbled row reduction on top of free-XOR: three rows per non-free gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grr3;
+
+impl CostModel for Grr3 {
+    fn gate_cost(&self, op: GateType) -> f64 {
+        if free_under_free_xor(op) {
+            0.0
+        } else {
+            3.0
+        }
+    }
+}
+
+/// Half-gates: every AND-like gate is two half-gates of one ciphertext each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HalfGates;
+
+impl CostModel for HalfGates {
+    fn gate_cost(&self, op: GateType) -> f64 {
+        if free_under_free_xor(op) {
+            0.0
+        } else {
+            2.0
+        }
+    }
+}
+
+/// Three-halves: 1.5 ciphertexts per AND-like gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThreeHalves;
+
+impl CostModel for ThreeHalves {
+    fn gate_cost(&self, op: GateType) -> f64 {
+        if free_under_free_xor(op) {
+            0.0
+        } else {
+            1.5
+        }
+    }
+}
+
+impl GarblingScheme {
+    /// The cost model matching this scheme.
+    pub fn cost_model(self) -> Box<dyn CostModel> {
+        match self {
+            GarblingScheme::Classic { free_xor } => Box::new(ClassicYao { free_xor }),
+            GarblingScheme::Grr3 => Box::new(Grr3),
+            GarblingScheme::HalfGates => Box::new(HalfGates),
+            GarblingScheme::ThreeHalves => Box::new(ThreeHalves),
+        }
+    }
+}
+
+/// Tree cost for `egg::Extractor`: the model cost of every node, summed over every use.
+pub struct GarbleCost<'a>(pub &'a dyn CostModel);
+
+impl CostFunction<CircuitLang> for GarbleCost<'_> {
+    type Cost = f64;
+    fn cost<C>(&mut self, enode: &CircuitLang, mut costs: C) -> Self::Cost
+    where
+        C: FnMut(Id) -> Self::Cost,
+    {
+        enode.fold(self.0.node_cost(enode), |sum, id| sum + costs(id))
+    }
+}
+
+/// Cost of garbling the gates `outputs` depend on, each counted once, plus
+/// `FREE_GATE_COST` per gate so that fewer gates break ties like they do in extraction.
+pub fn circuit_cost(circuit: &Circuit, outputs: &[String], model: &dyn CostModel) -> f64 {
+    let mut seen: HashSet<&String> = HashSet::new();
+    let mut stack: Vec<&String> = outputs.iter().collect();
+    let mut cost = 0.0;
+    while let Some(wire) = stack.pop() {
+        if !seen.insert(wire) {
+            continue;
+        }
+        if let Some((op, inputs)) = circuit.get(wire) {
+            if *op != GateType::INPUT {
+                cost += model.gate_cost(*op) + FREE_GATE_COST;
+            }
+            stack.extend(inputs.iter());
+        }
+    }
+    cost
+}
@@ -0,0 +1,287 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ir::{IndexedCircuit, WireId},
+    optimizer::{cleanup, Affine, McDatabase, TruthTable, VARIABLES},
+    utils::{Circuit, GateType},
+};
+
+/// Most leaves a cut can have, the number of variables `McDatabase` covers.
+pub const CUT_SIZE: usize = 4;
+/// Cuts kept per wire, cheapest first.
+const CUTS_PER_WIRE: usize = 8;
+
+#[derive(Debug, Clone)]
+struct Cut {
+    /// Sorted, without duplicates.
+    leaves: Vec<WireId>,
+    /// Function of the wire in terms of `leaves`, leaf `i` being variable `i`.
+    table: TruthTable,
+    /// Area flow: ANDs the cut needs plus the leaves' area flow split among their readers.
+    cost: f64,
+}
+
+/// Re-expresses a table over `from` in terms of `to`, which must contain `from`.
+fn expand(table: TruthTable, from: &[WireId], to: &[WireId]) -> TruthTable {
+    let positions: Vec<usize> = from
+        .iter()
+        .map(|leaf| to.iter().position(|wire| wire == leaf).unwrap())
+        .collect();
+    (0..16).fold(0, |acc, x| {
+        let y = positions
+            .iter()
+            .enumerate()
+            .fold(0, |y, (i, &position)| y | (x >> position & 1) << i);
+        acc | (table >> y & 1) << x
+    })
+}
+
+fn merge(cuts: &[&Cut]) -> Option<Vec<WireId>> {
+    let mut leaves: Vec<WireId> = cuts.iter().flat_map(|cut| cut.leaves.clone()).collect();
+    leaves.sort();
+    leaves.dedup();
+    (leaves.len() <= CUT_SIZE).then_some(leaves)
+}
+
+/// Rewrites the circuit with cut enumeration and the minimum-AND database.
+///
+/// Every wire enumerates the cuts of up to four leaves that its inputs' cuts combine
+/// into, and keeps the few with the lowest area flow, where a cut costs the ANDs in the
+/// database's implementation of its function. The outputs are then covered with each
+/// wire's best cut, and every cut in the cover is replaced by its implementation. Logic
+/// shared by several cuts may be duplicated when that saves ANDs overall.
+///
+/// Rounds repeat until one no longer lowers the number of nonlinear gates, since each
+/// round's cuts see past the boundaries of the last one's. The result never has more
+/// nonlinear gates than `circuit`, and once a round succeeds it only contains inputs,
+/// AND, XOR and NOT gates, plus constants and buffers where an output needs them.
+pub fn rewrite_cuts(circuit: &Circuit, outputs: &[String]) -> Circuit {
+    let nonlinear =
+        |circuit: &Circuit| circuit.values().filter(|(op, _)| op.is_nonlinear()).count();
+    let mut best = circuit.clone();
+    loop {
+        let rewritten = rewrite_round(&best, outputs);
+        if nonlinear(&rewritten) >= nonlinear(&best) {
+            return best;
+        }
+        best = rewritten;
+    }
+}
+
+fn rewrite_round(circuit: &Circuit, outputs: &[String]) -> Circuit {
+    let indexed = IndexedCircuit::from_circuit(circuit);
+    let names = indexed.name_table().unwrap();
+    let mut database = McDatabase::new();
+
+    let mut fanout = vec![0usize; indexed.num_wires()];
+    for gate in indexed.gates.iter() {
+        for input in gate.inputs.iter() {
+            fanout[input.index()] += 1;
+        }
+    }
+    for output in outputs.iter() {
+        fanout[names[output.as_str()].index()] += 1;
+    }
+
+    let trivial = |wire: WireId| Cut {
+        leaves: vec![wire],
+        table: VARIABLES[0],
+        cost: 0.0,
+    };
+    let mut cuts: Vec<Vec<Cut>> = vec![vec![]; indexed.num_wires()];
+    let mut best: Vec<Option<Cut>> = vec![None; indexed.num_wires()];
+    let mut flow = vec![0.0; indexed.num_wires()];
+    for &input in indexed.inputs.iter() {
+        cuts[input.index()].push(trivial(input));
+    }
+    for gate in indexed.gates.iter() {
+        // Every choice of one cut per input
+        let combinations: Vec<Vec<&Cut>> =
+            gate.inputs
+                .iter()
+                .fold(vec![vec![]], |combinations, input| {
+                    combinations
+                        .iter()
+                        .flat_map(|combination| {
+                            cuts[input.index()].iter().map(move |cut| {
+                                let mut combination = combination.clone();
+                                combination.push(cut);
+                                combination
+                            })
+                        })
+                        .collect()
+                });
+
+        let mut candidates: Vec<Cut> = Vec::new();
+        for combination in combinations {
+            let Some(leaves) = merge(&combination) else {
+                continue;
+            };
+            if candidates.iter().any(|cut| cut.leaves == leaves) {
+                continue;
+            }
+            let inputs: Vec<u64> = combination
+                .iter()
+                .map(|cut| expand(cut.table, &cut.leaves, &leaves) as u64)
+                .collect();
+            let table = gate.op.eval_word(&inputs) as TruthTable;
+            let cost = database.lookup(table).and_count() as f64
+                + leaves
+                    .iter()
+                    .map(|leaf| flow[leaf.index()] / fanout[leaf.index()] as f64)
+                    .sum::<f64>();
+            candidates.push(Cut {
+                leaves,
+                table,
+                cost,
+            });
+        }
+        candidates.sort_by(|a, b| {
+            a.cost
+                .total_cmp(&b.cost)
+                .then(a.leaves.len().cmp(&b.leaves.len()))
+        });
+        candidates.truncate(CUTS_PER_WIRE);
+
+        let wire = gate.output.index();
+        flow[wire] = candidates[0].cost;
+        best[wire] = Some(candidates[0].clone());
+        // Constants are absorbed into their readers' tables instead of becoming leaves
+        if gate.op.arity() > 0 {
+            candidates.push(trivial(gate.output));
+        }
+        cuts[wire] = candidates;
+    }
+
+    let mut needed = vec![false; indexed.num_wires()];
+    let mut stack: Vec<WireId> = outputs
+        .iter()
+        .map(|output| names[output.as_str()])
+        .collect();
+    while let Some(wire) = stack.pop() {
+        if needed[wire.index()] {
+            continue;
+        }
+        needed[wire.index()] = true;
+        if let Some(cut) = &best[wire.index()] {
+            stack.extend(cut.leaves.iter());
+        }
+    }
+
+    let output_names: HashSet<&String> = outputs.iter().collect();
+    let mut builder = Builder::new(circuit);
+    let mut wires: Vec<String> = vec![String::new(); indexed.num_wires()];
+    for &input in indexed.inputs.iter() {
+        let name = indexed.name(input);
+        builder
+            .circuit
+            .insert(name.clone(), (GateType::INPUT, vec![]));
+        wires[input.index()] = name;
+    }
+    for gate in indexed.gates.iter() {
+        let wire = gate.output.index();
+        if !needed[wire] {
+            continue;
+        }
+        let cut = best[wire].as_ref().unwrap();
+        let implementation = database.lookup(cut.table).clone();
+
+        // Signals line up with the bits of `Affine`. Variables past the cut's leaves
+        // don't affect the function, so any wire can stand in for them.
+        let mut signals = vec![String::new()];
+        for i in 0..CUT_SIZE {
+            let leaf = cut.leaves.get(i).or(cut.leaves.first());
+            signals.push(leaf.map_or_else(String::new, |leaf| wires[leaf.index()].clone()));
+        }
+        for &(a, b) in implementation.ands.iter() {
+            let a = builder.affine(a, &signals, None);
+            let b = builder.affine(b, &signals, None);
+            let and = builder.gate(GateType::AND, vec![a, b], None);
+            signals.push(and);
+        }
+        let name = indexed.name(gate.output);
+        let output = output_names.contains(&name).then(|| name.clone());
+        wires[wire] = builder.affine(implementation.output, &signals, output);
+    }
+
+    let mut rewritten = builder.circuit;
+    cleanup(&mut rewritten, outputs);
+    rewritten
+}
+
+/// Emits gates into the rewritten circuit, reusing identical gates and picking fresh
+/// names that don't clash with the original circuit.
+struct Builder<'a> {
+    original: &'a Circuit,
+    circuit: Circuit,
+    existing: HashMap<(String, Vec<String>), String>,
+    counter: usize,
+}
+
+impl<'a> Builder<'a> {
+    fn new(original: &'a Circuit) -> Self {
+        Self {
+            original,
+            circuit: Circuit::new(),
+            existing: HashMap::new(),
+            counter: 0,
+        }
+    }
+
+    /// Adds a gate, named `name` if given and otherwise shared with an identical gate.
+    fn gate(&mut self, op: GateType, mut inputs: Vec<String>, name: Option<String>) -> String {
+        if let Some(name) = name {
+            self.circuit.insert(name.clone(), (op, inputs));
+            return name;
+        }
+        inputs.sort();
+        let key = (op.to_string(), inputs);
+        if let Some(wire) = self.existing.get(&key) {
+            return wire.clone();
+        }
+        let wire = loop {
+            let wire = format!("c_{}", self.counter);
+            self.counter += 1;
+            if !self.original.contains_key(&wire) {
+                break wire;
+            }
+        };
+        self.circuit.insert(wire.clone(), (op, key.1.clone()));
+        self.existing.insert(key, wire.clone());
+        wire
+    }
+
+    /// XORs together the signals selected by `affine`.
+    fn affine(&mut self, affine: Affine, signals: &[String], name: Option<String>) -> String {
+        let terms: Vec<&String> = (1..signals.len())
+            .filter(|&i| affine >> i & 1 == 1)
+            .map(|i| &signals[i])
+            .collect();
+        let negated = affine & 1 == 1;
+        let Some((&first, rest)) = terms.split_first() else {
+            let op = if negated {
+                GateType::TRUE
+            } else {
+                GateType::FALSE
+            };
+            return self.gate(op, vec![], name);
+        };
+        if rest.is_empty() && !negated {
+            return match name {
+                Some(name) => self.gate(GateType::CONST, vec![first.clone()], Some(name)),
+                None => first.clone(),
+            };
+        }
+
+        let mut wire = first.clone();
+        for (i, &term) in rest.iter().enumerate() {
+            let last = i + 1 == rest.len() && !negated;
+            let name = if last { name.clone() } else { None };
+            wire = self.gate(GateType::XOR, vec![wire, term.clone()], name);
+        }
+        if negated {
+            wire = self.gate(GateType::NOT, vec![wire], name);
+        }
+        wire
+    }
+}
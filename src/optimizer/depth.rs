@@ -0,0 +1,28 @@
+use egg::{CostFunction, Id, Language};
+
+use crate::optimizer::CircuitLang;
+
+/// Whether a node costs a round in GMW-style protocols.
+pub fn is_nonlinear(node: &CircuitLang) -> bool {
+    node.gate_type().is_some_and(|op| op.is_nonlinear())
+}
+
+/// Tree cost for `egg::Extractor` that minimizes AND depth, breaking ties by tree size.
+///
+/// The size makes every node strictly more expensive than its children, so chains of
+/// free gates that rewrite into each other cannot be extracted as a cycle.
+pub struct DepthCost;
+
+impl CostFunction<CircuitLang> for DepthCost {
+    type Cost = (usize, u64);
+    fn cost<C>(&mut self, enode: &CircuitLang, mut costs: C) -> Self::Cost
+    where
+        C: FnMut(Id) -> Self::Cost,
+    {
+        let (depth, size) = enode.fold((0, 1u64), |(depth, size), id| {
+            let (child_depth, child_size) = costs(id);
+            (depth.max(child_depth), size.saturating_add(child_size))
+        });
+        (depth.saturating_add(is_nonlinear(enode) as usize), size)
+    }
+}
@@ -0,0 +1,497 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    rc::Rc,
+};
+
+use egg::{Analysis, EGraph, Extractor, Id, Language, RecExpr};
+use log::warn;
+
+use crate::optimizer::{
+    is_nonlinear, CircuitLang, CostModel, DepthCost, GarbleCost, FREE_GATE_COST,
+};
+
+/// Upper bound on the number of sweeps `DagExtractor::refine` makes over the DAG.
+const REFINE_PASSES: usize = 8;
+
+/// Upper bound on the number of repair rounds `DagExtractor::limit_depth` makes before
+/// giving up on keeping the cheap choices.
+const DEPTH_ROUNDS: usize = 64;
+
+/// Upper bound on the number of classes a cost set tracks. Copying sets up a long chain
+/// of gates is quadratic, so past this size a choice is priced like `egg::Extractor`
+/// does, and `refine` recovers the sharing for the roots.
+const COST_SET_LIMIT: usize = 1024;
+
+/// Best known way to compute one e-class: the chosen node and the cost of every class
+/// it transitively depends on, itself included. Sets are shared with the classes that
+/// read them, and past `COST_SET_LIMIT` they are the set of the largest child, with
+/// `total` summing the children instead.
+#[derive(Debug, Clone)]
+struct CostSet {
+    choice: CircuitLang,
+    costs: Rc<HashMap<Id, f64>>,
+    total: f64,
+}
+
+/// Greedy DAG extractor.
+///
+/// Unlike `egg::Extractor`, which sums the cost of every child and therefore pays for a
+/// shared subexpression once per use, this tracks the set of classes each choice depends
+/// on and counts every class once. A class is recomputed whenever one of its children
+/// improves, until no choice changes. The result is not guaranteed optimal; `refine`
+/// further improves it for a given set of roots.
+#[derive(Debug)]
+pub struct DagExtractor<'a, N: Analysis<CircuitLang> = ()> {
+    egraph: &'a EGraph<CircuitLang, N>,
+    model: &'a dyn CostModel,
+    best: HashMap<Id, CostSet>,
+}
+
+impl<'a, N: Analysis<CircuitLang>> DagExtractor<'a, N> {
+    pub fn new(egraph: &'a EGraph<CircuitLang, N>, model: &'a dyn CostModel) -> Self {
+        let mut best: HashMap<Id, CostSet> = HashMap::new();
+        let mut queue: VecDeque<Id> = VecDeque::new();
+        let mut queued: HashSet<Id> = HashSet::new();
+        for class in egraph.classes() {
+            if class.iter().any(|node| node.is_leaf()) {
+                queue.push_back(class.id);
+                queued.insert(class.id);
+            }
+        }
+
+        while let Some(id) = queue.pop_front() {
+            queued.remove(&id);
+            let mut improved = false;
+            for node in egraph[id].iter() {
+                let Some(candidate) = Self::cost_set(egraph, &best, id, node, model) else {
+                    continue;
+                };
+                let better = match best.get(&id) {
+                    None => true,
+                    Some(current) => candidate.total < current.total,
+                };
+                if better {
+                    best.insert(id, candidate);
+                    improved = true;
+                }
+            }
+            if improved {
+                for (_, parent) in egraph[id].parents() {
+                    let parent = egraph.find(parent);
+                    if queued.insert(parent) {
+                        queue.push_back(parent);
+                    }
+                }
+            }
+        }
+
+        let mut extractor = Self {
+            egraph,
+            model,
+            best,
+        };
+        extractor.break_cycles();
+        extractor
+    }
+
+    /// Re-chooses the classes whose choice reads the class itself.
+    ///
+    /// The greedy pass rules out cycles with the cost sets of the children alone, which
+    /// misses the ones through classes whose sets are stale or capped. Walking the
+    /// choices for every candidate would be quadratic, so they are broken here instead,
+    /// and if some class has no node left that avoids its cycle, every class falls back
+    /// to the choice of `egg::Extractor`.
+    fn break_cycles(&mut self) {
+        while let Some(id) = self.find_cycle() {
+            let cheapest = self.egraph[id]
+                .iter()
+                .filter(|node| node.all(|child| self.best.contains_key(&self.egraph.find(child))))
+                .filter(|node| !Self::reaches(self.egraph, &self.best, node, id))
+                .map(|node| {
+                    let set = Self::cost_set(self.egraph, &self.best, id, node, self.model);
+                    (
+                        set.as_ref().map_or(f64::INFINITY, |set| set.total),
+                        node,
+                        set,
+                    )
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            let Some((_, node, set)) = cheapest else {
+                warn!("DAG extraction found no acyclic choice, falling back to tree extraction");
+                let tree = Extractor::new(self.egraph, GarbleCost(self.model));
+                for (id, best) in self.best.iter_mut() {
+                    best.choice = tree.find_best_node(*id).clone();
+                }
+                return;
+            };
+            let node = node.clone();
+            let best = self.best.get_mut(&id).unwrap();
+            match set {
+                Some(set) => *best = set,
+                None => best.choice = node,
+            }
+        }
+    }
+
+    /// A class whose current choice reaches itself, if there is one.
+    fn find_cycle(&self) -> Option<Id> {
+        // missing = unvisited, false = on the stack, true = done
+        let mut state: HashMap<Id, bool> = HashMap::with_capacity(self.best.len());
+        for class in self.egraph.classes() {
+            if state.contains_key(&class.id) || !self.best.contains_key(&class.id) {
+                continue;
+            }
+            let mut stack: Vec<(Id, usize)> = vec![(class.id, 0)];
+            state.insert(class.id, false);
+            while let Some((id, next)) = stack.pop() {
+                let children = self.best[&id].choice.children();
+                let Some(child) = children.get(next) else {
+                    state.insert(id, true);
+                    continue;
+                };
+                stack.push((id, next + 1));
+                let child = self.egraph.find(*child);
+                match state.get(&child) {
+                    Some(false) => return Some(id),
+                    Some(true) => {}
+                    None => {
+                        state.insert(child, false);
+                        stack.push((child, 0));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Cost set of computing `id` with `node`, or `None` if a child has no choice yet or
+    /// the node would make the choice cyclic.
+    fn cost_set(
+        egraph: &EGraph<CircuitLang, N>,
+        best: &HashMap<Id, CostSet>,
+        id: Id,
+        node: &CircuitLang,
+        model: &dyn CostModel,
+    ) -> Option<CostSet> {
+        let children: Vec<&CostSet> = node
+            .children()
+            .iter()
+            .map(|child| best.get(&egraph.find(*child)))
+            .collect::<Option<Vec<_>>>()?;
+        if children.iter().any(|child| child.costs.contains_key(&id)) {
+            return None;
+        }
+
+        let own = model.node_cost(node);
+        let Some(largest) = children.iter().max_by_key(|child| child.costs.len()) else {
+            return Some(CostSet {
+                choice: node.clone(),
+                costs: Rc::new(HashMap::from([(id, own)])),
+                total: own,
+            });
+        };
+        let size: usize = children.iter().map(|child| child.costs.len()).sum();
+        if size >= COST_SET_LIMIT {
+            let total = children.iter().map(|child| child.total).sum::<f64>() + own;
+            return Some(CostSet {
+                choice: node.clone(),
+                costs: Rc::clone(&largest.costs),
+                total,
+            });
+        }
+
+        // Merge the smaller child sets into a copy of the largest.
+        let mut costs = HashMap::clone(&largest.costs);
+        for child in children.iter() {
+            if !Rc::ptr_eq(&child.costs, &largest.costs) {
+                for (class, cost) in child.costs.iter() {
+                    costs.entry(*class).or_insert(*cost);
+                }
+            }
+        }
+        costs.insert(id, own);
+        let total = costs.values().sum();
+        Some(CostSet {
+            choice: node.clone(),
+            costs: Rc::new(costs),
+            total,
+        })
+    }
+
+    /// Whether following the current choices from the children of `node` leads back to
+    /// `id`. Cost sets of classes upstream of a change are stale until they are
+    /// recomputed, so they cannot be trusted to rule out cycles on their own.
+    fn reaches(
+        egraph: &EGraph<CircuitLang, N>,
+        best: &HashMap<Id, CostSet>,
+        node: &CircuitLang,
+        id: Id,
+    ) -> bool {
+        let mut seen: HashSet<Id> = HashSet::new();
+        let mut stack: Vec<Id> = node.children().iter().map(|c| egraph.find(*c)).collect();
+        while let Some(class) = stack.pop() {
+            if class == id {
+                return true;
+            }
+            if seen.insert(class) {
+                stack.extend(
+                    best[&class]
+                        .choice
+                        .children()
+                        .iter()
+                        .map(|c| egraph.find(*c)),
+                );
+            }
+        }
+        false
+    }
+
+    /// Node chosen for the class `id`.
+    pub fn find_best_node(&self, id: Id) -> &CircuitLang {
+        &self.best[&self.egraph.find(id)].choice
+    }
+
+    /// Improves the choices for `roots` by local search.
+    ///
+    /// The greedy pass prices every class on its own, so it cannot see that a subterm is
+    /// already paid for by another root. This switches one class at a time to another
+    /// node whenever that lowers the cost of all roots together, tracking how often each
+    /// class is read so that only the classes entering or leaving the DAG are priced.
+    pub fn refine(&mut self, roots: &[Id]) {
+        let roots: Vec<Id> = roots.iter().map(|root| self.egraph.find(*root)).collect();
+        let mut references: HashMap<Id, usize> = HashMap::new();
+        for root in roots.iter() {
+            self.acquire(*root, &mut references);
+        }
+
+        for _ in 0..REFINE_PASSES {
+            let mut improved = false;
+            let mut classes: Vec<Id> = references.keys().copied().collect();
+            classes.sort();
+            for id in classes {
+                if references.get(&id).copied().unwrap_or(0) == 0 {
+                    continue;
+                }
+                for node in self.egraph[id].nodes.iter() {
+                    let current = self.best[&id].choice.clone();
+                    if *node == current
+                        || !node.all(|child| self.best.contains_key(&self.egraph.find(child)))
+                        || Self::reaches(self.egraph, &self.best, node, id)
+                    {
+                        continue;
+                    }
+                    let mut delta = self.model.node_cost(node) - self.model.node_cost(&current);
+                    self.best.get_mut(&id).unwrap().choice = node.clone();
+                    for child in node.children() {
+                        delta += self.acquire(self.egraph.find(*child), &mut references);
+                    }
+                    for child in current.children() {
+                        delta -= self.release(self.egraph.find(*child), &mut references);
+                    }
+                    if delta < -FREE_GATE_COST / 2.0 {
+                        improved = true;
+                        continue;
+                    }
+                    self.best.get_mut(&id).unwrap().choice = current.clone();
+                    for child in current.children() {
+                        self.acquire(self.egraph.find(*child), &mut references);
+                    }
+                    for child in node.children() {
+                        self.release(self.egraph.find(*child), &mut references);
+                    }
+                }
+            }
+            if !improved {
+                break;
+            }
+        }
+    }
+
+    /// Re-chooses nodes so that no root is deeper than `bound` in nonlinear gates, or as
+    /// shallow as it can be with `None`. Roots that cannot meet `bound` get their minimum
+    /// depth instead.
+    ///
+    /// Works like required-time repair in technology mapping: every class on the DAG gets
+    /// the depth its parents allow it, and a class whose choice cannot meet that is
+    /// switched to the cheapest node that can, judged by the minimum depth of its
+    /// children. Returns `false` if that did not settle, in which case every class falls
+    /// back to its minimum-depth node.
+    pub fn limit_depth(&mut self, roots: &[Id], bound: Option<usize>) -> bool {
+        let depths = Extractor::new(self.egraph, DepthCost);
+        let min_depth = |id: Id| depths.find_best_cost(id).0;
+        let arrival = |node: &CircuitLang| {
+            let children = node.children().iter().map(|child| min_depth(*child));
+            children
+                .max()
+                .unwrap_or(0)
+                .saturating_add(is_nonlinear(node) as usize)
+        };
+        let roots: Vec<(Id, usize)> = roots
+            .iter()
+            .map(|root| {
+                let root = self.egraph.find(*root);
+                let depth = min_depth(root);
+                (root, bound.map_or(depth, |bound| bound.max(depth)))
+            })
+            .collect();
+
+        for _ in 0..DEPTH_ROUNDS {
+            let (mut changed, mut stuck) = (false, false);
+            let mut required: HashMap<Id, usize> = HashMap::new();
+            for (root, depth) in roots.iter() {
+                let entry = required.entry(*root).or_insert(*depth);
+                *entry = (*entry).min(*depth);
+            }
+            for id in self.topological_order(roots.iter().map(|(root, _)| *root)) {
+                // Dropped off the DAG by a switch earlier in this round
+                let Some(&limit) = required.get(&id) else {
+                    continue;
+                };
+                if arrival(&self.best[&id].choice) > limit {
+                    let cheapest = self.egraph[id]
+                        .iter()
+                        .filter(|node| arrival(node) <= limit)
+                        .filter(|node| !Self::reaches(self.egraph, &self.best, node, id))
+                        .map(|node| {
+                            // Stale sets may reject a node that `reaches` has just cleared
+                            let set = Self::cost_set(self.egraph, &self.best, id, node, self.model);
+                            (set.map_or(f64::INFINITY, |set| set.total), node)
+                        })
+                        .min_by(|a, b| a.0.total_cmp(&b.0));
+                    let Some((_, node)) = cheapest else {
+                        stuck = true;
+                        break;
+                    };
+                    self.best.get_mut(&id).unwrap().choice = node.clone();
+                    changed = true;
+                }
+                let choice = &self.best[&id].choice;
+                let limit = limit.saturating_sub(is_nonlinear(choice) as usize);
+                for child in choice.children() {
+                    let entry = required.entry(self.egraph.find(*child)).or_insert(limit);
+                    *entry = (*entry).min(limit);
+                }
+            }
+            if stuck {
+                break;
+            }
+            if !changed {
+                return true;
+            }
+        }
+
+        for (id, best) in self.best.iter_mut() {
+            best.choice = depths.find_best_node(*id).clone();
+        }
+        false
+    }
+
+    /// Classes reachable from `roots` through the current choices, parents first.
+    fn topological_order(&self, roots: impl Iterator<Item = Id>) -> Vec<Id> {
+        let mut order = Vec::new();
+        let mut done: HashSet<Id> = HashSet::new();
+        for root in roots {
+            let mut stack: Vec<(Id, bool)> = vec![(root, false)];
+            while let Some((id, expanded)) = stack.pop() {
+                if expanded {
+                    order.push(id);
+                    continue;
+                }
+                if !done.insert(id) {
+                    continue;
+                }
+                stack.push((id, true));
+                let choice = &self.best[&id].choice;
+                stack.extend(
+                    choice
+                        .children()
+                        .iter()
+                        .map(|c| (self.egraph.find(*c), false)),
+                );
+            }
+        }
+        order.reverse();
+        order
+    }
+
+    /// Adds a reference to `id`, returning the cost of the classes that enter the DAG.
+    fn acquire(&self, id: Id, references: &mut HashMap<Id, usize>) -> f64 {
+        let mut added = 0.0;
+        let mut stack = vec![id];
+        while let Some(class) = stack.pop() {
+            let count = references.entry(class).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                let choice = &self.best[&class].choice;
+                added += self.model.node_cost(choice);
+                stack.extend(choice.children().iter().map(|c| self.egraph.find(*c)));
+            }
+        }
+        added
+    }
+
+    /// Drops a reference to `id`, returning the cost of the classes that leave the DAG.
+    fn release(&self, id: Id, references: &mut HashMap<Id, usize>) -> f64 {
+        let mut freed = 0.0;
+        let mut stack = vec![id];
+        while let Some(class) = stack.pop() {
+            let count = references.get_mut(&class).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                let choice = &self.best[&class].choice;
+                freed += self.model.node_cost(choice);
+                stack.extend(choice.children().iter().map(|c| self.egraph.find(*c)));
+            }
+        }
+        freed
+    }
+
+    /// Cost of the sub-DAG below `id`, counting every class once.
+    pub fn find_best_cost(&self, id: Id) -> f64 {
+        self.dag_cost(&[id])
+    }
+
+    /// Cost of all `roots` together, counting classes shared between roots once.
+    pub fn dag_cost(&self, roots: &[Id]) -> f64 {
+        let mut seen: HashSet<Id> = HashSet::new();
+        let mut stack: Vec<Id> = roots.iter().map(|root| self.egraph.find(*root)).collect();
+        let mut total = 0.0;
+        while let Some(class) = stack.pop() {
+            if !seen.insert(class) {
+                continue;
+            }
+            let choice = &self.best[&class].choice;
+            total += self.model.node_cost(choice);
+            stack.extend(choice.children().iter().map(|c| self.egraph.find(*c)));
+        }
+        total
+    }
+
+    /// Builds the chosen DAG below `root` as an expression whose shared subterms appear
+    /// only once.
+    pub fn find_best(&self, root: Id) -> (f64, RecExpr<CircuitLang>) {
+        let mut expr = RecExpr::default();
+        let mut ids: HashMap<Id, Id> = HashMap::new();
+        let mut stack: Vec<(Id, bool)> = vec![(self.egraph.find(root), false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if ids.contains_key(&id) {
+                continue;
+            }
+            let node = self.find_best_node(id);
+            if !expanded {
+                stack.push((id, true));
+                stack.extend(
+                    node.children()
+                        .iter()
+                        .map(|child| (self.egraph.find(*child), false)),
+                );
+                continue;
+            }
+            let node = node
+                .clone()
+                .map_children(|child| ids[&self.egraph.find(child)]);
+            ids.insert(id, expr.add(node));
+        }
+        (self.find_best_cost(root), expr)
+    }
+}
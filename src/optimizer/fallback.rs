@@ -0,0 +1,107 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    optimizer::{circuit_cost, eliminate_dead_gates, CostModel, KeepBest, Kept},
+    utils::{Circuit, GateType},
+};
+
+/// Picks the circuit to return out of `original` and `optimized` as `keep_best` says,
+/// along with which one it is and its `circuit_cost`.
+pub fn keep_best(
+    original: &Circuit,
+    optimized: Circuit,
+    outputs: &[String],
+    keep_best: KeepBest,
+    model: &dyn CostModel,
+) -> (Circuit, Kept, f64) {
+    let optimized_cost = circuit_cost(&optimized, outputs, model);
+    if keep_best == KeepBest::Off {
+        return (optimized, Kept::Optimized, optimized_cost);
+    }
+    let mut alternatives = vec![(
+        original.clone(),
+        Kept::Original,
+        circuit_cost(original, outputs, model),
+    )];
+    if keep_best == KeepBest::PerOutput {
+        let cheaper: Vec<String> = outputs
+            .iter()
+            .filter(|output| {
+                let cone = std::slice::from_ref(*output);
+                circuit_cost(original, cone, model) < circuit_cost(&optimized, cone, model)
+            })
+            .cloned()
+            .collect();
+        if !cheaper.is_empty() && cheaper.len() < outputs.len() {
+            let mixed = mix(original, &optimized, outputs, &cheaper);
+            let cost = circuit_cost(&mixed, outputs, model);
+            let kept = Kept::Mixed {
+                original_outputs: cheaper,
+            };
+            alternatives.push((mixed, kept, cost));
+        }
+    }
+    // Ties keep the optimized circuit, which has been through `cleanup` as well
+    let mut best = (optimized, Kept::Optimized, optimized_cost);
+    for alternative in alternatives {
+        if alternative.2 < best.2 {
+            best = alternative;
+        }
+    }
+    best
+}
+
+/// `optimized` with the cones of `replaced` copied over from `original`. Their internal
+/// wires are renamed `k_<name>` so they can't clash with the optimized circuit's wires,
+/// and logic the copied cones share is copied once.
+fn mix(
+    original: &Circuit,
+    optimized: &Circuit,
+    outputs: &[String],
+    replaced: &[String],
+) -> Circuit {
+    let replaced: HashSet<&String> = replaced.iter().collect();
+    let mut mixed = optimized.clone();
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut taken: HashSet<String> = original.keys().chain(optimized.keys()).cloned().collect();
+
+    let mut rename = |wire: &String, mixed: &mut Circuit| -> String {
+        if let Some(name) = names.get(wire) {
+            return name.clone();
+        }
+        let name = match original.get(wire) {
+            Some((GateType::INPUT, _)) => {
+                mixed.insert(wire.clone(), (GateType::INPUT, vec![]));
+                wire.clone()
+            }
+            _ if replaced.contains(wire) => wire.clone(),
+            _ => {
+                let mut name = format!("k_{}", wire);
+                while !taken.insert(name.clone()) {
+                    name.push('_');
+                }
+                name
+            }
+        };
+        names.insert(wire.clone(), name.clone());
+        name
+    };
+
+    let mut stack: Vec<&String> = replaced.iter().copied().collect();
+    let mut copied: HashSet<&String> = HashSet::new();
+    while let Some(wire) = stack.pop() {
+        let (op, inputs) = &original[wire];
+        if *op == GateType::INPUT || !copied.insert(wire) {
+            continue;
+        }
+        stack.extend(inputs.iter());
+        let inputs: Vec<String> = inputs
+            .iter()
+            .map(|input| rename(input, &mut mixed))
+            .collect();
+        let name = rename(wire, &mut mixed);
+        mixed.insert(name, (*op, inputs));
+    }
+    eliminate_dead_gates(&mut mixed, outputs);
+    mixed
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use egg::{Analysis, EGraph, Id, Language, RecExpr};
+
+use crate::{
+    ir::{IndexedCircuit, WireId},
+    optimizer::CircuitLang,
+    utils::{Circuit, GateType},
+};
+
+/// Lowers output cones of a circuit node by node, remembering the node of every wire so
+/// that logic shared between outputs is only added once.
+///
+/// The walk uses an explicit stack, so long carry chains can't overflow the call stack.
+/// Inputs are visited in order and every wire is added after its inputs. Inputs become
+/// `Wire` nodes, and `CONST` buffers take the node of the wire they copy.
+pub(crate) struct Lowering<'a> {
+    circuit: &'a IndexedCircuit,
+    ids: &'a HashMap<&'a str, WireId>,
+    added: HashMap<WireId, Id>,
+}
+
+impl<'a> Lowering<'a> {
+    /// `ids` is the name table of `circuit`.
+    pub(crate) fn new(circuit: &'a IndexedCircuit, ids: &'a HashMap<&'a str, WireId>) -> Self {
+        Self {
+            circuit,
+            ids,
+            added: HashMap::new(),
+        }
+    }
+
+    /// Adds the cone of `output` with `add`, which receives every node with its children
+    /// already added, and returns the id of `output`. Outputs that aren't in the circuit
+    /// are wires of their own.
+    pub(crate) fn lower(&mut self, output: &str, mut add: impl FnMut(CircuitLang) -> Id) -> Id {
+        let Some(&root) = self.ids.get(output) else {
+            return add(CircuitLang::Wire(output.to_string()));
+        };
+        let first_gate = self.circuit.inputs.len();
+        // (wire, whether its inputs have been pushed already)
+        let mut stack = vec![(root, false)];
+        while let Some((wire, expanded)) = stack.pop() {
+            if self.added.contains_key(&wire) {
+                continue;
+            }
+            let (op, inputs) = match wire.index().checked_sub(first_gate) {
+                None => (GateType::INPUT, &[][..]),
+                Some(gate) => {
+                    let gate = &self.circuit.gates[gate];
+                    (gate.op, gate.inputs.as_slice())
+                }
+            };
+            if !expanded {
+                stack.push((wire, true));
+                stack.extend(inputs.iter().rev().map(|&input| (input, false)));
+                continue;
+            }
+            let children: Vec<Id> = inputs.iter().map(|input| self.added[input]).collect();
+            let id = match CircuitLang::gate(op, &children) {
+                Some(node) => add(node),
+                None if op == GateType::CONST => {
+                    *children.first().expect("Expected one input for buffer")
+                }
+                None => add(CircuitLang::Wire(self.circuit.name(wire))),
+            };
+            self.added.insert(wire, id);
+        }
+        self.added[&root]
+    }
+}
+
+/// Adds the cones of `outputs` straight to `egraph`, one e-node per gate, and returns
+/// the e-class of every output. Gates shared between outputs are added once.
+pub fn circuit_to_egraph<N: Analysis<CircuitLang>>(
+    circuit: &Circuit,
+    outputs: &[String],
+    egraph: &mut EGraph<CircuitLang, N>,
+) -> Vec<Id> {
+    let indexed = IndexedCircuit::from_circuit(circuit);
+    let ids = indexed
+        .name_table()
+        .expect("Lowered circuits keep their names");
+    let mut lowering = Lowering::new(&indexed, &ids);
+    outputs
+        .iter()
+        .map(|output| lowering.lower(output, |node| egraph.add(node)))
+        .collect()
+}
+
+/// The expression computing `output`, with one node per gate of its cone.
+pub fn circuit_to_expr(circuit: &Circuit, output: &str) -> RecExpr<CircuitLang> {
+    let indexed = IndexedCircuit::from_circuit(circuit);
+    let ids = indexed
+        .name_table()
+        .expect("Lowered circuits keep their names");
+    let mut expr = RecExpr::default();
+    Lowering::new(&indexed, &ids).lower(output, |node| expr.add(node));
+    expr
+}
+
+/// Lifts an extracted expression back into a circuit whose last node drives `output`.
+/// Every other gate gets a fresh `i_<n>` wire, and an output that is just another wire
+/// becomes a `CONST` buffer of it.
+pub fn expr_to_circuit(expr: &RecExpr<CircuitLang>, output: &str) -> Circuit {
+    let mut circuit = Circuit::new();
+    lift_expr(expr, output, &mut circuit, &mut HashMap::new(), &mut 0);
+    circuit
+}
+
+/// `expr_to_circuit` into an existing circuit. Gates with the same type and inputs as
+/// one in `existing_gates` reuse its wire instead of being added again.
+pub(crate) fn lift_expr(
+    expr: &RecExpr<CircuitLang>,
+    output: &str,
+    circuit: &mut Circuit,
+    existing_gates: &mut HashMap<String, String>,
+    wire_counter: &mut usize,
+) {
+    let nodes = expr.as_ref();
+    let mut wires: Vec<String> = Vec::with_capacity(nodes.len());
+    for (i, node) in nodes.iter().enumerate() {
+        let is_output = i + 1 == nodes.len();
+        let wire = match node {
+            // An output that is an input wire stays an input, a buffer would read itself
+            CircuitLang::Wire(wire) if is_output && wire == output => {
+                circuit
+                    .entry(wire.clone())
+                    .or_insert((GateType::INPUT, Vec::new()));
+                return;
+            }
+            CircuitLang::Wire(wire) if is_output => {
+                circuit.insert(output.to_string(), (GateType::CONST, vec![wire.clone()]));
+                return;
+            }
+            CircuitLang::Wire(wire) => {
+                circuit
+                    .entry(wire.clone())
+                    .or_insert((GateType::INPUT, Vec::new()));
+                wire.clone()
+            }
+            _ => {
+                let op = node.gate_type().expect("Only wires have no gate type");
+                let inputs: Vec<String> = node
+                    .children()
+                    .iter()
+                    .map(|child| wires[usize::from(*child)].clone())
+                    .collect();
+                if is_output {
+                    circuit.insert(output.to_string(), (op, inputs));
+                    return;
+                }
+                add_or_reuse_gate(circuit, op, inputs, existing_gates, wire_counter)
+            }
+        };
+        wires.push(wire);
+    }
+}
+
+fn add_or_reuse_gate(
+    circuit: &mut Circuit,
+    op: GateType,
+    inputs: Vec<String>,
+    existing_gates: &mut HashMap<String, String>,
+    wire_counter: &mut usize,
+) -> String {
+    let gate_key = format!("{}_{:?}", op, inputs);
+    if let Some(wire) = existing_gates.get(&gate_key) {
+        return wire.clone();
+    }
+    let wire = format!("i_{}", wire_counter);
+    *wire_counter += 1;
+    circuit.insert(wire.clone(), (op, inputs));
+    existing_gates.insert(gate_key, wire.clone());
+    wire
+}
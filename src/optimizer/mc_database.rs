@@ -0,0 +1,230 @@
+//! Generated by `cargo run --release --example mc_database`, do not edit.
+
+use crate::optimizer::McEntry;
+
+/// Minimum-AND XAG of each four-input NPN class representative.
+#[rustfmt::skip]
+pub static MC_DATABASE: &[McEntry] = &[
+    (0x0000, &[], 0b00000000),
+    (0x0001, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100110, 0b1011000)], 0b11111111),
+    (0x0003, &[(0b0000100, 0b0001000), (0b0010000, 0b0101100)], 0b01111101),
+    (0x0006, &[(0b0000110, 0b0001000), (0b0010000, 0b0100110)], 0b01100110),
+    (0x0007, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100000, 0b1011000)], 0b11111001),
+    (0x000f, &[(0b0001000, 0b0010000)], 0b00111001),
+    (0x0016, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110000, 0b1001110)], 0b11001110),
+    (0x0017, &[(0b0000110, 0b0001010), (0b0010000, 0b0100010)], 0b01110011),
+    (0x0018, &[(0b0000110, 0b0001010), (0b0010000, 0b0101010)], 0b01101010),
+    (0x0019, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110000, 0b1001110)], 0b10010111),
+    (0x001b, &[(0b0000010, 0b0001100), (0b0010000, 0b0100100)], 0b01110101),
+    (0x001e, &[(0b0000010, 0b0000100), (0b0010000, 0b0101110)], 0b01101110),
+    (0x001f, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100110, 0b1001000)], 0b10010001),
+    (0x003c, &[(0b0001100, 0b0010000)], 0b00101100),
+    (0x003d, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110110, 0b1011100)], 0b10100111),
+    (0x003f, &[(0b0000100, 0b0001000), (0b0010000, 0b0100000)], 0b01110001),
+    (0x0069, &[(0b0001110, 0b0010000)], 0b00111111),
+    (0x006b, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110010, 0b1011110)], 0b11001111),
+    (0x006f, &[(0b0000110, 0b0001000), (0b0010000, 0b0101000)], 0b01111001),
+    (0x007e, &[(0b0000110, 0b0001010), (0b0010000, 0b0101100)], 0b01101100),
+    (0x007f, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100000, 0b1001000)], 0b10010001),
+    (0x00ff, &[], 0b00010001),
+    (0x0116, &[(0b0000110, 0b0001010), (0b0011110, 0b0100010)], 0b01011110),
+    (0x0117, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111000, 0b1000110)], 0b11100001),
+    (0x0118, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111000, 0b1000110)], 0b10111000),
+    (0x0119, &[(0b0001010, 0b0010010), (0b0000110, 0b0100010)], 0b01100101),
+    (0x011a, &[(0b0000010, 0b0001100), (0b0011010, 0b0100100)], 0b01011010),
+    (0x011b, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111100, 0b1000010)], 0b11100101),
+    (0x011e, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100110, 0b1000000)], 0b10111110),
+    (0x011f, &[(0b0000010, 0b0000100), (0b0011000, 0b0101110)], 0b01001001),
+    (0x012c, &[(0b0000010, 0b0000100), (0b0011100, 0b0101010)], 0b01011100),
+    (0x012d, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110110, 0b1000100)], 0b11101111),
+    (0x012f, &[(0b0000100, 0b0001010), (0b0011000, 0b0100110)], 0b01001001),
+    (0x013c, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110110, 0b1010100)], 0b11001000),
+    (0x013d, &[(0b0000010, 0b0000100), (0b0001100, 0b0110110)], 0b01100111),
+    (0x013e, &[(0b0000100, 0b0010010), (0b0001100, 0b0100010)], 0b01111110),
+    (0x013f, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111010, 0b1010100)], 0b10010001),
+    (0x0168, &[(0b0000010, 0b0000100), (0b0001110, 0b0110110)], 0b01110110),
+    (0x0169, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110110, 0b1010110)], 0b11011001),
+    (0x016a, &[(0b0000010, 0b0000100), (0b0001000, 0b0010010), (0b0111000, 0b1011100)], 0b11101010),
+    (0x016b, &[(0b0000100, 0b0001000), (0b0011110, 0b0100010)], 0b01001111),
+    (0x016e, &[(0b0001000, 0b0010010), (0b0000110, 0b0100010)], 0b01110100),
+    (0x016f, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111000, 0b1010110)], 0b11011001),
+    (0x017e, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101000, 0b1000110)], 0b11111110),
+    (0x017f, &[(0b0000110, 0b0001010), (0b0010010, 0b0101100)], 0b01000011),
+    (0x0180, &[(0b0000110, 0b0001010), (0b0010010, 0b0101100)], 0b01010010),
+    (0x0181, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101000, 0b1000110)], 0b11101111),
+    (0x0182, &[(0b0000010, 0b0000100), (0b0001000, 0b0010010), (0b0110110, 0b1000100)], 0b10110110),
+    (0x0183, &[(0b0000010, 0b0010100), (0b0001100, 0b0100100)], 0b01101001),
+    (0x0186, &[(0b0000010, 0b0000100), (0b0011110, 0b0101000)], 0b01011110),
+    (0x0187, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110110, 0b1000110)], 0b11101111),
+    (0x0189, &[(0b0000010, 0b0011000), (0b0000110, 0b0101000)], 0b01101111),
+    (0x018b, &[(0b0000010, 0b0010000), (0b0000100, 0b0001000), (0b0100100, 0b1001010)], 0b11001101),
+    (0x018f, &[(0b0000110, 0b0001010), (0b0011000, 0b0100100)], 0b01001001),
+    (0x0196, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110110, 0b1010110)], 0b11001000),
+    (0x0197, &[(0b0000010, 0b0000100), (0b0001110, 0b0110110)], 0b01100111),
+    (0x0198, &[(0b0000010, 0b0001000), (0b0000110, 0b0111010)], 0b01111010),
+    (0x0199, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110110, 0b1010110)], 0b10010001),
+    (0x019a, &[(0b0000010, 0b0001000), (0b0000100, 0b0011000), (0b0111010, 0b1011010)], 0b10000000),
+    (0x019b, &[(0b0000100, 0b0001000), (0b0010110, 0b0101010)], 0b01000111),
+    (0x019e, &[(0b0000110, 0b0010010), (0b0001110, 0b0100010)], 0b01111100),
+    (0x019f, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111000, 0b1010110)], 0b10010001),
+    (0x01a8, &[(0b0000100, 0b0001000), (0b0010010, 0b0101110)], 0b01010010),
+    (0x01a9, &[(0b0000010, 0b0010000), (0b0000100, 0b0001000), (0b0100000, 0b1001100)], 0b11001111),
+    (0x01aa, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110110, 0b1010010)], 0b10000000),
+    (0x01ab, &[(0b0000100, 0b0001000), (0b0000010, 0b0111100)], 0b01101101),
+    (0x01ac, &[(0b0000010, 0b0000100), (0b0001000, 0b0010100), (0b0111100, 0b1011110)], 0b11101000),
+    (0x01ad, &[(0b0000110, 0b0001000), (0b0011010, 0b0100100)], 0b01001011),
+    (0x01ae, &[(0b0001000, 0b0010100), (0b0000010, 0b0100100)], 0b01110110),
+    (0x01af, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111100, 0b1010010)], 0b11011001),
+    (0x01bc, &[(0b0000110, 0b0010010), (0b0001100, 0b0100010)], 0b01111110),
+    (0x01bd, &[(0b0000010, 0b0000100), (0b0001000, 0b0010010), (0b0111010, 0b1001100)], 0b10101011),
+    (0x01be, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101100, 0b1000010)], 0b11111110),
+    (0x01bf, &[(0b0000010, 0b0001100), (0b0010100, 0b0101110)], 0b01000101),
+    (0x01e8, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111000, 0b1000110)], 0b11110000),
+    (0x01e9, &[(0b0000110, 0b0001010), (0b0011110, 0b0100010)], 0b01001111),
+    (0x01ea, &[(0b0001100, 0b0010100), (0b0000010, 0b0100100)], 0b01110110),
+    (0x01eb, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111100, 0b1000010)], 0b10101101),
+    (0x01ee, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100110, 0b1000000)], 0b11110110),
+    (0x01ef, &[(0b0000010, 0b0000100), (0b0011000, 0b0100110)], 0b01001001),
+    (0x01fe, &[(0b0000010, 0b0000100), (0b0001000, 0b0100110)], 0b01111110),
+    (0x033c, &[(0b0000100, 0b0001000), (0b0010000, 0b0100000)], 0b01011100),
+    (0x033d, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111010, 0b1011100)], 0b11111011),
+    (0x033f, &[(0b0001100, 0b0010100)], 0b00100101),
+    (0x0356, &[(0b0000010, 0b0010000), (0b0000100, 0b0001000)], 0b01111110),
+    (0x0357, &[(0b0000010, 0b0010000), (0b0000100, 0b0001000), (0b0110010, 0b1001100)], 0b10000001),
+    (0x0358, &[(0b0000010, 0b0000100), (0b0001000, 0b0010100), (0b0110010, 0b1011110)], 0b10001010),
+    (0x0359, &[(0b0000010, 0b0000100), (0b0001010, 0b0010100)], 0b01100111),
+    (0x035a, &[(0b0000100, 0b0001000), (0b0010000, 0b0100110)], 0b01011010),
+    (0x035b, &[(0b0000010, 0b0000100), (0b0001000, 0b0010100), (0b0110110, 0b1011110)], 0b11110011),
+    (0x035e, &[(0b0000010, 0b0010000), (0b0000100, 0b0001000), (0b0100010, 0b1000100)], 0b11111110),
+    (0x035f, &[(0b0000110, 0b0001000), (0b0011000, 0b0101100)], 0b01001001),
+    (0x0368, &[(0b0000010, 0b0000100), (0b0001000, 0b0010010), (0b0111000, 0b1010100)], 0b10000000),
+    (0x0369, &[(0b0000100, 0b0001000), (0b0010000, 0b0100010)], 0b01001111),
+    (0x036a, &[(0b0000010, 0b0000100), (0b0001110, 0b0010100)], 0b01110110),
+    (0x036b, &[(0b0000010, 0b0000100), (0b0001000, 0b0010010), (0b0111100, 0b1011100)], 0b10010001),
+    (0x036c, &[(0b0000110, 0b0010000), (0b0001000, 0b0110010)], 0b01010100),
+    (0x036d, &[(0b0000010, 0b0001000), (0b0000100, 0b0011000), (0b0111010, 0b1000110)], 0b11001001),
+    (0x036e, &[(0b0000010, 0b0000100), (0b0001000, 0b0010010), (0b0111110, 0b1011100)], 0b11101010),
+    (0x036f, &[(0b0000010, 0b0001000), (0b0011000, 0b0100100)], 0b01001001),
+    (0x037c, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101000, 0b1001100)], 0b11110100),
+    (0x037d, &[(0b0000110, 0b0010000), (0b0001100, 0b0110010)], 0b01100011),
+    (0x037e, &[(0b0000010, 0b0010000), (0b0001100, 0b0100110)], 0b01111010),
+    (0x03c0, &[(0b0001100, 0b0010100)], 0b00110100),
+    (0x03c1, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111110, 0b1011100)], 0b10110011),
+    (0x03c3, &[(0b0000100, 0b0001000), (0b0010000, 0b0100000)], 0b01001101),
+    (0x03c5, &[(0b0000010, 0b0001000), (0b0011000, 0b0100110)], 0b01101011),
+    (0x03c6, &[(0b0000110, 0b0001000), (0b0011000, 0b0101010)], 0b01111110),
+    (0x03c7, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111000, 0b1011100)], 0b10010001),
+    (0x03cf, &[(0b0000100, 0b0011000)], 0b00101001),
+    (0x03d4, &[(0b0000010, 0b0010000), (0b0001100, 0b0100110)], 0b01011000),
+    (0x03d5, &[(0b0000010, 0b0000100), (0b0001000, 0b0010010), (0b0111110, 0b1001110)], 0b10001101),
+    (0x03d6, &[(0b0000010, 0b0010000), (0b0000100, 0b0001000), (0b0100010, 0b1000000)], 0b11111110),
+    (0x03d7, &[(0b0000110, 0b0001010), (0b0011100, 0b0101110)], 0b01001101),
+    (0x03d8, &[(0b0000110, 0b0010000), (0b0001100, 0b0100010)], 0b01011000),
+    (0x03d9, &[(0b0000010, 0b0000100), (0b0001000, 0b0010100), (0b0110100, 0b1000010)], 0b10000111),
+    (0x03db, &[(0b0000010, 0b0001100), (0b0011100, 0b0101000)], 0b01001101),
+    (0x03dc, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101010, 0b1001100)], 0b11010100),
+    (0x03dd, &[(0b0001010, 0b0010000), (0b0000100, 0b0110010)], 0b01100011),
+    (0x03de, &[(0b0000010, 0b0010000), (0b0000100, 0b0101010)], 0b01111110),
+    (0x03fc, &[(0b0000100, 0b0001000)], 0b00111100),
+    (0x0660, &[(0b0000110, 0b0011000)], 0b00100000),
+    (0x0661, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111110, 0b1011110)], 0b11100001),
+    (0x0662, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111010, 0b1011110)], 0b10011000),
+    (0x0663, &[(0b0000100, 0b0001000), (0b0011000, 0b0101010)], 0b01110101),
+    (0x0666, &[(0b0000110, 0b0001000), (0b0010000, 0b0100000)], 0b01000110),
+    (0x0667, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111000, 0b1011110)], 0b11100001),
+    (0x0669, &[(0b0000110, 0b0001000), (0b0010000, 0b0101000)], 0b01011111),
+    (0x066b, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111100, 0b1011110)], 0b11000001),
+    (0x066f, &[(0b0001110, 0b0010110)], 0b00111111),
+    (0x0672, &[(0b0000100, 0b0001010), (0b0011110, 0b0101100)], 0b01000110),
+    (0x0673, &[(0b0000010, 0b0010000), (0b0000100, 0b0001000), (0b0101010, 0b1001010)], 0b10011111),
+    (0x0676, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101000, 0b1001110)], 0b10000110),
+    (0x0678, &[(0b0001010, 0b0010000), (0b0000110, 0b0110010)], 0b01101010),
+    (0x0679, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101000, 0b1001110)], 0b11011111),
+    (0x067a, &[(0b0000010, 0b0000100), (0b0001000, 0b0010010), (0b0111110, 0b1001100)], 0b10000110),
+    (0x067b, &[(0b0000010, 0b0010000), (0b0001110, 0b0100100)], 0b01010001),
+    (0x067e, &[(0b0000010, 0b0000100), (0b0010110, 0b0101000)], 0b01101110),
+    (0x0690, &[(0b0001110, 0b0010110)], 0b00101110),
+    (0x0691, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111110, 0b1011110)], 0b10101001),
+    (0x0693, &[(0b0000100, 0b0001000), (0b0011000, 0b0100010)], 0b01110101),
+    (0x0696, &[(0b0000110, 0b0001000), (0b0010000, 0b0101000)], 0b01001110),
+    (0x0697, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111000, 0b1011110)], 0b10101001),
+    (0x069f, &[(0b0000110, 0b0011000)], 0b00110001),
+    (0x06b0, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111010, 0b1001110)], 0b10100010),
+    (0x06b1, &[(0b0000100, 0b0010000), (0b0001110, 0b0100010)], 0b01110101),
+    (0x06b2, &[(0b0000010, 0b0000100), (0b0010110, 0b0101100)], 0b01001110),
+    (0x06b3, &[(0b0000010, 0b0000100), (0b0001000, 0b0010100), (0b0111010, 0b1001010)], 0b11111111),
+    (0x06b4, &[(0b0000110, 0b0001010), (0b0010110, 0b0101000)], 0b01001110),
+    (0x06b5, &[(0b0000010, 0b0001000), (0b0000100, 0b0010000), (0b0100100, 0b1001100)], 0b10010111),
+    (0x06b6, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101100, 0b1001110)], 0b10100010),
+    (0x06b7, &[(0b0000010, 0b0001100), (0b0011110, 0b0100110)], 0b01110111),
+    (0x06b9, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101100, 0b1001110)], 0b11111011),
+    (0x06bd, &[(0b0000010, 0b0010000), (0b0000110, 0b0111010)], 0b01010001),
+    (0x06f0, &[(0b0000110, 0b0001000), (0b0010000, 0b0101110)], 0b01001000),
+    (0x06f1, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101000, 0b1011110)], 0b10111111),
+    (0x06f2, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101100, 0b1011110)], 0b11000110),
+    (0x06f6, &[(0b0001000, 0b0010110)], 0b00101110),
+    (0x06f9, &[(0b0000110, 0b0001000)], 0b00110111),
+    (0x0776, &[(0b0000110, 0b0001010), (0b0011110, 0b0100100)], 0b01011000),
+    (0x0778, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100000, 0b1000000)], 0b10111000),
+    (0x0779, &[(0b0001010, 0b0010010), (0b0000110, 0b0111010)], 0b01100101),
+    (0x077a, &[(0b0001100, 0b0010100), (0b0000010, 0b0111100)], 0b01011010),
+    (0x077e, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111110, 0b1000110)], 0b10111000),
+    (0x07b0, &[(0b0000100, 0b0001010), (0b0010010, 0b0100000)], 0b01111000),
+    (0x07b1, &[(0b0000010, 0b0000100), (0b0001000, 0b0010010), (0b0110000, 0b1001110)], 0b11100111),
+    (0x07b4, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110000, 0b1000100)], 0b11011100),
+    (0x07b5, &[(0b0000110, 0b0001010), (0b0010010, 0b0101000)], 0b01100011),
+    (0x07b6, &[(0b0000010, 0b0001100), (0b0010110, 0b0100110)], 0b01011000),
+    (0x07bc, &[(0b0000010, 0b0011000), (0b0000100, 0b0110000)], 0b01011100),
+    (0x07e0, &[(0b0000110, 0b0001010), (0b0010010, 0b0101010)], 0b01110010),
+    (0x07e1, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110000, 0b1000110)], 0b11101111),
+    (0x07e2, &[(0b0000010, 0b0000100), (0b0001000, 0b0010100), (0b0110000, 0b1001010)], 0b11010010),
+    (0x07e3, &[(0b0000010, 0b0001100), (0b0010100, 0b0100100)], 0b01101001),
+    (0x07e6, &[(0b0000010, 0b0000100), (0b0011110, 0b0101110)], 0b01011000),
+    (0x07e9, &[(0b0000010, 0b0011000), (0b0000110, 0b0110000)], 0b01101111),
+    (0x07f0, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100000, 0b1010000)], 0b10011000),
+    (0x07f1, &[(0b0000110, 0b0001000), (0b0010010, 0b0101110)], 0b01100101),
+    (0x07f2, &[(0b0000010, 0b0001000), (0b0010100, 0b0100010)], 0b01111010),
+    (0x07f8, &[(0b0000010, 0b0000100), (0b0001000, 0b0100000)], 0b01111000),
+    (0x0ff0, &[], 0b00011000),
+    (0x1668, &[(0b0000110, 0b0001010), (0b0010010, 0b0101100)], 0b01100000),
+    (0x1669, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100000, 0b1000000)], 0b10011111),
+    (0x166a, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111100, 0b1000100)], 0b11100110),
+    (0x166b, &[(0b0000010, 0b0001100), (0b0010100, 0b0100010)], 0b01111101),
+    (0x166e, &[(0b0000010, 0b0000100), (0b0011000, 0b0101000)], 0b01101110),
+    (0x167e, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111110, 0b1010110)], 0b11111000),
+    (0x1681, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111110, 0b1010110)], 0b11101001),
+    (0x1683, &[(0b0000100, 0b0001000), (0b0010010, 0b0100010)], 0b01111111),
+    (0x1686, &[(0b0000110, 0b0001000), (0b0010010, 0b0101000)], 0b01100110),
+    (0x1687, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0110000, 0b1010110)], 0b11101001),
+    (0x1689, &[(0b0000110, 0b0010010), (0b0001000, 0b0100100)], 0b01011111),
+    (0x168b, &[(0b0000010, 0b0001000), (0b0000100, 0b0011000), (0b0110110, 0b1000010)], 0b10111111),
+    (0x168e, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111110, 0b1010110)], 0b10110000),
+    (0x1696, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100000, 0b1000000)], 0b10001110),
+    (0x1697, &[(0b0000110, 0b0001010), (0b0010010, 0b0101100)], 0b01110001),
+    (0x1698, &[(0b0000110, 0b0001010), (0b0011000, 0b0100100)], 0b01101010),
+    (0x1699, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100000, 0b1000000)], 0b11010111),
+    (0x169a, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0111100, 0b1000100)], 0b10101110),
+    (0x169b, &[(0b0000010, 0b0001100), (0b0011000, 0b0101010)], 0b01111101),
+    (0x169e, &[(0b0000010, 0b0000100), (0b0011000, 0b0100000)], 0b01101110),
+    (0x16a9, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101100, 0b1000100)], 0b11111011),
+    (0x16ac, &[(0b0000010, 0b0010000), (0b0000100, 0b0001000), (0b0111000, 0b1001010)], 0b10101100),
+    (0x16ad, &[(0b0000010, 0b0011000), (0b0000100, 0b0110010)], 0b01011011),
+    (0x16bc, &[(0b0000100, 0b0001000), (0b0000010, 0b0110000)], 0b01001100),
+    (0x16e9, &[(0b0000010, 0b0000100), (0b0001000, 0b0100000)], 0b01011111),
+    (0x177e, &[(0b0000010, 0b0000100), (0b0001110, 0b0010110)], 0b01111000),
+    (0x178e, &[(0b0000110, 0b0011010)], 0b00110100),
+    (0x1796, &[(0b0000110, 0b0001010), (0b0010000, 0b0101100)], 0b01011110),
+    (0x1798, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0101000, 0b1001110)], 0b10110000),
+    (0x179a, &[(0b0000010, 0b0001100), (0b0011100, 0b0101010)], 0b01010010),
+    (0x17ac, &[(0b0000010, 0b0010000), (0b0000110, 0b0111000)], 0b01010100),
+    (0x17e8, &[(0b0000110, 0b0001010)], 0b00110010),
+    (0x18e7, &[(0b0000110, 0b0001010)], 0b00111011),
+    (0x19e1, &[(0b0000010, 0b0000100), (0b0001000, 0b0010000), (0b0100000, 0b1010000)], 0b11101111),
+    (0x19e3, &[(0b0000010, 0b0010100), (0b0001010, 0b0110010)], 0b01101111),
+    (0x19e6, &[(0b0000010, 0b0000100), (0b0001000, 0b0100000)], 0b01010110),
+    (0x1bd8, &[(0b0001100, 0b0010010)], 0b00111000),
+    (0x1be4, &[(0b0000010, 0b0001100)], 0b00110100),
+    (0x1ee1, &[(0b0000010, 0b0000100)], 0b00111111),
+    (0x3cc3, &[], 0b00011101),
+    (0x6996, &[], 0b00011110),
+];
@@ -1,10 +1,50 @@
+mod analysis;
+mod cache;
+mod cleanup;
+mod config;
+mod cost;
+mod cuts;
+mod depth;
+mod extract;
+mod fallback;
+mod lower;
+mod mc_database;
+mod npn;
 mod optim;
+mod partition;
+mod progress;
+mod report;
+mod rules_file;
+mod soundness;
+mod synthesis;
+mod xag;
 
+pub use self::analysis::*;
+pub use self::cache::*;
+pub use self::cleanup::*;
+pub use self::config::*;
+pub use self::cost::*;
+pub use self::cuts::*;
+pub use self::depth::*;
+pub use self::extract::*;
+pub use self::fallback::*;
+pub use self::lower::*;
+pub use self::mc_database::*;
+pub use self::npn::*;
 pub use self::optim::*;
-use egg::{define_language, rewrite, CostFunction, Id, Language, Rewrite};
+pub use self::partition::*;
+pub use self::progress::*;
+pub use self::report::*;
+pub use self::rules_file::*;
+pub use self::soundness::*;
+pub use self::synthesis::*;
+pub use self::xag::*;
+use egg::{define_language, rewrite, Id, Rewrite};
+
+use crate::utils::GateType;
 
 define_language! {
-    enum CircuitLang {
+    pub enum CircuitLang {
         "and" = And([Id; 2]),
         "not" = Not(Id),
         "or" = Or([Id; 2]),
@@ -14,13 +54,71 @@ define_language! {
         "nand" = Nand([Id; 2]),
         "andnot" = AndNot([Id; 2]),
         "xnor" = Xnor([Id; 2]),
+        "mux" = Mux([Id; 3]),
+        "maj" = Maj([Id; 3]),
+        "true" = True,
+        "false" = False,
         Wire(String),
     }
 }
 
-fn circuit_rules() -> Vec<Rewrite<CircuitLang, ()>> {
+impl CircuitLang {
+    /// The gate this node stands for, or `None` for a wire.
+    pub fn gate_type(&self) -> Option<GateType> {
+        match self {
+            CircuitLang::And(_) => Some(GateType::AND),
+            CircuitLang::Not(_) => Some(GateType::NOT),
+            CircuitLang::Or(_) => Some(GateType::OR),
+            CircuitLang::Xor(_) => Some(GateType::XOR),
+            CircuitLang::OrNot(_) => Some(GateType::ORNOT),
+            CircuitLang::Nor(_) => Some(GateType::NOR),
+            CircuitLang::Nand(_) => Some(GateType::NAND),
+            CircuitLang::AndNot(_) => Some(GateType::ANDNOT),
+            CircuitLang::Xnor(_) => Some(GateType::XNOR),
+            CircuitLang::Mux(_) => Some(GateType::MUX),
+            CircuitLang::Maj(_) => Some(GateType::MAJ),
+            CircuitLang::True => Some(GateType::TRUE),
+            CircuitLang::False => Some(GateType::FALSE),
+            CircuitLang::Wire(_) => None,
+        }
+    }
+
+    /// The node for a gate reading `children`, or `None` for inputs and buffers, which
+    /// have no node of their own.
+    pub fn gate(op: GateType, children: &[Id]) -> Option<CircuitLang> {
+        let unary = || children[0];
+        let binary = || [children[0], children[1]];
+        let ternary = || [children[0], children[1], children[2]];
+        match op {
+            GateType::AND => Some(CircuitLang::And(binary())),
+            GateType::OR => Some(CircuitLang::Or(binary())),
+            GateType::XOR => Some(CircuitLang::Xor(binary())),
+            GateType::NOR => Some(CircuitLang::Nor(binary())),
+            GateType::ORNOT => Some(CircuitLang::OrNot(binary())),
+            GateType::NAND => Some(CircuitLang::Nand(binary())),
+            GateType::ANDNOT => Some(CircuitLang::AndNot(binary())),
+            GateType::XNOR => Some(CircuitLang::Xnor(binary())),
+            GateType::NOT => Some(CircuitLang::Not(unary())),
+            GateType::MUX => Some(CircuitLang::Mux(ternary())),
+            GateType::MAJ => Some(CircuitLang::Maj(ternary())),
+            GateType::TRUE => Some(CircuitLang::True),
+            GateType::FALSE => Some(CircuitLang::False),
+            GateType::CONST | GateType::INPUT => None,
+        }
+    }
+
+    /// The value of a constant node.
+    pub fn constant(&self) -> Option<bool> {
+        match self {
+            CircuitLang::True => Some(true),
+            CircuitLang::False => Some(false),
+            _ => None,
+        }
+    }
+}
+
+pub fn circuit_rules() -> Vec<Rewrite<CircuitLang, ()>> {
     vec![
-        rewrite!("simplify-and"; "(and ?a ?a)" => "?a"),
         rewrite!("simplify-or"; "(or (and ?a (not ?b)) (and ?b (not ?a)))" => "(xor ?a ?b)"),
         rewrite!("double-negation-xor"; "(xor (not (not ?a)) ?b)" => "(xor ?a ?b)"),
         rewrite!("optimize-xor-combinations"; "(xor (xor ?a ?b) ?b)" => "?a"),
@@ -48,32 +146,14 @@ fn circuit_rules() -> Vec<Rewrite<CircuitLang, ()>> {
         // rewrite!("simplify-nested-not-and"; "(and (not (not ?a)) ?b)" => "(and ?a ?b)"),
         // rewrite!("simplify-nested-not-or"; "(or (not (not ?a)) ?b)" => "(or ?a ?b)"),
         rewrite!("merge-consecutive-xor"; "(xor (xor ?a ?b) ?c)" => "(xor ?a (xor ?b ?c))"),
-        rewrite!("eliminate-double-negation-xor"; "(xor (not (not ?a)) ?b)" => "(xor ?a ?b)"),
-        rewrite!("nand-simplify-pattern"; "(nand (nand ?a ?b) ?a)" => "(nand ?a ?b)"),
-        rewrite!("nor-simplify-pattern"; "(nor (nor ?a ?b) ?a)" => "(nor ?a ?b)"),
+        rewrite!("nand-simplify-pattern"; "(nand (nand ?a ?b) ?a)" => "(ornot ?b ?a)"),
+        rewrite!("nor-simplify-pattern"; "(nor (nor ?a ?b) ?a)" => "(andnot ?b ?a)"),
+        // Multiplexers and carries, and their forms with a single AND
+        rewrite!("discover-mux"; "(or (and ?s ?a) (andnot ?b ?s))" => "(mux ?s ?a ?b)"),
+        rewrite!("discover-mux-xor"; "(xor ?b (and ?s (xor ?a ?b)))" => "(mux ?s ?a ?b)"),
+        rewrite!("discover-maj"; "(or (and ?a ?b) (and ?c (or ?a ?b)))" => "(maj ?a ?b ?c)"),
+        rewrite!("discover-maj-xor"; "(xor (and ?a ?b) (and ?c (xor ?a ?b)))" => "(maj ?a ?b ?c)"),
+        rewrite!("mux-to-and"; "(mux ?s ?a ?b)" => "(xor ?b (and ?s (xor ?a ?b)))"),
+        rewrite!("maj-to-and"; "(maj ?a ?b ?c)" => "(xor ?a (and (xor ?a ?b) (xor ?a ?c)))"),
     ]
 }
-
-struct GarbleCost;
-impl CostFunction<CircuitLang> for GarbleCost {
-    type Cost = f64;
-    fn cost<C>(&mut self, enode: &CircuitLang, mut costs: C) -> Self::Cost
-    where
-        C: FnMut(Id) -> Self::Cost,
-    {
-        // Cost function is AST size weighted by the cost of each operator
-        let op_cost = match enode {
-            CircuitLang::And(_) => 4.0,
-            CircuitLang::Or(_) => 4.0,
-            CircuitLang::Not(_) => 2.0,
-            CircuitLang::Xor(_) => 1.0,
-            CircuitLang::OrNot(_) => 4.0,
-            CircuitLang::Nor(_) => 4.0,
-            CircuitLang::Nand(_) => 4.0,
-            CircuitLang::AndNot(_) => 4.0,
-            CircuitLang::Xnor(_) => 4.0,
-            _ => 4.0,
-        };
-        enode.fold(op_cost, |sum, id| sum + costs(id))
-    }
-}
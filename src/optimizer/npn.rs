@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use crate::optimizer::MC_DATABASE;
+
+/// Truth table of a function of up to four variables. Bit `m` is the value on the
+/// assignment where variable `i` is bit `i` of `m`.
+pub type TruthTable = u16;
+
+/// Truth tables of the four variables.
+pub const VARIABLES: [TruthTable; 4] = [0xAAAA, 0xCCCC, 0xF0F0, 0xFF00];
+
+/// An XOR of signals in an [`XagImpl`]: bit 0 is the constant one, bits 1 to 4 are the
+/// variables and bit `5 + k` is the `k`-th AND gate.
+pub type Affine = u16;
+
+/// A row of `MC_DATABASE`: a class representative, then the `ands` and `output` of its
+/// [`XagImpl`].
+pub type McEntry = (TruthTable, &'static [(Affine, Affine)], Affine);
+
+const VARIABLE_BITS: Affine = 0b11110;
+
+/// An XOR-AND circuit over four variables. AND gate `k` multiplies two affine
+/// combinations of the variables and the gates before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XagImpl {
+    pub ands: Vec<(Affine, Affine)>,
+    pub output: Affine,
+}
+
+impl XagImpl {
+    pub fn and_count(&self) -> usize {
+        self.ands.len()
+    }
+
+    pub fn eval(&self) -> TruthTable {
+        let mut signals = vec![0xFFFF];
+        signals.extend(VARIABLES);
+        for &(a, b) in self.ands.iter() {
+            let and = eval_affine(a, &signals) & eval_affine(b, &signals);
+            signals.push(and);
+        }
+        eval_affine(self.output, &signals)
+    }
+
+    /// Replaces variable `i` with the affine function `variables[i]` of the variables.
+    fn substitute(&self, variables: [Affine; 4]) -> Self {
+        let substitute = |affine: Affine| {
+            (0..4)
+                .filter(|i| affine >> (i + 1) & 1 == 1)
+                .fold(affine & !VARIABLE_BITS, |acc, i| acc ^ variables[i])
+        };
+        Self {
+            ands: self
+                .ands
+                .iter()
+                .map(|&(a, b)| (substitute(a), substitute(b)))
+                .collect(),
+            output: substitute(self.output),
+        }
+    }
+}
+
+pub fn eval_affine(affine: Affine, signals: &[TruthTable]) -> TruthTable {
+    signals
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| affine >> i & 1 == 1)
+        .fold(0, |acc, (_, signal)| acc ^ signal)
+}
+
+/// Maps a function onto its NPN class representative: `canonical(x) = f(y) ^ output`,
+/// where `y[permutation[i]] = x[i] ^ negations[i]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NpnTransform {
+    pub permutation: [usize; 4],
+    pub negations: u8,
+    pub output: bool,
+}
+
+impl NpnTransform {
+    pub fn apply(&self, table: TruthTable) -> TruthTable {
+        (0..16).fold(0, |acc, x: usize| {
+            let y = (0..4)
+                .filter(|&i| (x >> i & 1) as u8 ^ (self.negations >> i & 1) == 1)
+                .fold(0, |y, i| y | 1 << self.permutation[i]);
+            let bit = (table >> y & 1 == 1) ^ self.output;
+            acc | (bit as u16) << x
+        })
+    }
+
+    /// Turns an implementation of the representative into one of the original function.
+    pub fn undo(&self, canonical: &XagImpl) -> XagImpl {
+        let mut variables = [0; 4];
+        for (i, variable) in variables.iter_mut().enumerate() {
+            *variable = 1 << (self.permutation[i] + 1) | (self.negations >> i & 1) as Affine;
+        }
+        let mut implementation = canonical.substitute(variables);
+        implementation.output ^= self.output as Affine;
+        implementation
+    }
+}
+
+fn permutations() -> Vec<[usize; 4]> {
+    let mut permutations = Vec::with_capacity(24);
+    for a in 0..4 {
+        for b in (0..4).filter(|&b| b != a) {
+            for c in (0..4).filter(|&c| c != a && c != b) {
+                permutations.push([a, b, c, 6 - a - b - c]);
+            }
+        }
+    }
+    permutations
+}
+
+/// The smallest truth table in the NPN class of `table`, and the transform reaching it.
+pub fn npn_canonical(table: TruthTable) -> (TruthTable, NpnTransform) {
+    let mut best: Option<(TruthTable, NpnTransform)> = None;
+    for permutation in permutations() {
+        for negations in 0..16 {
+            for output in [false, true] {
+                let transform = NpnTransform {
+                    permutation,
+                    negations,
+                    output,
+                };
+                let canonical = transform.apply(table);
+                if best.is_none_or(|(smallest, _)| canonical < smallest) {
+                    best = Some((canonical, transform));
+                }
+            }
+        }
+    }
+    best.unwrap()
+}
+
+/// Minimum-AND implementations of every four-input function, looked up through the
+/// NPN class table in `MC_DATABASE`. Canonicalization results are memoized, since a
+/// circuit tends to repeat the same few functions.
+pub struct McDatabase {
+    classes: HashMap<TruthTable, XagImpl>,
+    memo: HashMap<TruthTable, XagImpl>,
+}
+
+impl Default for McDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl McDatabase {
+    pub fn new() -> Self {
+        let classes = MC_DATABASE
+            .iter()
+            .map(|&(table, ands, output)| {
+                let implementation = XagImpl {
+                    ands: ands.to_vec(),
+                    output,
+                };
+                (table, implementation)
+            })
+            .collect();
+        Self {
+            classes,
+            memo: HashMap::new(),
+        }
+    }
+
+    /// Number of NPN classes in the table.
+    pub fn classes(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// A minimum-AND implementation of `table`, over the variables in `VARIABLES`.
+    pub fn lookup(&mut self, table: TruthTable) -> &XagImpl {
+        let classes = &self.classes;
+        self.memo.entry(table).or_insert_with(|| {
+            let (canonical, transform) = npn_canonical(table);
+            transform.undo(&classes[&canonical])
+        })
+    }
+}
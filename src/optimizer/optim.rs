@@ -1,430 +1,688 @@
 use std::{
-    collections::{BTreeMap, HashMap},
-    rc::Rc,
-    sync::Mutex,
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    time::Instant,
 };
 
-use egg::{Extractor, Id, RecExpr, Runner};
-use indicatif::{ProgressBar, ProgressStyle};
+use egg::{CostFunction, EGraph, Extractor, Id, Language, RecExpr, Rewrite, Runner};
+use log::{debug, warn};
 use rayon::prelude::*;
 
 use crate::{
+    ir::{IndexedCircuit, WireId},
     optimizer::CircuitLang,
     utils::{Circuit, GateType},
 };
 
-use super::{circuit_rules, GarbleCost};
+use super::{
+    add_xag, cache_key, check_rules, circuit_cost, cleanup, constant_rules, gate_counts, keep_best,
+    lift_expr, load_cached, nonfree_gates, partition_circuit, store_cached, to_xag, with_analysis,
+    xag_lift_rules, CircuitAnalysis, CostModel, DagExtractor, Extraction, GarbleCost, Lowering,
+    NoProgress, Normalization, OptimizationReport, OptimizerConfig, OutputStats, Partition, Phase,
+    PhaseTimes, Progress, RuleError, RuleSet, SaturationMode, StopReason, Sweeping,
+};
 
-#[derive(Debug)]
-enum GateOutputStructure {
-    Gate(GateType, Vec<Rc<GateMapping>>),
+pub fn optimize(circuit: Circuit, outputs: Vec<String>) -> Circuit {
+    let (optimized, _report) = optimize_with_config(circuit, outputs, &OptimizerConfig::default())
+        .expect("Built-in rules are sound");
+    optimized
 }
 
-#[derive(Debug)]
-struct GateMapping {
-    mapping: BTreeMap<String, Option<Rc<Box<GateOutputStructure>>>>,
+/// Optimizes with a caller-supplied rule set. Every rule is proven sound first, and
+/// the circuit is left untouched if any of them is not.
+pub fn optimize_with_rules(
+    circuit: Circuit,
+    outputs: Vec<String>,
+    rules: &[Rewrite<CircuitLang, ()>],
+) -> Result<Circuit, RuleError> {
+    let config = OptimizerConfig {
+        rules: RuleSet::Only(rules.to_vec()),
+        ..Default::default()
+    };
+    optimize_with_config(circuit, outputs, &config).map(|(optimized, _report)| optimized)
 }
 
-pub fn optimize(circuit: Circuit, outputs: Vec<String>) -> Circuit {
-    let mut output_to_expr: HashMap<String, RecExpr<CircuitLang>> = HashMap::new();
-
-    let pb = ProgressBar::new(outputs.len() as u64);
-    let pb_style = ProgressStyle::default_bar()
-        .template("{msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len}")
-        .unwrap()
-        .progress_chars("#>-");
-    pb.set_style(pb_style.clone());
-    pb.set_message("Traversing outputs...");
-
-    let memo = Mutex::new(HashMap::new());
-
-    for wire_name in outputs.iter() {
-        let circuit_structure = traverse_wire(wire_name, &circuit, &memo);
-        let expr = gates_to_expr(&circuit_structure);
-        output_to_expr.insert(wire_name.clone(), expr);
-        pb.inc(1);
+/// Optimizes `circuit` with the given runner limits, scheduler, rules and parallelism,
+/// returning the new circuit together with per-output saturation statistics. Unless
+/// `config.keep_best` is off, the result never costs more to garble than the input.
+pub fn optimize_with_config(
+    circuit: Circuit,
+    outputs: Vec<String>,
+    config: &OptimizerConfig,
+) -> Result<(Circuit, OptimizationReport), RuleError> {
+    let rules = config.rules.rules(config.normalization);
+    check_rules(&rules)?;
+    let cache = config
+        .cache
+        .as_ref()
+        .map(|dir| (dir, cache_key(&circuit, &outputs, &rules, config)));
+    if let Some((dir, key)) = &cache {
+        if let Some((cached, report)) = load_cached(dir, key) {
+            debug!("Read optimized circuit from cache entry {}", key);
+            return Ok((
+                cached,
+                OptimizationReport {
+                    cache_hit: true,
+                    ..report
+                },
+            ));
+        }
+    }
+    let mut rules = with_analysis(&rules);
+    if config.sweeping != Sweeping::Off {
+        rules.extend(constant_rules());
     }
+    let start = Instant::now();
+    let model = config.scheme.cost_model();
+
+    let original_gates = circuit.len();
+    let original_gate_counts = gate_counts(&circuit);
+    let original_nonfree = nonfree_gates(&circuit, model.as_ref());
+    let original_depth = IndexedCircuit::from_circuit(&circuit).and_depth();
+    let mut circuit = circuit;
+    let cleanup_start = Instant::now();
+    let cleanup_before = cleanup(&mut circuit, &outputs);
+    let mut cleanup_time = cleanup_start.elapsed();
+    debug!("Cleanup before saturation: {:?}", cleanup_before);
 
-    pb.finish_with_message("Finished traversing outputs.");
+    let (mut new_circuit, stats, windows, mut phases) = match config.partition {
+        Partition::None => {
+            let (new_circuit, stats, phases) =
+                saturate_circuit(&circuit, &outputs, &rules, config, config.progress.as_ref());
+            (new_circuit, stats, 1, phases)
+        }
+        partition => saturate_windows(&circuit, &outputs, partition, &rules, config),
+    };
+    let cleanup_start = Instant::now();
+    let cleanup_after = cleanup(&mut new_circuit, &outputs);
+    debug!("Cleanup after saturation: {:?}", cleanup_after);
+    let original_cost = circuit_cost(&circuit, &outputs, model.as_ref());
+    let (new_circuit, kept, optimized_cost) = keep_best(
+        &circuit,
+        new_circuit,
+        &outputs,
+        config.keep_best,
+        model.as_ref(),
+    );
+    debug!("Kept {:?}", kept);
+    cleanup_time += cleanup_start.elapsed();
+    phases.cleanup = cleanup_time;
+    phases.total = start.elapsed();
 
-    if output_to_expr.len() != outputs.len() {
-        panic!("Mismatch between number of outputs and number of gates");
+    let report = OptimizationReport {
+        original_gates,
+        optimized_gates: new_circuit.len(),
+        original_gate_counts,
+        optimized_gate_counts: gate_counts(&new_circuit),
+        original_nonfree,
+        optimized_nonfree: nonfree_gates(&new_circuit, model.as_ref()),
+        original_depth,
+        optimized_depth: IndexedCircuit::from_circuit(&new_circuit).and_depth(),
+        original_cost,
+        optimized_cost,
+        kept,
+        cleanup_before,
+        cleanup_after,
+        windows,
+        outputs: stats,
+        phases,
+        cache_hit: false,
+    };
+    debug!("Orig circuit len: {}", report.original_gates);
+    debug!("New circuit len: {}", report.optimized_gates);
+    debug!("Orig AND depth: {}", report.original_depth);
+    debug!("New AND depth: {}", report.optimized_depth);
+    debug!("Phases: {:?}", report.phases);
+    if report.outputs_hitting_limits() > 0 {
+        warn!(
+            "{} of {} outputs stopped before saturating",
+            report.outputs_hitting_limits(),
+            report.outputs.len()
+        );
+    }
+    if let Some((dir, key)) = &cache {
+        store_cached(dir, key, &new_circuit, &report);
+    }
+    Ok((new_circuit, report))
+}
+
+/// Lowers every output into its own expression, or all of them into one e-graph, and
+/// saturates them as `config.mode` says.
+fn saturate_circuit(
+    circuit: &Circuit,
+    outputs: &[String],
+    rules: &[Rewrite<CircuitLang, CircuitAnalysis>],
+    config: &OptimizerConfig,
+    progress: &dyn Progress,
+) -> (Circuit, Vec<OutputStats>, PhaseTimes) {
+    let indexed = IndexedCircuit::from_circuit(circuit);
+    let ids = indexed
+        .name_table()
+        .expect("Lowered circuits keep their names");
+    match config.mode {
+        SaturationMode::PerOutput => {
+            let start = Instant::now();
+            progress.phase_started(Phase::Traverse, outputs.len());
+            let output_to_expr: Vec<(String, RecExpr<CircuitLang>)> = in_pool(config, || {
+                outputs
+                    .par_iter()
+                    .map(|output| {
+                        let expr = cone_expr(&indexed, &ids, output);
+                        progress.output_done(Phase::Traverse);
+                        (output.clone(), expr)
+                    })
+                    .collect()
+            });
+            progress.phase_finished(Phase::Traverse);
+            let traverse = start.elapsed();
+            let (new_circuit, stats, phases) =
+                saturate_per_output(&output_to_expr, rules, config, progress);
+            (new_circuit, stats, PhaseTimes { traverse, ..phases })
+        }
+        SaturationMode::Shared => saturate_shared(&indexed, &ids, outputs, rules, config, progress),
+    }
+}
+
+/// Cuts the circuit into windows with `partition_circuit`, saturates each one with
+/// `saturate_circuit` and stitches the results back together. Window outputs keep
+/// their names, so windows connect as before, and the fresh wires inside each window
+/// get a `w<window>_` prefix.
+fn saturate_windows(
+    circuit: &Circuit,
+    outputs: &[String],
+    partition: Partition,
+    rules: &[Rewrite<CircuitLang, CircuitAnalysis>],
+    config: &OptimizerConfig,
+) -> (Circuit, Vec<OutputStats>, usize, PhaseTimes) {
+    let windows = partition_circuit(circuit, outputs, partition);
+    debug!(
+        "Partitioned {} gates into {} windows",
+        circuit.len(),
+        windows.len()
+    );
+
+    let mut new_circuit: Circuit = circuit
+        .iter()
+        .filter(|(_, (gate_type, _))| *gate_type == GateType::INPUT)
+        .map(|(wire, gate)| (wire.clone(), gate.clone()))
+        .collect();
+    let mut stats = Vec::new();
+    let mut phases = PhaseTimes::default();
+    config.progress.phase_started(Phase::Windows, windows.len());
+    for (i, window) in windows.iter().enumerate() {
+        let (optimized, window_stats, window_phases) = saturate_circuit(
+            &window.circuit(circuit),
+            &window.outputs,
+            rules,
+            config,
+            &NoProgress,
+        );
+        let start = Instant::now();
+        let kept: HashSet<&String> = window.inputs.iter().chain(&window.outputs).collect();
+        let rename = |wire: &String| -> String {
+            if kept.contains(wire) {
+                return wire.clone();
+            }
+            let mut renamed = format!("w{}_{}", i, wire);
+            while circuit.contains_key(&renamed) {
+                renamed.insert(0, 'w');
+            }
+            renamed
+        };
+        for (wire, (gate_type, inputs)) in optimized {
+            if gate_type == GateType::INPUT {
+                continue;
+            }
+            let inputs = inputs.iter().map(rename).collect();
+            new_circuit.insert(rename(&wire), (gate_type, inputs));
+        }
+        stats.extend(window_stats);
+        phases += window_phases;
+        phases.rebuild += start.elapsed();
+        config.progress.output_done(Phase::Windows);
     }
+    config.progress.phase_finished(Phase::Windows);
+    (new_circuit, stats, windows.len(), phases)
+}
 
+/// Saturates every output in its own runner, in parallel, and merges the extracted
+/// expressions back into one circuit.
+fn saturate_per_output(
+    output_to_expr: &[(String, RecExpr<CircuitLang>)],
+    rules: &[Rewrite<CircuitLang, CircuitAnalysis>],
+    config: &OptimizerConfig,
+    progress: &dyn Progress,
+) -> (Circuit, Vec<OutputStats>, PhaseTimes) {
     let mut new_circuit: Circuit = Circuit::new();
     let mut wire_counter: usize = 0;
 
     let mut existing_gates: HashMap<String, String> = HashMap::new();
 
-    let pb = ProgressBar::new(output_to_expr.len() as u64);
-    pb.set_style(pb_style.clone());
-    pb.set_message("Simplifying expressions...");
+    progress.phase_started(Phase::Saturate, output_to_expr.len());
 
-    let simplified_exprs = output_to_expr
-        .par_iter_mut()
-        .map(|(output_name, expr)| {
-            let simplified = simplify(expr);
-            pb.inc(1);
-            return (output_name, simplified);
-        })
-        .collect::<Vec<(&String, RecExpr<CircuitLang>)>>();
-
-    pb.finish_with_message("Simplified expressions.");
-    let pb = ProgressBar::new(simplified_exprs.len() as u64);
-    pb.set_style(pb_style);
-    pb.set_message("Building circuit...");
-    for (output_name, expr) in simplified_exprs {
-        build_circuit(
-            expr,
+    let saturate = || {
+        output_to_expr
+            .par_iter()
+            .map(|(output_name, expr)| {
+                let (simplified, stats, phases) = simplify(output_name, expr, rules, config);
+                progress.output_done(Phase::Saturate);
+                (output_name, simplified, stats, phases)
+            })
+            .collect::<Vec<(&String, RecExpr<CircuitLang>, OutputStats, PhaseTimes)>>()
+    };
+    let simplified_exprs = in_pool(config, saturate);
+
+    progress.phase_finished(Phase::Saturate);
+    progress.phase_started(Phase::Rebuild, simplified_exprs.len());
+    let start = Instant::now();
+    let mut outputs = Vec::with_capacity(simplified_exprs.len());
+    let mut phases = PhaseTimes::default();
+    for (output_name, expr, stats, output_phases) in simplified_exprs {
+        phases += output_phases;
+        lift_expr(
+            &expr,
+            output_name,
             &mut new_circuit,
             &mut existing_gates,
             &mut wire_counter,
-            output_name.to_string(),
         );
-        pb.inc(1);
+        outputs.push(stats);
+        progress.output_done(Phase::Rebuild);
     }
 
-    pb.finish();
+    progress.phase_finished(Phase::Rebuild);
+    phases.rebuild = start.elapsed();
+    (new_circuit, outputs, phases)
+}
 
-    println!("Orig circuit len: {}", circuit.keys().len());
-    println!("New circuit len: {}", new_circuit.keys().len());
-    new_circuit
+/// Saturates all outputs as roots of one e-graph and extracts them together, so every
+/// e-class reachable from more than one output becomes a single gate.
+///
+/// The runner statistics are shared, so every output reports the same iterations, sizes,
+/// stop reason and time; only the costs are per output.
+fn saturate_shared(
+    circuit: &IndexedCircuit,
+    ids: &HashMap<&str, WireId>,
+    outputs: &[String],
+    rules: &[Rewrite<CircuitLang, CircuitAnalysis>],
+    config: &OptimizerConfig,
+    progress: &dyn Progress,
+) -> (Circuit, Vec<OutputStats>, PhaseTimes) {
+    let start = Instant::now();
+    let mut phases = PhaseTimes::default();
+    let model = config.scheme.cost_model();
+    let model = model.as_ref();
+    let mut runner = config.runner_with(CircuitAnalysis::new(config.sweeping));
 
-    // TODO: if no optimizations found, we just return the original circuit
-}
+    // Outputs are lowered straight into the e-graph, so logic they share is added once
+    progress.phase_started(Phase::Traverse, outputs.len());
+    let mut lowering = Lowering::new(circuit, ids);
+    for output in outputs {
+        let egraph = &mut runner.egraph;
+        let root = match config.normalization {
+            Normalization::None => lowering.lower(output, |node| egraph.add(node)),
+            Normalization::Xag => {
+                lowering.lower(output, |node| add_xag(node, &mut |node| egraph.add(node)))
+            }
+        };
+        runner.roots.push(root);
+        progress.output_done(Phase::Traverse);
+    }
+    progress.phase_finished(Phase::Traverse);
+    // Costs of the unoptimized cones, measured as in `saturate_per_output`
+    let initial_costs: Vec<f64> = in_pool(config, || {
+        outputs
+            .par_iter()
+            .map(|output| initial_cost(&cone_expr(circuit, ids, output), config))
+            .collect()
+    });
+    phases.traverse = start.elapsed();
 
-fn add_or_reuse_gate(
-    circuit: &mut Circuit,
-    gate_type: GateType,
-    inputs: &Vec<String>,
-    existing_gates: &mut HashMap<String, String>,
-    wire_counter: &mut usize,
-    output_wire: &Option<String>,
-) -> String {
-    if let Some(output_wire) = output_wire {
-        circuit.insert(output_wire.clone(), (gate_type, inputs.to_vec()));
-        return output_wire.to_string();
+    progress.phase_started(Phase::Saturate, outputs.len());
+    debug!(
+        "Shared e-graph starts with {} nodes for {} outputs",
+        runner.egraph.total_number_of_nodes(),
+        outputs.len()
+    );
+    let saturate_start = Instant::now();
+    let runner = lift(runner.run(rules), config);
+    phases.saturate = saturate_start.elapsed();
+    let egraph = &runner.egraph;
+    let roots: Vec<(String, Id)> = outputs
+        .iter()
+        .zip(runner.roots.iter())
+        .map(|(output_name, &root)| (output_name.clone(), egraph.find(root)))
+        .collect();
+    let (new_circuit, best_costs): (Circuit, Vec<f64>) = match config.extraction {
+        Extraction::Tree => {
+            // Tree costs ignore sharing, so many classes have several equally cheap
+            // nodes. Break those ties towards nodes whose children are also used
+            // elsewhere in the plain extraction, so that outputs agree on how shared
+            // logic is built.
+            let extract_start = Instant::now();
+            let extractor = Extractor::new(egraph, GarbleCost(model));
+            let references = count_references(|id| extractor.find_best_node(id).clone(), &roots);
+            phases.extract = extract_start.elapsed();
+            let rebuild_start = Instant::now();
+            let new_circuit = build_shared_circuit(
+                |id, active| {
+                    let best_cost = extractor.find_best_cost(id);
+                    let tied = egraph[id].iter().filter(|node| {
+                        let cost =
+                            GarbleCost(model).cost(node, |child| extractor.find_best_cost(child));
+                        is_tie(cost, best_cost)
+                    });
+                    prefer_shared(id, tied, extractor.find_best_node(id), &references, active)
+                },
+                &roots,
+            );
+            phases.rebuild = rebuild_start.elapsed();
+            let best_costs = roots
+                .iter()
+                .map(|(_, root)| extractor.find_best_cost(*root))
+                .collect();
+            (new_circuit, best_costs)
+        }
+        extraction => {
+            let root_ids: Vec<Id> = roots.iter().map(|(_, root)| *root).collect();
+            let extract_start = Instant::now();
+            let extractor = dag_extractor(egraph, model, extraction, &root_ids);
+            phases.extract = extract_start.elapsed();
+            let rebuild_start = Instant::now();
+            let new_circuit =
+                build_shared_circuit(|id, _| extractor.find_best_node(id).clone(), &roots);
+            phases.rebuild = rebuild_start.elapsed();
+            let best_costs = roots
+                .iter()
+                .map(|(_, root)| extractor.find_best_cost(*root))
+                .collect();
+            (new_circuit, best_costs)
+        }
+    };
+
+    let time = saturate_start.elapsed();
+    let stats = outputs
+        .iter()
+        .zip(initial_costs)
+        .zip(best_costs)
+        .map(|((output_name, initial_cost), best_cost)| OutputStats {
+            output: output_name.clone(),
+            iterations: runner.iterations.len(),
+            egraph_nodes: egraph.total_number_of_nodes(),
+            egraph_classes: egraph.number_of_classes(),
+            stop_reason: runner.stop_reason.as_ref().map(StopReason::from),
+            initial_cost,
+            best_cost,
+            time,
+        })
+        .collect();
+    for _ in outputs {
+        progress.output_done(Phase::Saturate);
     }
+    progress.phase_finished(Phase::Saturate);
+    (new_circuit, stats, phases)
+}
 
-    let gate_key = format!("{}_{:?}", gate_type, inputs);
+/// Tree costs of long carry chains exceed f64 precision, so costs are compared with a
+/// relative tolerance.
+fn is_tie(cost: f64, best_cost: f64) -> bool {
+    cost <= best_cost * (1.0 + 1e-9)
+}
 
-    if let Some(output_wire) = existing_gates.get(&gate_key) {
-        return output_wire.clone();
+/// Among the equally cheap `tied` nodes of class `id`, picks the one with the most
+/// children that other classes read as well, keeping `best` unless another node wins
+/// outright. Nodes reading `id` or a class in `active` would close a cycle.
+fn prefer_shared<'a>(
+    id: Id,
+    tied: impl Iterator<Item = &'a CircuitLang>,
+    best: &'a CircuitLang,
+    references: &HashMap<Id, usize>,
+    active: &HashSet<Id>,
+) -> CircuitLang {
+    let shared_children = |node: &CircuitLang| -> usize {
+        node.children()
+            .iter()
+            .filter(|child| {
+                let own = best.children().contains(child) as usize;
+                references.get(child).copied().unwrap_or(0) > own
+            })
+            .count()
+    };
+    let acyclic = |node: &CircuitLang| {
+        node.children()
+            .iter()
+            .all(|child| *child != id && !active.contains(child))
+    };
+    let mut choice = best;
+    for node in tied {
+        if acyclic(node) && shared_children(node) > shared_children(choice) {
+            choice = node;
+        }
     }
-    let output_wire = format!("i_{}", wire_counter.to_string());
-    *wire_counter += 1;
-    circuit.insert(output_wire.clone(), (gate_type, inputs.to_vec()));
-    existing_gates.insert(gate_key, output_wire.clone());
-    output_wire
+    choice.clone()
 }
 
-fn build_circuit(
-    expr: RecExpr<CircuitLang>,
-    global_circuit: &mut Circuit,
-    existing_gates: &mut HashMap<String, String>,
-    wire_counter: &mut usize,
-    output_name: String,
-) {
-    let rexpr = expr.as_ref();
-    let mut stack: Vec<String> = vec![];
-    for e in rexpr.iter() {
-        let is_last = rexpr.last() == Some(e);
-        let output_wire = if is_last {
-            Some(output_name.clone())
-        } else {
-            None
-        };
-        match e {
-            CircuitLang::And(ids) => {
-                let mut inputs: Vec<String> = vec![];
-                for id in ids.iter() {
-                    let wire = &stack[usize::from(*id)];
-                    inputs.push(wire.to_string());
-                }
-                let output_wire = add_or_reuse_gate(
-                    global_circuit,
-                    GateType::AND,
-                    &inputs,
-                    existing_gates,
-                    wire_counter,
-                    &output_wire,
-                );
-                stack.push(output_wire);
+/// Number of distinct classes whose chosen node reads each class, over every class
+/// reachable from `roots`.
+fn count_references(
+    choose: impl Fn(Id) -> CircuitLang,
+    roots: &[(String, Id)],
+) -> HashMap<Id, usize> {
+    let mut references: HashMap<Id, usize> = HashMap::new();
+    let mut seen: HashSet<Id> = roots.iter().map(|(_, root)| *root).collect();
+    let mut stack: Vec<Id> = seen.iter().copied().collect();
+    while let Some(id) = stack.pop() {
+        let node = choose(id);
+        let children: HashSet<Id> = node.children().iter().copied().collect();
+        for child in children {
+            *references.entry(child).or_insert(0) += 1;
+            if seen.insert(child) {
+                stack.push(child);
             }
-            CircuitLang::Or(ids) => {
-                let mut inputs: Vec<String> = vec![];
-                for id in ids.iter() {
-                    let wire = &stack[usize::from(*id)];
-                    inputs.push(wire.to_string());
-                }
-                let output_wire = add_or_reuse_gate(
-                    global_circuit,
-                    GateType::OR,
-                    &inputs,
-                    existing_gates,
-                    wire_counter,
-                    &output_wire,
-                );
-                stack.push(output_wire);
-            }
-            CircuitLang::Not(id) => {
-                let mut inputs: Vec<String> = vec![];
-                let wire = &stack[usize::from(*id)];
-                inputs.push(wire.to_string());
-                let output_wire = add_or_reuse_gate(
-                    global_circuit,
-                    GateType::NOT,
-                    &inputs,
-                    existing_gates,
-                    wire_counter,
-                    &output_wire,
-                );
-                stack.push(output_wire);
-            }
-            CircuitLang::Xor(ids) => {
-                let mut inputs: Vec<String> = vec![];
-                for id in ids.iter() {
-                    let wire = &stack[usize::from(*id)];
-                    inputs.push(wire.to_string());
-                }
-                let output_wire = add_or_reuse_gate(
-                    global_circuit,
-                    GateType::XOR,
-                    &inputs,
-                    existing_gates,
-                    wire_counter,
-                    &output_wire,
-                );
-                stack.push(output_wire);
-            }
-            CircuitLang::OrNot(ids) => {
-                let mut inputs: Vec<String> = vec![];
-                for id in ids.iter() {
-                    let wire = &stack[usize::from(*id)];
-                    inputs.push(wire.to_string());
-                }
-                let output_wire = add_or_reuse_gate(
-                    global_circuit,
-                    GateType::ORNOT,
-                    &inputs,
-                    existing_gates,
-                    wire_counter,
-                    &output_wire,
-                );
-                stack.push(output_wire);
+        }
+    }
+    references
+}
+
+/// Rebuilds a circuit from one e-node per e-class, emitting one gate per reachable class.
+/// `choose` is called once per class, the first time the class is reached, together with
+/// the classes on the path to it, which its node must not read. Root classes
+/// are named after their output and the remaining classes get fresh `i_<n>` wires.
+/// Outputs that alias an input or another output get a `CONST` buffer, as in
+/// `lift_expr`.
+fn build_shared_circuit(
+    mut choose: impl FnMut(Id, &HashSet<Id>) -> CircuitLang,
+    roots: &[(String, Id)],
+) -> Circuit {
+    let mut circuit = Circuit::new();
+    let mut wires: HashMap<Id, String> = HashMap::new();
+    let mut choices: HashMap<Id, CircuitLang> = HashMap::new();
+    let mut wire_counter: usize = 0;
+    let mut root_names: HashMap<Id, &String> = HashMap::new();
+    for (output_name, root) in roots {
+        root_names.entry(*root).or_insert(output_name);
+    }
+
+    let mut done: HashSet<Id> = HashSet::new();
+    // Classes whose children are still being emitted
+    let mut active: HashSet<Id> = HashSet::new();
+    for (_, root) in roots {
+        // Post-order walk: a class is emitted once all of its children have wires.
+        let mut stack: Vec<(Id, bool)> = vec![(*root, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if done.contains(&id) {
+                continue;
             }
-            CircuitLang::Nor(ids) => {
-                let mut inputs: Vec<String> = vec![];
-                for id in ids.iter() {
-                    let wire = &stack[usize::from(*id)];
-                    inputs.push(wire.to_string());
-                }
-                let output_wire = add_or_reuse_gate(
-                    global_circuit,
-                    GateType::NOR,
-                    &inputs,
-                    existing_gates,
-                    wire_counter,
-                    &output_wire,
-                );
-                stack.push(output_wire);
+            if !expanded && active.contains(&id) {
+                panic!("Extraction chose a cyclic term");
             }
-            CircuitLang::Nand(ids) => {
-                let mut inputs: Vec<String> = vec![];
-                for id in ids.iter() {
-                    let wire = &stack[usize::from(*id)];
-                    inputs.push(wire.to_string());
-                }
-                let output_wire = add_or_reuse_gate(
-                    global_circuit,
-                    GateType::NAND,
-                    &inputs,
-                    existing_gates,
-                    wire_counter,
-                    &output_wire,
-                );
-                stack.push(output_wire);
+            let node = choices
+                .entry(id)
+                .or_insert_with(|| choose(id, &active))
+                .clone();
+            if !expanded {
+                active.insert(id);
+                stack.push((id, true));
+                stack.extend(node.children().iter().map(|&child| (child, false)));
+                continue;
             }
-            CircuitLang::AndNot(ids) => {
-                let mut inputs: Vec<String> = vec![];
-                for id in ids.iter() {
-                    let wire = &stack[usize::from(*id)];
-                    inputs.push(wire.to_string());
+            match &node {
+                CircuitLang::Wire(wire) => {
+                    circuit
+                        .entry(wire.clone())
+                        .or_insert((GateType::INPUT, Vec::new()));
+                    wires.insert(id, wire.clone());
                 }
-                let output_wire = add_or_reuse_gate(
-                    global_circuit,
-                    GateType::ANDNOT,
-                    &inputs,
-                    existing_gates,
-                    wire_counter,
-                    &output_wire,
-                );
-                stack.push(output_wire);
-            }
-            CircuitLang::Xnor(ids) => {
-                let mut inputs: Vec<String> = vec![];
-                for id in ids.iter() {
-                    let wire = &stack[usize::from(*id)];
-                    inputs.push(wire.to_string());
-                }
-                let output_wire = add_or_reuse_gate(
-                    global_circuit,
-                    GateType::XNOR,
-                    &inputs,
-                    existing_gates,
-                    wire_counter,
-                    &output_wire,
-                );
-                stack.push(output_wire);
-            }
-
-            CircuitLang::Wire(wire) => {
-                if let Some(output_wire) = output_wire {
-                    global_circuit.insert(
-                        output_wire.clone(),
-                        (GateType::CONST, vec![wire.to_string()]),
-                    );
-                    return;
+                _ => {
+                    let inputs: Vec<String> = node
+                        .children()
+                        .iter()
+                        .map(|child| wires[child].clone())
+                        .collect();
+                    let wire = match root_names.get(&id) {
+                        Some(output_name) => output_name.to_string(),
+                        None => {
+                            wire_counter += 1;
+                            format!("i_{}", wire_counter - 1)
+                        }
+                    };
+                    wires.insert(id, wire.clone());
+                    circuit.insert(wire, (node.gate_type().unwrap(), inputs));
                 }
-                global_circuit
-                    .entry(wire.to_string())
-                    .or_insert((GateType::INPUT, Vec::new()));
-                stack.push(wire.to_string());
             }
+            active.remove(&id);
+            done.insert(id);
         }
     }
-}
 
-fn traverse_wire(
-    output_wire: &str,
-    circuit: &Circuit,
-    memo: &Mutex<HashMap<String, Rc<GateMapping>>>,
-) -> Rc<GateMapping> {
-    let memo_lock = memo.lock().unwrap();
-    if let Some(result) = memo_lock.get(output_wire) {
-        return result.clone();
-    }
-    drop(memo_lock);
-
-    let gate = circuit.get(output_wire);
-    let result = match gate {
-        None => {
-            let mut mapping = BTreeMap::new();
-            mapping.insert(output_wire.to_string(), None);
-            Rc::new(GateMapping { mapping })
-        }
-        Some((gate_type, input_wires)) => {
-            let mut input_structures: Vec<Rc<GateMapping>> = Vec::new();
-            for input_wire in input_wires {
-                let input_structure = traverse_wire(input_wire, circuit, memo);
-                input_structures.push(input_structure);
-            }
-            let mut mapping = BTreeMap::new();
-            mapping.insert(
-                output_wire.to_string(),
-                Some(Rc::new(Box::new(GateOutputStructure::Gate(
-                    gate_type.clone(),
-                    input_structures,
-                )))),
+    for (output_name, root) in roots {
+        if &wires[root] != output_name {
+            circuit.insert(
+                output_name.clone(),
+                (GateType::CONST, vec![wires[root].clone()]),
             );
-            Rc::new(GateMapping { mapping })
         }
-    };
-
-    let mut memo_lock = memo.lock().unwrap();
-    memo_lock.insert(output_wire.to_string(), result.clone());
-    result
+    }
+    circuit
 }
 
-fn gates_to_expr(gate: &GateMapping) -> RecExpr<CircuitLang> {
+/// The expression of `output`'s cone, for `simplify`.
+fn cone_expr(
+    circuit: &IndexedCircuit,
+    ids: &HashMap<&str, WireId>,
+    output: &str,
+) -> RecExpr<CircuitLang> {
     let mut expr = RecExpr::default();
-    let mut cache = HashMap::new();
-    build_expr(&gate, &mut expr, &mut cache);
+    Lowering::new(circuit, ids).lower(output, |node| expr.add(node));
     expr
 }
 
-fn build_expr(
-    gate_mapping: &GateMapping,
-    expr: &mut RecExpr<CircuitLang>,
-    cache: &mut HashMap<String, Id>,
-) -> Id {
-    gate_mapping
-        .mapping
-        .iter()
-        .map(|(gate_id, gate_info)| {
-            if let Some(&cached_id) = cache.get(gate_id) {
-                return cached_id;
-            }
-            let result_id = match gate_info {
-                None => expr.add(CircuitLang::Wire(gate_id.clone())),
-                Some(gate_structure) => {
-                    let gos = gate_structure.as_ref();
-                    let GateOutputStructure::Gate(gate_type, inputs) = gos.as_ref();
-                    let input_exprs: Vec<Id> = inputs
-                        .iter()
-                        .map(|input_gate| build_expr(input_gate, expr, cache))
-                        .collect();
+/// Runs `f` on a pool of `config.threads` threads, or on rayon's global pool.
+fn in_pool<R: Send>(config: &OptimizerConfig, f: impl FnOnce() -> R + Send) -> R {
+    match config.threads {
+        Some(threads) => rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("Couldn't build thread pool")
+            .install(f),
+        None => f(),
+    }
+}
 
-                    match gate_type {
-                        GateType::INPUT => expr.add(CircuitLang::Wire(gate_id.clone())),
-                        GateType::CONST => expr.add(CircuitLang::Wire(gate_id.clone())),
-                        GateType::AND
-                        | GateType::OR
-                        | GateType::XOR
-                        | GateType::NOR
-                        | GateType::ORNOT
-                        | GateType::NAND
-                        | GateType::ANDNOT
-                        | GateType::XNOR => {
-                            let ids: [Id; 2] = input_exprs
-                                .try_into()
-                                .expect("Expected two inputs for binary gate");
-                            match gate_type {
-                                GateType::AND => expr.add(CircuitLang::And(ids)),
-                                GateType::OR => expr.add(CircuitLang::Or(ids)),
-                                GateType::XOR => expr.add(CircuitLang::Xor(ids)),
-                                GateType::NOR => expr.add(CircuitLang::Nor(ids)),
-                                GateType::ORNOT => expr.add(CircuitLang::OrNot(ids)),
-                                GateType::NAND => expr.add(CircuitLang::Nand(ids)),
-                                GateType::ANDNOT => expr.add(CircuitLang::AndNot(ids)),
-                                GateType::XNOR => expr.add(CircuitLang::Xnor(ids)),
-                                _ => unreachable!("Handled all binary gate types"),
-                            }
-                        }
-                        GateType::NOT => {
-                            let id = *input_exprs
-                                .first()
-                                .expect("Expected one input for unary gate");
-                            expr.add(CircuitLang::Not(id))
-                        }
-                        _ => unreachable!("All gate types handled"),
-                    }
-                }
-            };
-            cache.insert(gate_id.clone(), result_id);
-            result_id
-        })
-        .next()
-        .unwrap_or_else(|| panic!("Invalid gate mapping encountered."))
+fn simplify(
+    output_name: &str,
+    expr: &RecExpr<CircuitLang>,
+    rules: &[Rewrite<CircuitLang, CircuitAnalysis>],
+    config: &OptimizerConfig,
+) -> (RecExpr<CircuitLang>, OutputStats, PhaseTimes) {
+    let start = Instant::now();
+    let model = config.scheme.cost_model();
+    let model = model.as_ref();
+    let runner = config
+        .runner_with(CircuitAnalysis::new(config.sweeping))
+        .with_expr(&normalize(expr, config));
+    let runner = lift(runner.run(rules), config);
+    let root = runner.egraph.find(runner.roots[0]);
+    let saturate = start.elapsed();
+    let (best_cost, best) = match config.extraction {
+        Extraction::Tree => Extractor::new(&runner.egraph, GarbleCost(model)).find_best(root),
+        extraction => dag_extractor(&runner.egraph, model, extraction, &[root]).find_best(root),
+    };
+    let time = start.elapsed();
+    let phases = PhaseTimes {
+        saturate,
+        extract: time - saturate,
+        ..Default::default()
+    };
+    let stats = OutputStats {
+        output: output_name.to_string(),
+        iterations: runner.iterations.len(),
+        egraph_nodes: runner.egraph.total_number_of_nodes(),
+        egraph_classes: runner.egraph.number_of_classes(),
+        stop_reason: runner.stop_reason.as_ref().map(StopReason::from),
+        initial_cost: initial_cost(expr, config),
+        best_cost,
+        time,
+    };
+    (best, stats, phases)
 }
 
-fn simplify(expr: &RecExpr<CircuitLang>) -> RecExpr<CircuitLang> {
-    let runner = Runner::default()
-        .with_expr(&expr)
-        // .with_iter_limit(75)
-        // .with_node_limit(5_000_000)
-        // .with_time_limit(std::time::Duration::from_secs(30))
-        .run(&circuit_rules());
-    // println!(
-    //     "Stopped after {} iterations, reason: {:?}",
-    //     runner.iterations.len(),
-    //     runner.stop_reason
-    // );
-    let root = runner.roots[0];
-    let extractor = Extractor::new(&runner.egraph, GarbleCost);
-    let (best_cost, best) = extractor.find_best(root);
-    // if best.to_string() != *expr.to_string() {
-    //     println!("Simplified to cost {}", best_cost);
-    // }
-    best
+/// The expression a runner is seeded with for `config.normalization`.
+fn normalize<'a>(
+    expr: &'a RecExpr<CircuitLang>,
+    config: &OptimizerConfig,
+) -> Cow<'a, RecExpr<CircuitLang>> {
+    match config.normalization {
+        Normalization::None => Cow::Borrowed(expr),
+        Normalization::Xag => Cow::Owned(to_xag(expr)),
+    }
+}
+
+/// Undoes `normalize` on a saturated runner's e-graph. Roots must be canonicalized
+/// again afterwards.
+fn lift(
+    mut runner: Runner<CircuitLang, CircuitAnalysis>,
+    config: &OptimizerConfig,
+) -> Runner<CircuitLang, CircuitAnalysis> {
+    if config.normalization == Normalization::Xag {
+        // The lifting rules only fold nodes that are already there, so one iteration
+        // is enough and the node limit must not stop it before it starts.
+        let egraph = std::mem::take(&mut runner.egraph);
+        runner.egraph = Runner::default()
+            .with_egraph(egraph)
+            .with_iter_limit(1)
+            .with_node_limit(usize::MAX)
+            .with_time_limit(config.time_limit)
+            .run(&with_analysis(&xag_lift_rules()))
+            .egraph;
+    }
+    runner
+}
+
+/// Runs `DagExtractor` for a DAG or depth-bounded `extraction` of `roots`.
+fn dag_extractor<'a>(
+    egraph: &'a EGraph<CircuitLang, CircuitAnalysis>,
+    model: &'a dyn CostModel,
+    extraction: Extraction,
+    roots: &[Id],
+) -> DagExtractor<'a, CircuitAnalysis> {
+    let mut extractor = DagExtractor::new(egraph, model);
+    extractor.refine(roots);
+    if let Some(bound) = extraction.depth_bound() {
+        if !extractor.limit_depth(roots, bound) {
+            warn!("Depth repair did not settle, falling back to minimum-depth extraction");
+        }
+    }
+    extractor
+}
+
+/// Cost of an unoptimized expression under the metric that `extraction` minimizes.
+/// Expressions built by `cone_expr` hold every wire once, so summing the nodes counts
+/// shared gates once.
+fn initial_cost(expr: &RecExpr<CircuitLang>, config: &OptimizerConfig) -> f64 {
+    let model = config.scheme.cost_model();
+    match config.extraction {
+        Extraction::Tree => GarbleCost(model.as_ref()).cost_rec(expr),
+        _ => expr.as_ref().iter().map(|node| model.node_cost(node)).sum(),
+    }
 }
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+
+use crate::{
+    ir::{IndexedCircuit, WireId},
+    optimizer::Partition,
+    utils::{Circuit, GateType},
+};
+
+/// A piece of a circuit that is saturated on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    /// The window's gates, in topological order.
+    pub gates: Vec<String>,
+    /// Wires the window reads from the circuit's inputs or from earlier windows.
+    pub inputs: Vec<String>,
+    /// Gates of the window that later windows read or that are outputs of the circuit.
+    pub outputs: Vec<String>,
+}
+
+impl Window {
+    /// The window as a circuit of its own, with its inputs as `INPUT` wires.
+    pub fn circuit(&self, circuit: &Circuit) -> Circuit {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| (input.clone(), (GateType::INPUT, vec![])));
+        let gates = self
+            .gates
+            .iter()
+            .map(|gate| (gate.clone(), circuit[gate].clone()));
+        inputs.chain(gates).collect()
+    }
+}
+
+/// Cuts `circuit` into windows as `partition` describes. Windows only read the
+/// circuit's inputs and earlier windows, so they can be saturated in any order and
+/// stitched back together by wire name.
+pub fn partition_circuit(
+    circuit: &Circuit,
+    outputs: &[String],
+    partition: Partition,
+) -> Vec<Window> {
+    let indexed = IndexedCircuit::from_circuit(circuit);
+    let mut window_of: Vec<Option<usize>> = vec![None; indexed.num_wires()];
+    let gate_ids = indexed.gates.iter().map(|gate| gate.output);
+    match partition {
+        Partition::None => {
+            for id in gate_ids {
+                window_of[id.index()] = Some(0);
+            }
+        }
+        Partition::Levels(levels) => {
+            let levels = levels.max(1);
+            let mut level = vec![0usize; indexed.num_wires()];
+            for gate in indexed.gates.iter() {
+                let deepest = gate.inputs.iter().map(|input| level[input.index()]).max();
+                level[gate.output.index()] = deepest.unwrap_or(0) + 1;
+                window_of[gate.output.index()] = Some((level[gate.output.index()] - 1) / levels);
+            }
+        }
+        Partition::Gates(budget) => {
+            for (i, id) in gate_ids.enumerate() {
+                window_of[id.index()] = Some(i / budget.max(1));
+            }
+        }
+        Partition::Cones(budget) => {
+            let budget = budget.max(1);
+            let names = indexed.name_table().unwrap();
+            let mut is_gate = vec![false; indexed.num_wires()];
+            for id in gate_ids {
+                is_gate[id.index()] = true;
+            }
+            let mut windows = 0;
+            let mut current = 0;
+            for output in outputs.iter() {
+                // Gates of the output's cone that no earlier cone claimed
+                let mut cone: Vec<WireId> = Vec::new();
+                let mut seen: HashSet<WireId> = HashSet::new();
+                let mut stack = vec![names[output.as_str()]];
+                while let Some(wire) = stack.pop() {
+                    if !is_gate[wire.index()]
+                        || window_of[wire.index()].is_some()
+                        || !seen.insert(wire)
+                    {
+                        continue;
+                    }
+                    cone.push(wire);
+                    let gate = &indexed.gates[wire.index() - indexed.inputs.len()];
+                    stack.extend(gate.inputs.iter());
+                }
+                if cone.is_empty() {
+                    continue;
+                }
+                cone.sort();
+
+                if current > 0 && current + cone.len() > budget {
+                    windows += 1;
+                    current = 0;
+                }
+                for chunk in cone.chunks(budget) {
+                    if current + chunk.len() > budget {
+                        windows += 1;
+                        current = 0;
+                    }
+                    for wire in chunk {
+                        window_of[wire.index()] = Some(windows);
+                    }
+                    current += chunk.len();
+                }
+            }
+        }
+    }
+    windows(&indexed, &window_of, outputs)
+}
+
+/// Turns a window number per wire into windows, dropping empty windows.
+fn windows(
+    indexed: &IndexedCircuit,
+    window_of: &[Option<usize>],
+    outputs: &[String],
+) -> Vec<Window> {
+    let count = window_of.iter().flatten().max().map_or(0, |&last| last + 1);
+    let mut windows: Vec<Window> = vec![
+        Window {
+            gates: vec![],
+            inputs: vec![],
+            outputs: vec![],
+        };
+        count
+    ];
+    let mut read_elsewhere = vec![false; indexed.num_wires()];
+    let mut inputs: Vec<HashSet<WireId>> = vec![HashSet::new(); count];
+    for gate in indexed.gates.iter() {
+        let Some(window) = window_of[gate.output.index()] else {
+            continue;
+        };
+        windows[window].gates.push(indexed.name(gate.output));
+        for &input in gate.inputs.iter() {
+            if window_of[input.index()] != Some(window) {
+                read_elsewhere[input.index()] = true;
+                if inputs[window].insert(input) {
+                    windows[window].inputs.push(indexed.name(input));
+                }
+            }
+        }
+    }
+
+    let outputs: HashSet<&String> = outputs.iter().collect();
+    for gate in indexed.gates.iter() {
+        let Some(window) = window_of[gate.output.index()] else {
+            continue;
+        };
+        let name = indexed.name(gate.output);
+        if read_elsewhere[gate.output.index()] || outputs.contains(&name) {
+            windows[window].outputs.push(name);
+        }
+    }
+    windows.retain(|window| !window.gates.is_empty());
+    windows
+}
@@ -0,0 +1,89 @@
+use std::fmt::Debug;
+
+/// Steps of `optimize_with_config` that report progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Turning outputs into expressions, one step per output.
+    Traverse,
+    /// Saturating and extracting outputs, one step per output. Outputs of
+    /// `SaturationMode::Shared` all finish together.
+    Saturate,
+    /// Adding extracted outputs to the new circuit, one step per output.
+    Rebuild,
+    /// Optimizing windows, one step per window. The phases of each window aren't
+    /// reported on their own.
+    Windows,
+}
+
+/// Observer of the optimizer's progress, set with `OptimizerConfig::progress`. Steps of
+/// `Phase::Traverse` and `Phase::Saturate` are reported from rayon's threads, in no
+/// particular order.
+pub trait Progress: Debug + Send + Sync {
+    /// `phase` is starting and will take `steps` steps.
+    fn phase_started(&self, _phase: Phase, _steps: usize) {}
+
+    /// One step of `phase` is done.
+    fn output_done(&self, _phase: Phase) {}
+
+    fn phase_finished(&self, _phase: Phase) {}
+}
+
+/// Reports nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {}
+
+#[cfg(feature = "indicatif")]
+pub use self::bars::IndicatifProgress;
+
+#[cfg(feature = "indicatif")]
+mod bars {
+    use std::sync::Mutex;
+
+    use indicatif::{ProgressBar, ProgressStyle};
+
+    use super::{Phase, Progress};
+
+    /// Draws a terminal progress bar per phase with `indicatif`.
+    #[derive(Debug, Default)]
+    pub struct IndicatifProgress {
+        bar: Mutex<Option<ProgressBar>>,
+    }
+
+    impl Progress for IndicatifProgress {
+        fn phase_started(&self, phase: Phase, steps: usize) {
+            let bar = ProgressBar::new(steps as u64);
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template("{msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len}")
+                    .unwrap()
+                    .progress_chars("#>-"),
+            );
+            bar.set_message(match phase {
+                Phase::Traverse => "Traversing outputs...",
+                Phase::Saturate => "Simplifying expressions...",
+                Phase::Rebuild => "Building circuit...",
+                Phase::Windows => "Saturating windows...",
+            });
+            *self.bar.lock().unwrap() = Some(bar);
+        }
+
+        fn output_done(&self, _phase: Phase) {
+            if let Some(bar) = self.bar.lock().unwrap().as_ref() {
+                bar.inc(1);
+            }
+        }
+
+        fn phase_finished(&self, phase: Phase) {
+            if let Some(bar) = self.bar.lock().unwrap().take() {
+                bar.finish_with_message(match phase {
+                    Phase::Traverse => "Finished traversing outputs.",
+                    Phase::Saturate => "Simplified expressions.",
+                    Phase::Rebuild => "Built circuit.",
+                    Phase::Windows => "Saturated windows.",
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, ops::AddAssign, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    optimizer::{CleanupStats, CostModel},
+    utils::{Circuit, GateType},
+};
+
+/// Why saturation of an output stopped, mirroring `egg::StopReason`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StopReason {
+    Saturated,
+    IterationLimit(usize),
+    NodeLimit(usize),
+    /// The time limit in seconds.
+    TimeLimit(f64),
+    Other(String),
+}
+
+impl From<&egg::StopReason> for StopReason {
+    fn from(reason: &egg::StopReason) -> Self {
+        match reason {
+            egg::StopReason::Saturated => StopReason::Saturated,
+            egg::StopReason::IterationLimit(n) => StopReason::IterationLimit(*n),
+            egg::StopReason::NodeLimit(n) => StopReason::NodeLimit(*n),
+            egg::StopReason::TimeLimit(t) => StopReason::TimeLimit(*t),
+            egg::StopReason::Other(s) => StopReason::Other(s.clone()),
+        }
+    }
+}
+
+/// Saturation statistics for a single output wire.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputStats {
+    pub output: String,
+    pub iterations: usize,
+    pub egraph_nodes: usize,
+    pub egraph_classes: usize,
+    pub stop_reason: Option<StopReason>,
+    /// Cost of the output's expression before and after extraction. Always finite, since
+    /// JSON has no infinity.
+    pub initial_cost: f64,
+    pub best_cost: f64,
+    pub time: Duration,
+}
+
+/// Time spent in each phase of `optimize_with_config`. Outputs saturated in parallel
+/// add up their own times, so the phases can sum to more than `total`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PhaseTimes {
+    /// Turning the circuit's outputs into expressions.
+    pub traverse: Duration,
+    /// Running the rewrite rules, including normalization and lifting.
+    pub saturate: Duration,
+    pub extract: Duration,
+    /// Building a circuit from the extracted expressions and stitching windows together.
+    pub rebuild: Duration,
+    /// `cleanup` before and after saturation, and `keep_best`.
+    pub cleanup: Duration,
+    /// Wall-clock time of the whole optimization.
+    pub total: Duration,
+}
+
+impl AddAssign for PhaseTimes {
+    fn add_assign(&mut self, other: Self) {
+        self.traverse += other.traverse;
+        self.saturate += other.saturate;
+        self.extract += other.extract;
+        self.rebuild += other.rebuild;
+        self.cleanup += other.cleanup;
+        self.total += other.total;
+    }
+}
+
+/// Which circuit `optimize_with_config` returned, see `KeepBest`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Kept {
+    #[default]
+    Optimized,
+    /// The input after `cleanup`, since optimizing made it more expensive.
+    Original,
+    /// The optimized circuit with these outputs' cones taken from the input instead.
+    Mixed { original_outputs: Vec<String> },
+}
+
+/// What `optimize_with_config` did, serializable with serde, e.g. through `to_json`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct OptimizationReport {
+    pub original_gates: usize,
+    pub optimized_gates: usize,
+    /// `gate_counts` of the circuit before and after.
+    pub original_gate_counts: BTreeMap<GateType, usize>,
+    pub optimized_gate_counts: BTreeMap<GateType, usize>,
+    /// `nonfree_gates` under the configured scheme before and after.
+    pub original_nonfree: usize,
+    pub optimized_nonfree: usize,
+    /// AND depth, see `IndexedCircuit::and_depth`, of the circuit before and after.
+    pub original_depth: usize,
+    pub optimized_depth: usize,
+    /// `circuit_cost` of the input after cleanup and of the returned circuit.
+    pub original_cost: f64,
+    pub optimized_cost: f64,
+    pub kept: Kept,
+    /// What `cleanup` did to the circuit before and after saturation.
+    pub cleanup_before: CleanupStats,
+    pub cleanup_after: CleanupStats,
+    /// Number of windows saturated, see `Partition`. 1 without partitioning.
+    pub windows: usize,
+    /// One entry per output, or per window output when partitioning.
+    pub outputs: Vec<OutputStats>,
+    /// Timings of the run that produced the circuit, which is an earlier one on a cache
+    /// hit.
+    pub phases: PhaseTimes,
+    /// Whether the circuit was read from `OptimizerConfig::cache`.
+    #[serde(default)]
+    pub cache_hit: bool,
+}
+
+impl OptimizationReport {
+    /// Number of outputs whose runner hit a limit instead of saturating.
+    pub fn outputs_hitting_limits(&self) -> usize {
+        self.outputs
+            .iter()
+            .filter(|stats| !matches!(stats.stop_reason, Some(StopReason::Saturated)))
+            .count()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Reports only hold serializable values")
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Number of wires of every type, inputs included.
+pub fn gate_counts(circuit: &Circuit) -> BTreeMap<GateType, usize> {
+    let mut counts = BTreeMap::new();
+    for (op, _) in circuit.values() {
+        *counts.entry(*op).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Number of gates `model` charges for, e.g. everything but XOR, XNOR, NOT and buffers
+/// under free-XOR.
+pub fn nonfree_gates(circuit: &Circuit, model: &dyn CostModel) -> usize {
+    circuit
+        .values()
+        .filter(|(op, _)| model.gate_cost(*op) > 0.0)
+        .count()
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    fs::read_to_string,
+    path::Path,
+};
+
+use egg::{ENodeOrVar, Pattern, Rewrite};
+
+use crate::{
+    optimizer::{check_rule, CircuitLang, RuleError},
+    parser::{ParseError, ParseErrorKind},
+};
+
+/// Separates the two sides of a rule applied left to right only.
+const ARROW: &str = "=>";
+/// Separates the two sides of a rule applied in both directions.
+const BIDIRECTIONAL_ARROW: &str = "<=>";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RulesFileError {
+    /// The file could not be read.
+    Io {
+        path: String,
+        message: String,
+    },
+    Parse(ParseError),
+    /// The rule on this 1-based line is not sound, see `check_rule`.
+    Unsound {
+        line: usize,
+        error: RuleError,
+    },
+}
+
+impl Display for RulesFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RulesFileError::Io { path, message } => {
+                write!(f, "couldn't read {}: {}", path, message)
+            }
+            RulesFileError::Parse(error) => write!(f, "{}", error),
+            RulesFileError::Unsound { line, error } => write!(f, "{} at line {}", error, line),
+        }
+    }
+}
+
+impl std::error::Error for RulesFileError {}
+
+impl From<ParseError> for RulesFileError {
+    fn from(error: ParseError) -> Self {
+        RulesFileError::Parse(error)
+    }
+}
+
+/// Reads a rules file, see `parse_rules`.
+pub fn load_rules(path: impl AsRef<Path>) -> Result<Vec<Rewrite<CircuitLang, ()>>, RulesFileError> {
+    let path = path.as_ref();
+    let source = read_to_string(path).map_err(|e| RulesFileError::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+    parse_rules(&source)
+}
+
+/// Parses rewrite rules, one per line:
+///
+/// ```text
+/// # Comments and blank lines are skipped
+/// xor-commute; (xor ?a ?b) => (xor ?b ?a)
+/// de-morgan-and; (not (and ?a ?b)) <=> (or (not ?a) (not ?b))
+/// ```
+///
+/// Each line is a name, a semicolon, and two patterns over `?`-variables separated by
+/// `=>`, or by `<=>` for a rule that also applies right to left. The reverse of a
+/// bidirectional rule is named `<name>-reverse`.
+///
+/// Every rule is proven sound with `check_rule` before it is returned, so a file that
+/// parses can be passed to `RuleSet::Only` or `RuleSet::Extended` as is.
+pub fn parse_rules(source: &str) -> Result<Vec<Rewrite<CircuitLang, ()>>, RulesFileError> {
+    let mut rules: Vec<Rewrite<CircuitLang, ()>> = Vec::new();
+    let mut names: HashSet<String> = HashSet::new();
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let content = text.split('#').next().unwrap();
+        if content.trim().is_empty() {
+            continue;
+        }
+        let malformed = |message: String, column: usize| {
+            ParseError::at_line(ParseErrorKind::Malformed(message), line, column)
+        };
+        let column = |part: &str| part.as_ptr() as usize - text.as_ptr() as usize + 1;
+
+        let Some((name, body)) = content.split_once(';') else {
+            return Err(malformed("expected `name; lhs => rhs`".into(), 1).into());
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(malformed("rule has no name".into(), 1).into());
+        }
+        let (lhs, rhs, bidirectional) = match body.split_once(BIDIRECTIONAL_ARROW) {
+            Some((lhs, rhs)) => (lhs, rhs, true),
+            None => match body.split_once(ARROW) {
+                Some((lhs, rhs)) => (lhs, rhs, false),
+                None => {
+                    return Err(malformed(
+                        format!("expected {} or {}", ARROW, BIDIRECTIONAL_ARROW),
+                        column(body),
+                    )
+                    .into())
+                }
+            },
+        };
+        let (lhs, rhs) = (lhs.trim(), rhs.trim());
+        let lhs_pattern = pattern(lhs).map_err(|e| malformed(e, column(lhs)))?;
+        let rhs_pattern = pattern(rhs).map_err(|e| malformed(e, column(rhs)))?;
+
+        let mut directions = vec![(name.to_string(), lhs_pattern.clone(), rhs_pattern.clone())];
+        if bidirectional {
+            directions.push((format!("{}-reverse", name), rhs_pattern, lhs_pattern));
+        }
+        for (name, searcher, applier) in directions {
+            if !names.insert(name.clone()) {
+                return Err(malformed(format!("duplicate rule name {}", name), 1).into());
+            }
+            let rule =
+                Rewrite::new(name, searcher, applier).map_err(|e| malformed(e, column(lhs)))?;
+            check_rule(&rule).map_err(|error| RulesFileError::Unsound { line, error })?;
+            rules.push(rule);
+        }
+    }
+    Ok(rules)
+}
+
+/// Parses one side of a rule. Wire names are rejected since they would only match the
+/// wire of that name; pattern variables start with `?`.
+fn pattern(text: &str) -> Result<Pattern<CircuitLang>, String> {
+    let pattern: Pattern<CircuitLang> = text
+        .parse()
+        .map_err(|e| format!("invalid pattern {:?}: {}", text, e))?;
+    for node in pattern.ast.as_ref() {
+        if let ENodeOrVar::ENode(CircuitLang::Wire(wire)) = node {
+            return Err(format!(
+                "unknown operator or wire {} in pattern {:?}, variables start with ?",
+                wire, text
+            ));
+        }
+    }
+    Ok(pattern)
+}
+
+/// Writes plain pattern rules in the format `parse_rules` reads, one direction per
+/// line. Rules that aren't plain patterns are skipped.
+pub fn format_rules(rules: &[Rewrite<CircuitLang, ()>]) -> String {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            let lhs = rule.searcher.get_pattern_ast()?;
+            let rhs = rule.applier.get_pattern_ast()?;
+            Some(format!("{}; {} {} {}\n", rule.name, lhs, ARROW, rhs))
+        })
+        .collect()
+}
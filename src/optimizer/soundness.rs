@@ -0,0 +1,133 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter},
+};
+
+use egg::{ENodeOrVar, Language, PatternAst, Rewrite};
+
+use crate::{
+    optimizer::CircuitLang,
+    simulator::{enumeration_batches, enumeration_mask, enumeration_word},
+};
+
+/// Largest number of distinct pattern variables a rule may use.
+pub const RULE_VARIABLE_LIMIT: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    /// The searcher or applier is not a plain pattern, so it cannot be checked.
+    Opaque(String),
+    TooManyVariables {
+        rule: String,
+        found: usize,
+    },
+    /// An assignment to the pattern variables on which both sides differ.
+    Unsound {
+        rule: String,
+        counterexample: BTreeMap<String, bool>,
+    },
+}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleError::Opaque(rule) => write!(f, "rule {} is not a plain pattern rewrite", rule),
+            RuleError::TooManyVariables { rule, found } => write!(
+                f,
+                "rule {} uses {} variables, at most {} can be checked",
+                rule, found, RULE_VARIABLE_LIMIT
+            ),
+            RuleError::Unsound {
+                rule,
+                counterexample,
+            } => write!(f, "rule {} is unsound for {:?}", rule, counterexample),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+/// Pattern variables and concrete wires are both free inputs of a rule.
+fn free_name(node: &ENodeOrVar<CircuitLang>) -> Option<String> {
+    match node {
+        ENodeOrVar::Var(var) => Some(var.to_string()),
+        ENodeOrVar::ENode(CircuitLang::Wire(wire)) => Some(wire.clone()),
+        ENodeOrVar::ENode(_) => None,
+    }
+}
+
+/// Evaluates a pattern on 64 assignments at once, `words[i]` holding `variables[i]`.
+fn eval_pattern(pattern: &PatternAst<CircuitLang>, variables: &[String], words: &[u64]) -> u64 {
+    let mut values: Vec<u64> = Vec::with_capacity(pattern.as_ref().len());
+    for node in pattern.as_ref() {
+        let value = match free_name(node) {
+            Some(name) => words[variables.binary_search(&name).unwrap()],
+            None => {
+                let ENodeOrVar::ENode(enode) = node else {
+                    unreachable!("Variables are free names")
+                };
+                let operands: Vec<u64> = enode
+                    .children()
+                    .iter()
+                    .map(|&id| values[usize::from(id)])
+                    .collect();
+                enode.gate_type().unwrap().eval_word(&operands)
+            }
+        };
+        values.push(value);
+    }
+    *values.last().unwrap()
+}
+
+/// Proves a rewrite sound by evaluating both sides on every assignment to its variables.
+pub fn check_rule(rule: &Rewrite<CircuitLang, ()>) -> Result<(), RuleError> {
+    let name = rule.name.to_string();
+    let (Some(lhs), Some(rhs)) = (
+        rule.searcher.get_pattern_ast(),
+        rule.applier.get_pattern_ast(),
+    ) else {
+        return Err(RuleError::Opaque(name));
+    };
+
+    let variables: Vec<String> = lhs
+        .as_ref()
+        .iter()
+        .chain(rhs.as_ref().iter())
+        .filter_map(free_name)
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect();
+    if variables.len() > RULE_VARIABLE_LIMIT {
+        return Err(RuleError::TooManyVariables {
+            rule: name,
+            found: variables.len(),
+        });
+    }
+
+    let mask = enumeration_mask(variables.len());
+    for batch in 0..enumeration_batches(variables.len()) {
+        let words: Vec<u64> = (0..variables.len())
+            .map(|i| enumeration_word(i, batch))
+            .collect();
+        let diff =
+            (eval_pattern(lhs, &variables, &words) ^ eval_pattern(rhs, &variables, &words)) & mask;
+        if diff != 0 {
+            let index = (batch << 6) | diff.trailing_zeros() as usize;
+            let counterexample = variables
+                .iter()
+                .enumerate()
+                .map(|(i, var)| (var.clone(), (index >> i) & 1 == 1))
+                .collect();
+            return Err(RuleError::Unsound {
+                rule: name,
+                counterexample,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks every rule, failing on the first one that cannot be proven sound.
+pub fn check_rules(rules: &[Rewrite<CircuitLang, ()>]) -> Result<(), RuleError> {
+    rules.iter().try_for_each(check_rule)
+}
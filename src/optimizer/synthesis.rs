@@ -0,0 +1,274 @@
+use std::collections::{HashMap, HashSet};
+
+use egg::{EGraph, ENodeOrVar, Id, Language, Pattern, PatternAst, RecExpr, Rewrite, Runner};
+use log::debug;
+
+use crate::{
+    optimizer::CircuitLang,
+    simulator::{enumeration_mask, enumeration_word},
+    utils::GateType,
+};
+
+/// Settings for `synthesize_rules`.
+#[derive(Debug, Clone)]
+pub struct SynthesisConfig {
+    /// Number of pattern variables, at most 6 so that a truth table fits in one word.
+    /// Two variables only have 16 functions, so their terms can be enumerated far larger
+    /// than with three.
+    pub variables: usize,
+    /// Largest term enumerated, in e-nodes.
+    pub max_size: usize,
+    /// Gates terms are built from, e.g. only AND, XOR and NOT for `Normalization::Xag`.
+    pub gates: Vec<GateType>,
+    /// Limits of the runner that checks whether a candidate follows from the rules kept
+    /// so far.
+    pub iter_limit: usize,
+    pub node_limit: usize,
+}
+
+impl Default for SynthesisConfig {
+    fn default() -> Self {
+        Self {
+            variables: 2,
+            max_size: 7,
+            gates: vec![
+                GateType::AND,
+                GateType::NOT,
+                GateType::OR,
+                GateType::XOR,
+                GateType::ORNOT,
+                GateType::NOR,
+                GateType::NAND,
+                GateType::ANDNOT,
+                GateType::XNOR,
+            ],
+            iter_limit: 3,
+            node_limit: 2_000,
+        }
+    }
+}
+
+struct Term {
+    expr: RecExpr<CircuitLang>,
+    table: u64,
+}
+
+/// Synthesizes rewrite rules for the gates in `config`, in the style of Ruler.
+///
+/// Terms are enumerated by size over the variables `a`, `b`, ..., with the children of
+/// every new term taken from the smallest term found so far for each truth table. A
+/// term whose truth table already has a representative is a candidate rule from the
+/// term to the representative, so every rule is sound over all assignments by
+/// construction. Candidates are then considered smallest first, and one is kept only if
+/// the rules kept before it cannot already prove it in a small e-graph.
+///
+/// Rules are named `lhs => rhs`. Rules whose sides use the same variables are kept in
+/// both directions. Constants are never enumerated; they are left to `fold_constants`.
+pub fn synthesize_rules(config: &SynthesisConfig) -> Vec<Rewrite<CircuitLang, ()>> {
+    assert!(
+        (1..=6).contains(&config.variables),
+        "Truth tables hold at most 6 variables"
+    );
+    let mask = enumeration_mask(config.variables);
+    let mut representatives: HashMap<u64, RecExpr<CircuitLang>> = HashMap::new();
+    let mut by_size: Vec<Vec<Term>> = vec![vec![], vec![]];
+    for i in 0..config.variables {
+        let mut expr = RecExpr::default();
+        expr.add(CircuitLang::Wire(variable_name(i)));
+        let table = enumeration_word(i, 0) & mask;
+        representatives.insert(table, expr.clone());
+        by_size[1].push(Term { expr, table });
+    }
+
+    let mut candidates: HashSet<(RecExpr<CircuitLang>, RecExpr<CircuitLang>)> = HashSet::new();
+    for size in 2..=config.max_size {
+        let mut terms: Vec<Term> = Vec::new();
+        let smaller = &by_size;
+        for &op in config.gates.iter() {
+            let children: Vec<Vec<&Term>> = match op.arity() {
+                1 => smaller[size - 1].iter().map(|term| vec![term]).collect(),
+                2 => (1..size - 1)
+                    .flat_map(|left| {
+                        let right = size - 1 - left;
+                        smaller[left]
+                            .iter()
+                            .flat_map(move |a| smaller[right].iter().map(move |b| vec![a, b]))
+                    })
+                    .collect(),
+                _ => continue,
+            };
+            for children in children {
+                let table = op.eval_word(
+                    &children
+                        .iter()
+                        .map(|child| child.table)
+                        .collect::<Vec<u64>>(),
+                ) & mask;
+                let exprs: Vec<&RecExpr<CircuitLang>> =
+                    children.iter().map(|child| &child.expr).collect();
+                terms.push(Term {
+                    expr: combine(op, &exprs),
+                    table,
+                });
+            }
+        }
+        // The first term with a new truth table becomes its representative, and every
+        // later one a candidate rule
+        let mut fresh: Vec<Term> = Vec::new();
+        for term in terms {
+            match representatives.get(&term.table) {
+                Some(representative) => {
+                    if let Some(candidate) = candidate(&term.expr, representative) {
+                        candidates.insert(candidate);
+                    }
+                }
+                None => {
+                    representatives.insert(term.table, term.expr.clone());
+                    fresh.push(term);
+                }
+            }
+        }
+        debug!(
+            "Size {}: {} new representatives, {} candidates so far",
+            size,
+            fresh.len(),
+            candidates.len()
+        );
+        by_size.push(fresh);
+    }
+
+    let mut candidates: Vec<(RecExpr<CircuitLang>, RecExpr<CircuitLang>)> =
+        candidates.into_iter().collect();
+    candidates.sort_by_cached_key(|(lhs, rhs)| {
+        (
+            lhs.as_ref().len() + rhs.as_ref().len(),
+            lhs.to_string(),
+            rhs.to_string(),
+        )
+    });
+    let mut rules: Vec<Rewrite<CircuitLang, ()>> = Vec::new();
+    for (lhs, rhs) in candidates {
+        if is_derivable(&lhs, &rhs, &rules, config) {
+            continue;
+        }
+        rules.push(rewrite(&lhs, &rhs));
+        // A bare variable would match every e-class, and a rule like commutativity is
+        // its own reverse
+        let reverse = candidate(&rhs, &lhs);
+        let reversible = rhs.as_ref().len() > 1 && reverse != Some((lhs.clone(), rhs.clone()));
+        if reversible && variables(&lhs).len() == variables(&rhs).len() {
+            rules.push(rewrite(&rhs, &lhs));
+        }
+    }
+    rules
+}
+
+fn variable_name(i: usize) -> String {
+    ((b'a' + i as u8) as char).to_string()
+}
+
+/// Builds `op` over copies of the child expressions.
+fn combine(op: GateType, children: &[&RecExpr<CircuitLang>]) -> RecExpr<CircuitLang> {
+    let mut expr = RecExpr::default();
+    let ids: Vec<Id> = children
+        .iter()
+        .map(|child| {
+            let offset = expr.as_ref().len();
+            for node in child.as_ref() {
+                expr.add(
+                    node.clone()
+                        .map_children(|id| Id::from(usize::from(id) + offset)),
+                );
+            }
+            Id::from(expr.as_ref().len() - 1)
+        })
+        .collect();
+    expr.add(CircuitLang::gate(op, &ids).expect("Synthesis only enumerates gates"));
+    expr
+}
+
+/// Variables in order of first use.
+fn variables(expr: &RecExpr<CircuitLang>) -> Vec<String> {
+    let mut seen: Vec<String> = Vec::new();
+    for node in expr.as_ref() {
+        if let CircuitLang::Wire(wire) = node {
+            if !seen.contains(wire) {
+                seen.push(wire.clone());
+            }
+        }
+    }
+    seen
+}
+
+/// The rule `lhs => rhs` with its variables renamed to `a`, `b`, ... in order of first
+/// use, so that renamings of a rule are only considered once. `None` if the right side
+/// uses a variable the left side doesn't bind.
+fn candidate(
+    lhs: &RecExpr<CircuitLang>,
+    rhs: &RecExpr<CircuitLang>,
+) -> Option<(RecExpr<CircuitLang>, RecExpr<CircuitLang>)> {
+    let bound = variables(lhs);
+    if variables(rhs).iter().any(|wire| !bound.contains(wire)) {
+        return None;
+    }
+    let renaming: HashMap<&String, String> = bound
+        .iter()
+        .enumerate()
+        .map(|(i, wire)| (wire, variable_name(i)))
+        .collect();
+    let rename = |expr: &RecExpr<CircuitLang>| -> RecExpr<CircuitLang> {
+        expr.as_ref()
+            .iter()
+            .map(|node| match node {
+                CircuitLang::Wire(wire) => CircuitLang::Wire(renaming[wire].clone()),
+                node => node.clone(),
+            })
+            .collect::<Vec<_>>()
+            .into()
+    };
+    Some((rename(lhs), rename(rhs)))
+}
+
+/// Whether `rules` already prove `lhs = rhs`, treating the variables as opaque wires.
+fn is_derivable(
+    lhs: &RecExpr<CircuitLang>,
+    rhs: &RecExpr<CircuitLang>,
+    rules: &[Rewrite<CircuitLang, ()>],
+    config: &SynthesisConfig,
+) -> bool {
+    let mut egraph: EGraph<CircuitLang, ()> = EGraph::default();
+    let left = egraph.add_expr(lhs);
+    let right = egraph.add_expr(rhs);
+    let runner = Runner::default()
+        .with_egraph(egraph)
+        .with_iter_limit(config.iter_limit)
+        .with_node_limit(config.node_limit)
+        .with_hook(move |runner| {
+            if runner.egraph.find(left) == runner.egraph.find(right) {
+                Err("proved".to_string())
+            } else {
+                Ok(())
+            }
+        })
+        .run(rules);
+    runner.egraph.find(left) == runner.egraph.find(right)
+}
+
+fn pattern(expr: &RecExpr<CircuitLang>) -> Pattern<CircuitLang> {
+    let ast: PatternAst<CircuitLang> = expr
+        .as_ref()
+        .iter()
+        .map(|node| match node {
+            CircuitLang::Wire(wire) => ENodeOrVar::Var(format!("?{}", wire).parse().unwrap()),
+            node => ENodeOrVar::ENode(node.clone()),
+        })
+        .collect::<Vec<_>>()
+        .into();
+    Pattern::new(ast)
+}
+
+fn rewrite(lhs: &RecExpr<CircuitLang>, rhs: &RecExpr<CircuitLang>) -> Rewrite<CircuitLang, ()> {
+    let (lhs, rhs) = (pattern(lhs), pattern(rhs));
+    Rewrite::new(format!("{} => {}", lhs, rhs), lhs, rhs)
+        .expect("Representatives only use variables of the terms they stand for")
+}
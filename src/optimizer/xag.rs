@@ -0,0 +1,116 @@
+use egg::{rewrite, Id, Language, RecExpr, Rewrite};
+
+use crate::optimizer::CircuitLang;
+
+/// Rewrites an expression into an XOR-AND graph: AND, XOR and NOT over its wires.
+///
+/// Every binary gate becomes the AND or XOR it is built around plus inversions, so its
+/// count of AND and XOR gates does not change. MUX and MAJ are the exception: they
+/// become their one-AND forms, which add two and three XOR gates. `xag_lift_rules` turns
+/// the inversions back into the richer gates after saturation.
+pub fn to_xag(expr: &RecExpr<CircuitLang>) -> RecExpr<CircuitLang> {
+    let mut xag: RecExpr<CircuitLang> = RecExpr::default();
+    let mut ids: Vec<Id> = Vec::with_capacity(expr.as_ref().len());
+    for node in expr.as_ref() {
+        let node = node.clone().map_children(|id| ids[usize::from(id)]);
+        ids.push(add_xag(node, &mut |node| xag.add(node)));
+    }
+    xag
+}
+
+/// Adds the XAG form of `node`, whose children have been added already, with `add` and
+/// returns the id of its output.
+pub(crate) fn add_xag(node: CircuitLang, add: &mut impl FnMut(CircuitLang) -> Id) -> Id {
+    match node {
+        CircuitLang::And(_) | CircuitLang::Xor(_) | CircuitLang::Not(_) => add(node),
+        CircuitLang::Nand([a, b]) => {
+            let and = add(CircuitLang::And([a, b]));
+            add(CircuitLang::Not(and))
+        }
+        CircuitLang::Xnor([a, b]) => {
+            let xor = add(CircuitLang::Xor([a, b]));
+            add(CircuitLang::Not(xor))
+        }
+        CircuitLang::AndNot([a, b]) => {
+            let not_b = add(CircuitLang::Not(b));
+            add(CircuitLang::And([a, not_b]))
+        }
+        CircuitLang::Nor([a, b]) => {
+            let not_a = add(CircuitLang::Not(a));
+            let not_b = add(CircuitLang::Not(b));
+            add(CircuitLang::And([not_a, not_b]))
+        }
+        CircuitLang::Or([a, b]) => {
+            let not_a = add(CircuitLang::Not(a));
+            let not_b = add(CircuitLang::Not(b));
+            let nor = add(CircuitLang::And([not_a, not_b]));
+            add(CircuitLang::Not(nor))
+        }
+        CircuitLang::OrNot([a, b]) => {
+            // a | !b is !(!a & b)
+            let not_a = add(CircuitLang::Not(a));
+            let and = add(CircuitLang::And([not_a, b]));
+            add(CircuitLang::Not(and))
+        }
+        CircuitLang::Mux([s, a, b]) => {
+            // b ^ (s & (a ^ b))
+            let flip = add(CircuitLang::Xor([a, b]));
+            let taken = add(CircuitLang::And([s, flip]));
+            add(CircuitLang::Xor([b, taken]))
+        }
+        CircuitLang::Maj([a, b, c]) => {
+            // a ^ ((a ^ b) & (a ^ c))
+            let ab = add(CircuitLang::Xor([a, b]));
+            let ac = add(CircuitLang::Xor([a, c]));
+            let both = add(CircuitLang::And([ab, ac]));
+            add(CircuitLang::Xor([a, both]))
+        }
+        CircuitLang::True | CircuitLang::False | CircuitLang::Wire(_) => add(node),
+    }
+}
+
+/// Rules over XAGs. Fewer gate kinds mean that commutativity, associativity and the
+/// constant folds cover every combination, which the rules for the full gate set do not.
+pub fn xag_rules() -> Vec<Rewrite<CircuitLang, ()>> {
+    let mut rules = vec![
+        rewrite!("xag-and-comm"; "(and ?a ?b)" => "(and ?b ?a)"),
+        rewrite!("xag-xor-comm"; "(xor ?a ?b)" => "(xor ?b ?a)"),
+        rewrite!("xag-and-assoc"; "(and (and ?a ?b) ?c)" => "(and ?a (and ?b ?c))"),
+        rewrite!("xag-xor-assoc"; "(xor (xor ?a ?b) ?c)" => "(xor ?a (xor ?b ?c))"),
+        rewrite!("xag-and-idempotent"; "(and ?a ?a)" => "?a"),
+        rewrite!("xag-and-absorb"; "(and ?a (and ?a ?b))" => "(and ?a ?b)"),
+        rewrite!("xag-and-complement"; "(and ?a (not ?a))" => "false"),
+        rewrite!("xag-xor-self"; "(xor ?a ?a)" => "false"),
+        rewrite!("xag-xor-complement"; "(xor ?a (not ?a))" => "true"),
+        rewrite!("xag-xor-cancel"; "(xor ?a (xor ?a ?b))" => "?b"),
+        rewrite!("xag-double-not"; "(not (not ?a))" => "?a"),
+        // Factoring saves an AND gate, so it is only applied in that direction
+        rewrite!("xag-factor"; "(xor (and ?a ?b) (and ?a ?c))" => "(and ?a (xor ?b ?c))"),
+        rewrite!("xag-and-xor-self"; "(and ?a (xor ?a ?b))" => "(and ?a (not ?b))"),
+        rewrite!("xag-and-false"; "(and ?a false)" => "false"),
+        rewrite!("xag-and-true"; "(and ?a true)" => "?a"),
+        rewrite!("xag-xor-false"; "(xor ?a false)" => "?a"),
+        rewrite!("xag-xor-true"; "(xor ?a true)" => "(not ?a)"),
+        rewrite!("xag-not-true"; "(not true)" => "false"),
+        rewrite!("xag-not-false"; "(not false)" => "true"),
+    ];
+    // Free-XOR schemes want inversions inside XORs, the others want them outside
+    rules.extend(rewrite!("xag-xor-not"; "(xor (not ?a) ?b)" <=> "(not (xor ?a ?b))"));
+    rules
+}
+
+/// Rules that fold inversions in an XAG back into the gates `GateType` offers. They are
+/// applied once after saturation, so extraction can pick whichever form is cheaper.
+pub fn xag_lift_rules() -> Vec<Rewrite<CircuitLang, ()>> {
+    vec![
+        rewrite!("lift-nand"; "(not (and ?a ?b))" => "(nand ?a ?b)"),
+        rewrite!("lift-nor"; "(and (not ?a) (not ?b))" => "(nor ?a ?b)"),
+        rewrite!("lift-or"; "(not (and (not ?a) (not ?b)))" => "(or ?a ?b)"),
+        rewrite!("lift-andnot"; "(and ?a (not ?b))" => "(andnot ?a ?b)"),
+        rewrite!("lift-ornot"; "(not (and (not ?a) ?b))" => "(ornot ?a ?b)"),
+        rewrite!("lift-xnor"; "(not (xor ?a ?b))" => "(xnor ?a ?b)"),
+        rewrite!("lift-or-xor"; "(xor (xor ?a ?b) (and ?a ?b))" => "(or ?a ?b)"),
+        rewrite!("lift-mux"; "(xor ?b (and ?s (xor ?a ?b)))" => "(mux ?s ?a ?b)"),
+        rewrite!("lift-maj"; "(xor ?a (and (xor ?a ?b) (xor ?a ?c)))" => "(maj ?a ?b ?c)"),
+    ]
+}
@@ -24,24 +24,36 @@ fn hash(contents: Vec<RistrettoPoint>) -> [u8; 32] {
     }
     let digest = hasher.finalize();
     // return digest.as_bytes().to_vec();
-    return digest.into();
+    digest.into()
+}
+
+impl Default for Sender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Receiver {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Sender {
     pub fn new() -> Self {
         let y = Scalar::random(&mut rand::thread_rng());
         let s = &y * RISTRETTO_BASEPOINT_TABLE;
-        let t = &y * s;
+        let t = y * s;
         Self { y, s, t }
     }
 
     pub fn derive_keys(&self, blinded_choice: RistrettoPoint) -> ([u8; 32], [u8; 32]) {
-        let p1 = (self.y * blinded_choice) - Scalar::from(0 as u32) * self.t;
-        let p2 = (self.y * blinded_choice) - Scalar::from(1 as u32) * self.t;
+        let p1 = (self.y * blinded_choice) - Scalar::from(0_u32) * self.t;
+        let p2 = (self.y * blinded_choice) - Scalar::from(1_u32) * self.t;
         let digest1 = hash(vec![self.s, blinded_choice, p1]);
         let digest2 = hash(vec![self.s, blinded_choice, p2]);
 
-        return (digest1, digest2);
+        (digest1, digest2)
     }
 
     pub fn encrypt(
@@ -55,20 +67,20 @@ impl Sender {
             panic!("Message lengths must match key lengths");
         }
         let mut ciphertext = vec![0u8; m1.len() + 32];
-        ciphertext[..m1.len()].copy_from_slice(&m1);
+        ciphertext[..m1.len()].copy_from_slice(m1);
         for i in 0..m1.len() {
             ciphertext[i] ^= key1[i];
         }
         ciphertext[m1.len()..].copy_from_slice(&key1[..32]);
 
         let mut ciphertext2 = vec![0u8; m2.len() + 32];
-        ciphertext2[..m2.len()].copy_from_slice(&m2);
+        ciphertext2[..m2.len()].copy_from_slice(m2);
         for i in 0..m2.len() {
             ciphertext2[i] ^= key2[i];
         }
         ciphertext2[m2.len()..].copy_from_slice(&key2[..32]);
 
-        return (ciphertext, ciphertext2);
+        (ciphertext, ciphertext2)
     }
 }
 
@@ -85,19 +97,18 @@ impl Receiver {
 
     pub fn choose(&mut self, s: &RistrettoPoint, choice: u8) -> RistrettoPoint {
         let scalar_choice = Scalar::from(choice as u64);
-        let r = &self.x * RISTRETTO_BASEPOINT_TABLE + &scalar_choice * s;
+        let r = &self.x * RISTRETTO_BASEPOINT_TABLE + scalar_choice * s;
         self.r = Some(r);
         self.choice = Some(choice);
-        self.s = Some(s.clone());
-        return r;
+        self.s = Some(*s);
+        r
     }
 
     pub fn derive_key(&mut self) -> [u8; 32] {
         if let Some(s) = self.s {
             if let Some(r) = self.r {
                 let xs = self.x * s;
-                let digest = hash(vec![s, r, xs]);
-                digest
+                hash(vec![s, r, xs])
             } else {
                 panic!("Receiver has not chosen a value yet");
             }
@@ -124,9 +135,9 @@ impl Receiver {
         ciphertexts: (Vec<u8>, Vec<u8>),
     ) -> Option<Vec<u8>> {
         if self.choice == Some(0) {
-            return self.decrypt(&key, &ciphertexts.0);
+            self.decrypt(&key, &ciphertexts.0)
         } else if self.choice == Some(1) {
-            return self.decrypt(&key, &ciphertexts.1);
+            self.decrypt(&key, &ciphertexts.1)
         } else {
             panic!("Receiver has not chosen a value yet");
         }
@@ -1,8 +1,10 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+
+use log::debug;
 
 use crate::{
-    parser::Gate,
-    utils::{Circuit, GateType},
+    parser::{Gate, ParseError, ParseErrorKind},
+    utils::{Circuit, GateType, Ports},
 };
 
 #[derive(Debug)]
@@ -11,50 +13,123 @@ struct BristolHeader {
     total_wires: usize,
 }
 
-pub fn parse_bristol_fashion(
-    input: &str,
-) -> (
-    Circuit,
-    BTreeMap<String, Vec<String>>,
-    BTreeMap<String, Vec<String>>,
-) {
-    let mut circuit_inputs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+/// Splits a line into whitespace separated tokens, keeping their 1-based columns.
+fn tokenize(line: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start: Option<usize> = None;
+    for (i, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                tokens.push((s + 1, &line[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push((s + 1, &line[s..]));
+    }
+    tokens
+}
+
+fn parse_number(token: (usize, &str), line: usize) -> Result<usize, ParseError> {
+    token.1.parse::<usize>().map_err(|_| {
+        ParseError::at_line(
+            ParseErrorKind::InvalidNumber(token.1.to_string()),
+            line,
+            token.0,
+        )
+    })
+}
+
+/// Parses a `<count> <size> <size> ...` line, checking that `count` sizes follow.
+fn parse_counted_line(
+    tokens: &[(usize, &str)],
+    line: usize,
+    section: &'static str,
+) -> Result<Vec<usize>, ParseError> {
+    let first = tokens
+        .first()
+        .ok_or_else(|| ParseError::at_line(ParseErrorKind::Truncated(section), line, 1))?;
+    let count = parse_number(*first, line)?;
+    let sizes = tokens[1..]
+        .iter()
+        .map(|token| parse_number(*token, line))
+        .collect::<Result<Vec<usize>, ParseError>>()?;
+    if sizes.len() != count {
+        let column = tokens.last().map_or(1, |(c, t)| c + t.len());
+        return Err(ParseError::at_line(
+            ParseErrorKind::Malformed(format!(
+                "{} declares {} entries but lists {}",
+                section,
+                count,
+                sizes.len()
+            )),
+            line,
+            column,
+        ));
+    }
+    Ok(sizes)
+}
+
+pub fn parse_bristol_fashion(input: &str) -> Result<(Circuit, Ports, Ports), ParseError> {
+    let mut circuit_inputs: Ports = BTreeMap::new();
     let mut circuit: Circuit = BTreeMap::new();
-    let mut circuit_outputs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut circuit_outputs: Ports = BTreeMap::new();
 
-    let mut lines = input.lines();
+    let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line));
 
     // Parse header
-    let header_line = lines.next().unwrap();
-    println!("Header line: {:?}", header_line);
-    let _header_parts: Vec<&str> = header_line.split_whitespace().collect();
+    let (line_no, header_line) = lines
+        .next()
+        .ok_or_else(|| ParseError::at_line(ParseErrorKind::Truncated("header"), 1, 1))?;
+    debug!("Header line: {:?}", header_line);
+    let header_parts = tokenize(header_line);
+    if header_parts.len() < 2 {
+        let column = header_parts.last().map_or(1, |(c, t)| c + t.len());
+        return Err(ParseError::at_line(
+            ParseErrorKind::Truncated("header wire count"),
+            line_no,
+            column,
+        ));
+    }
     let header = BristolHeader {
-        total_gates: _header_parts[0].parse::<usize>().unwrap(),
-        total_wires: _header_parts[1].parse::<usize>().unwrap(),
+        total_gates: parse_number(header_parts[0], line_no)?,
+        total_wires: parse_number(header_parts[1], line_no)?,
     };
 
-    println!("Header: {:?}", header);
+    debug!("Header: {:?}", header);
 
     // Parse inputs
-    let inputs_line = lines.next().unwrap();
-    let inputs: Vec<usize> = inputs_line
-        .split_whitespace()
-        .skip(1) // The first number is the number of parties
-        .map(|n| n.parse::<usize>().unwrap())
-        .collect();
-
-    let max_input_id = inputs[0] + inputs[1] - 1;
+    let (line_no, inputs_line) = lines.next().ok_or_else(|| {
+        ParseError::at_line(ParseErrorKind::Truncated("input line"), line_no + 1, 1)
+    })?;
+    let inputs = parse_counted_line(&tokenize(inputs_line), line_no, "input line")?;
+    if inputs.len() != 2 {
+        return Err(ParseError::at_line(
+            ParseErrorKind::Malformed(format!("expected 2 parties, found {}", inputs.len())),
+            line_no,
+            1,
+        ));
+    }
+
+    let total_inputs = inputs[0] + inputs[1];
     let min_input_id = 0;
-    println!("Input ID range: {} - {}", min_input_id, max_input_id);
+    debug!(
+        "Input ID range: {} - {}",
+        min_input_id,
+        total_inputs.saturating_sub(1)
+    );
 
     let a_range = min_input_id..inputs[0];
-    let b_range = inputs[0]..max_input_id + 1;
+    let b_range = inputs[0]..total_inputs;
 
     let a_inputs: Vec<String> = (a_range).map(|n| format!("a_{}", n)).collect();
 
     let b_inputs: Vec<String> = (b_range).map(|n| format!("b_{}", n)).collect();
 
-    println!(
+    debug!(
         "Parsed inputs lengths: {}, {}",
         a_inputs.len(),
         b_inputs.len()
@@ -70,99 +145,217 @@ pub fn parse_bristol_fashion(
     circuit_inputs.insert("a".to_string(), a_inputs);
     circuit_inputs.insert("b".to_string(), b_inputs);
 
-    println!("Circuit inputs: {:?}", circuit_inputs);
+    debug!("Circuit inputs: {:?}", circuit_inputs);
+
+    let (outputs_line_no, outputs_line) = lines.next().ok_or_else(|| {
+        ParseError::at_line(ParseErrorKind::Truncated("output line"), line_no + 1, 1)
+    })?;
+    let outputs = parse_counted_line(&tokenize(outputs_line), outputs_line_no, "output line")?;
+    let total_outputs: usize = outputs.iter().sum();
 
-    let outputs_line = lines.next().unwrap();
-    let outputs: Vec<usize> = outputs_line
-        .split_whitespace()
-        .skip(1)
-        .map(|n| n.parse::<usize>().unwrap())
-        .collect();
+    if total_outputs + total_inputs > header.total_wires {
+        return Err(ParseError::at_line(
+            ParseErrorKind::Malformed(format!(
+                "{} inputs and {} outputs do not fit in {} wires",
+                total_inputs, total_outputs, header.total_wires
+            )),
+            outputs_line_no,
+            1,
+        ));
+    }
 
-    let max_output_id = header.total_wires - 1;
-    let min_output_id = header.total_wires - outputs[0] - 1;
-    println!("Output ID range: {} - {}", min_output_id, max_output_id);
-    assert!(max_output_id - min_output_id == outputs[0]);
+    let output_range = header.total_wires - total_outputs..header.total_wires;
+    debug!("Output ID range: {:?}", output_range);
 
-    let output_wires: Vec<String> = (min_output_id + 1..max_output_id + 1)
-        .map(|n| format!("out_{}", n))
-        .collect();
+    let output_wires: Vec<String> = output_range.clone().map(|n| format!("out_{}", n)).collect();
 
-    println!("Circuit outputs: {:?}", output_wires);
-    println!("Number of outputs: {:?} -> {}", outputs, output_wires.len());
-    assert!(output_wires.len() == outputs[0]);
+    debug!("Circuit outputs: {:?}", output_wires);
+    debug!("Number of outputs: {:?} -> {}", outputs, output_wires.len());
 
-    circuit_outputs.insert("out".to_string(), output_wires);
+    circuit_outputs.insert("out".to_string(), output_wires.clone());
+
+    let wire_name = |id: usize| {
+        if id < inputs[0] {
+            format!("a_{}", id)
+        } else if id < total_inputs {
+            format!("b_{}", id)
+        } else if output_range.contains(&id) {
+            format!("out_{}", id)
+        } else {
+            id.to_string()
+        }
+    };
 
-    lines.next().unwrap();
     let mut gates: Vec<Gate> = Vec::new();
-    for line in lines {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        let gate_type = match parts.last().unwrap() {
-            &"AND" => GateType::AND,
-            &"OR" => GateType::OR,
-            &"XOR" => GateType::XOR,
-            &"INV" => GateType::NOT,
-            _ => panic!("Unknown gate type"),
+    let mut uses: Vec<(String, usize, usize)> = Vec::new();
+    let mut last_line_no = outputs_line_no;
+    for (line_no, line) in lines {
+        last_line_no = line_no;
+        let parts = tokenize(line);
+        if parts.is_empty() {
+            continue;
+        }
+
+        let &(op_column, op) = parts.last().unwrap();
+        let mut gate_type = match op {
+            "AND" => GateType::AND,
+            "OR" => GateType::OR,
+            "XOR" => GateType::XOR,
+            "INV" => GateType::NOT,
+            "EQW" => GateType::CONST,
+            // Extensions some generators emit, `MUX` reading `s a b`
+            "MUX" => GateType::MUX,
+            "MAJ" => GateType::MAJ,
+            // The constant is given in place of the input wire and read below
+            "EQ" => GateType::FALSE,
+            _ if op.parse::<usize>().is_ok() => {
+                return Err(ParseError::at_line(
+                    ParseErrorKind::Truncated("gate type"),
+                    line_no,
+                    op_column + op.len(),
+                ))
+            }
+            _ => {
+                return Err(ParseError::at_line(
+                    ParseErrorKind::UnknownGate(op.to_string()),
+                    line_no,
+                    op_column,
+                ))
+            }
         };
 
-        let input_wires: Vec<String> = parts[2..parts.len() - 2]
-            .iter()
-            .map(|&n| n.parse::<usize>().unwrap().to_string())
-            .collect();
+        if parts.len() < 3 {
+            return Err(ParseError::at_line(
+                ParseErrorKind::Truncated("gate wire counts"),
+                line_no,
+                op_column,
+            ));
+        }
+        let n_in = parse_number(parts[0], line_no)?;
+        let n_out = parse_number(parts[1], line_no)?;
+        let expected = if op == "EQ" { 1 } else { gate_type.arity() };
+        if n_in != expected {
+            return Err(ParseError::at_line(
+                ParseErrorKind::WrongArity {
+                    gate: op.to_string(),
+                    expected,
+                    found: n_in,
+                },
+                line_no,
+                parts[0].0,
+            ));
+        }
+        if n_out != 1 {
+            return Err(ParseError::at_line(
+                ParseErrorKind::Malformed(format!("gate {} must have exactly 1 output", op)),
+                line_no,
+                parts[1].0,
+            ));
+        }
+        if parts.len() != n_in + n_out + 3 {
+            return Err(ParseError::at_line(
+                ParseErrorKind::WrongArity {
+                    gate: op.to_string(),
+                    expected: n_in,
+                    found: parts.len().saturating_sub(n_out + 3),
+                },
+                line_no,
+                op_column,
+            ));
+        }
 
         let mut new_input_wires: Vec<String> = Vec::new();
-        for input_wire in &input_wires {
-            let input_parsed = input_wire.parse::<usize>().unwrap();
-            let in_input_range = input_parsed >= min_input_id && input_parsed <= max_input_id;
-            let in_output_range =
-                input_parsed >= min_output_id + 1 && input_parsed <= max_output_id;
-            if in_input_range {
-                let in_b_range = input_parsed >= inputs[0] && input_parsed <= max_input_id + 1;
-                let in_a_range = input_parsed >= min_input_id && input_parsed < inputs[0];
-                if in_b_range {
-                    let new_input_wire = format!("b_{}", input_wire);
-                    new_input_wires.push(new_input_wire);
-                } else if in_a_range {
-                    let new_input_wire = format!("a_{}", input_wire);
-                    new_input_wires.push(new_input_wire);
-                } else {
-                    panic!("Input wire not in a or b range: {}", input_wire);
-                }
-            } else {
-                if in_output_range {
-                    let new_input_wire = format!("out_{}", input_wire);
-                    new_input_wires.push(new_input_wire);
-                } else {
-                    new_input_wires.push(input_wire.to_string());
+        if op == "EQ" {
+            let token = parts[2];
+            gate_type = match parse_number(token, line_no)? {
+                0 => GateType::FALSE,
+                1 => GateType::TRUE,
+                value => {
+                    return Err(ParseError::at_line(
+                        ParseErrorKind::Malformed(format!(
+                            "EQ constant must be 0 or 1, not {}",
+                            value
+                        )),
+                        line_no,
+                        token.0,
+                    ))
                 }
+            };
+        }
+        for &token in &parts[2..2 + gate_type.arity()] {
+            let input_wire = parse_number(token, line_no)?;
+            if input_wire >= header.total_wires {
+                return Err(ParseError::at_line(
+                    ParseErrorKind::Malformed(format!(
+                        "wire {} is outside of the {} declared wires",
+                        input_wire, header.total_wires
+                    )),
+                    line_no,
+                    token.0,
+                ));
             }
+            let name = wire_name(input_wire);
+            uses.push((name.clone(), line_no, token.0));
+            new_input_wires.push(name);
         }
 
-        let output_wire = parts[parts.len() - 2].parse::<usize>().unwrap();
-        let in_output_range = output_wire >= min_output_id + 1 && output_wire <= max_output_id;
-        if in_output_range {
-            let new_output_wire = format!("out_{}", output_wire);
-            gates.push(Gate {
-                inputs: new_input_wires,
-                output: new_output_wire,
-                type_: gate_type,
-            });
-        } else {
-            gates.push(Gate {
-                inputs: new_input_wires,
-                output: output_wire.to_string(),
-                type_: gate_type,
-            });
+        let output_token = parts[parts.len() - 2];
+        let output_wire = parse_number(output_token, line_no)?;
+        if output_wire < total_inputs || output_wire >= header.total_wires {
+            return Err(ParseError::at_line(
+                ParseErrorKind::Malformed(format!(
+                    "gate output {} is an input or out of range",
+                    output_wire
+                )),
+                line_no,
+                output_token.0,
+            ));
         }
+        gates.push(Gate {
+            inputs: new_input_wires,
+            output: wire_name(output_wire),
+            type_: gate_type,
+        });
+    }
+
+    if gates.len() < header.total_gates {
+        return Err(ParseError::at_line(
+            ParseErrorKind::Truncated("gates"),
+            last_line_no + 1,
+            1,
+        ));
+    }
+    if gates.len() > header.total_gates {
+        return Err(ParseError::at_line(
+            ParseErrorKind::Malformed(format!(
+                "header declares {} gates but {} were found",
+                header.total_gates,
+                gates.len()
+            )),
+            last_line_no,
+            1,
+        ));
     }
 
     for gate in gates {
-        if gate.inputs.len() > 2 {
-            panic!("Gate has more than 2 inputs {:?}", gate);
-        }
         circuit.insert(gate.output, (gate.type_, gate.inputs));
     }
 
-    (circuit, circuit_inputs, circuit_outputs)
+    let driven: HashSet<&String> = circuit.keys().collect();
+    if let Some((wire, line, column)) = uses.iter().find(|(wire, _, _)| !driven.contains(wire)) {
+        return Err(ParseError::at_line(
+            ParseErrorKind::DanglingWire(wire.clone()),
+            *line,
+            *column,
+        ));
+    }
+    if let Some(wire) = output_wires.iter().find(|wire| !driven.contains(wire)) {
+        return Err(ParseError::at_line(
+            ParseErrorKind::DanglingWire(wire.clone()),
+            outputs_line_no,
+            1,
+        ));
+    }
+
+    Ok((circuit, circuit_inputs, circuit_outputs))
 }
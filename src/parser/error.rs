@@ -0,0 +1,95 @@
+use std::fmt::{Display, Formatter};
+
+/// Where in the source a parse error was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    /// 1-based line and column in a line-oriented format such as Bristol.
+    Line { line: usize, column: usize },
+    /// Dotted path to the offending value in a JSON document.
+    JsonPath(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The input ended before a required section was read.
+    Truncated(&'static str),
+    /// A token that should have been an integer.
+    InvalidNumber(String),
+    /// A gate or cell type the crate does not know about.
+    UnknownGate(String),
+    /// A gate whose number of inputs or outputs does not match its type.
+    WrongArity {
+        gate: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A wire that is read but never driven by an input or a gate.
+    DanglingWire(String),
+    /// A port direction other than `input` or `output`.
+    UnknownPortDirection(String),
+    /// Any other structural problem with the input.
+    Malformed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub location: Location,
+}
+
+impl ParseError {
+    pub fn at_line(kind: ParseErrorKind, line: usize, column: usize) -> Self {
+        Self {
+            kind,
+            location: Location::Line { line, column },
+        }
+    }
+
+    pub fn at_path(kind: ParseErrorKind, path: impl Into<String>) -> Self {
+        Self {
+            kind,
+            location: Location::JsonPath(path.into()),
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Line { line, column } => write!(f, "line {}, column {}", line, column),
+            Location::JsonPath(path) => write!(f, "{}", path),
+        }
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::Truncated(section) => write!(f, "input truncated, missing {}", section),
+            ParseErrorKind::InvalidNumber(token) => write!(f, "invalid number: {:?}", token),
+            ParseErrorKind::UnknownGate(gate) => write!(f, "unknown gate type: {}", gate),
+            ParseErrorKind::WrongArity {
+                gate,
+                expected,
+                found,
+            } => write!(
+                f,
+                "gate {} expects {} inputs, found {}",
+                gate, expected, found
+            ),
+            ParseErrorKind::DanglingWire(wire) => write!(f, "wire {} is never driven", wire),
+            ParseErrorKind::UnknownPortDirection(dir) => {
+                write!(f, "unknown port direction: {}", dir)
+            }
+            ParseErrorKind::Malformed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.kind, self.location)
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -1,8 +1,10 @@
 mod bristol;
+mod error;
 mod yosys;
 use crate::utils::GateType;
 
 pub use self::bristol::*;
+pub use self::error::*;
 pub use self::yosys::*;
 
 #[derive(Debug)]
@@ -1,8 +1,11 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
-use crate::utils::{Circuit, GateType};
+use crate::{
+    parser::{ParseError, ParseErrorKind},
+    utils::{Circuit, GateType, Ports},
+};
 
 use super::Gate;
 
@@ -16,6 +19,7 @@ struct YosysPort {
 struct YosysCell {
     #[serde(rename = "type")]
     type_: String,
+    #[serde(default)]
     port_directions: BTreeMap<String, String>,
     connections: BTreeMap<String, Vec<i32>>,
 }
@@ -32,101 +36,166 @@ struct YosysJson {
     modules: BTreeMap<String, YosysModule>,
 }
 
-fn handle_port_direction(port_name: &String, bit: &i32, map: &mut BTreeMap<String, Vec<String>>) {
-    if map.contains_key(port_name) {
-        map.get_mut(port_name).unwrap().push(format!("w_{}", *bit));
-    } else {
-        map.insert(port_name.clone(), vec![format!("w_{}", *bit)]);
+fn handle_port_direction(port_name: &str, bit: &i32, map: &mut Ports) {
+    map.entry(port_name.to_string())
+        .or_default()
+        .push(format!("w_{}", *bit));
+}
+
+/// Ports of cells whose inputs `GateType` doesn't read in alphabetical order, in the
+/// order it reads them.
+fn input_ports(type_: GateType) -> Option<[&'static str; 3]> {
+    match type_ {
+        // `$_MUX_` is `S ? B : A`
+        GateType::MUX => Some(["S", "B", "A"]),
+        _ => None,
     }
 }
 
+/// Builds a gate from a cell, recording every input wire with the JSON path it was read from.
 fn build_gate(
     cell: &YosysCell,
-    inputs: &BTreeMap<String, Vec<String>>,
-    outputs: &BTreeMap<String, Vec<String>>,
-) -> Gate {
-    let mut gate_out = String::new();
-    let mut gate_in: Vec<String> = Vec::new();
-    let mut merged: BTreeMap<&str, (Option<i32>, Option<&String>)> = BTreeMap::new();
-    for (key, value) in cell.connections.iter() {
-        merged.entry(key).or_insert((None, None)).0 = Some(value[0]);
-    }
-    for (key, value) in cell.port_directions.iter() {
-        merged.entry(key).or_insert((None, None)).1 = Some(value);
-    }
+    path: &str,
+    uses: &mut Vec<(String, String)>,
+) -> Result<Gate, ParseError> {
+    let type_: GateType = cell.type_.parse().map_err(|_| {
+        ParseError::at_path(
+            ParseErrorKind::UnknownGate(cell.type_.clone()),
+            format!("{}.type", path),
+        )
+    })?;
 
-    for (_, (connection, port_dir)) in merged {
-        match (connection, port_dir) {
-            (Some(connection_value), Some(dir)) if dir == "input" => {
-                gate_in.push(
-                    inputs
-                        .iter()
-                        .find(|(_name, bits)| bits.contains(&connection_value.to_string()))
-                        .map_or_else(
-                            || format!("w_{}", connection_value.to_string()),
-                            // |(input_name, _)| format!("{}_{}", input_name, connection_value),
-                            |(input_name, _)| format!("w_{}", connection_value),
-                            // |(_input_name, _)| connection_value.to_string(),
-                        ),
-                );
+    let mut gate_out: Option<String> = None;
+    let mut gate_in: Vec<(&str, String)> = Vec::new();
+
+    for (port, bits) in cell.connections.iter() {
+        let port_path = format!("{}.connections.{}", path, port);
+        let bit = match bits.as_slice() {
+            [bit] => *bit,
+            _ => {
+                return Err(ParseError::at_path(
+                    ParseErrorKind::Malformed(format!(
+                        "expected a single bit, found {}",
+                        bits.len()
+                    )),
+                    port_path,
+                ))
+            }
+        };
+        let dir = cell.port_directions.get(port).ok_or_else(|| {
+            ParseError::at_path(
+                ParseErrorKind::Malformed("missing port direction".to_string()),
+                format!("{}.port_directions.{}", path, port),
+            )
+        })?;
+        match dir.as_str() {
+            "input" => {
+                let wire = format!("w_{}", bit);
+                uses.push((wire.clone(), port_path));
+                gate_in.push((port.as_str(), wire));
+            }
+            "output" if gate_out.is_none() => gate_out = Some(format!("w_{}", bit)),
+            "output" => {
+                return Err(ParseError::at_path(
+                    ParseErrorKind::Malformed("cell has more than one output".to_string()),
+                    port_path,
+                ))
             }
-            (Some(connection_value), Some(dir)) if dir == "output" => {
-                gate_out = outputs
-                    .iter()
-                    .find(|(_name, bits)| bits.contains(&connection_value.to_string()))
-                    .map_or_else(
-                        || format!("w_{}", connection_value.to_string()),
-                        // |(output_name, _)| format!("{}_{}", output_name, connection_value),
-                        |(output_name, _)| format!("w_{}", connection_value),
-                        // |(_, _)| connection_value.to_string(),
-                    );
+            _ => {
+                return Err(ParseError::at_path(
+                    ParseErrorKind::UnknownPortDirection(dir.clone()),
+                    format!("{}.port_directions.{}", path, port),
+                ))
             }
-            (None, _) | (_, None) => panic!("Connection or port_dir is None"),
-            (_, Some(dir)) => panic!("Unknown port direction: {}", dir),
         }
     }
 
-    Gate {
-        inputs: gate_in,
-        output: gate_out,
-        type_: cell.type_.parse().unwrap(),
+    if gate_in.len() != type_.arity() {
+        return Err(ParseError::at_path(
+            ParseErrorKind::WrongArity {
+                gate: cell.type_.clone(),
+                expected: type_.arity(),
+                found: gate_in.len(),
+            },
+            format!("{}.connections", path),
+        ));
     }
+    if let Some(ports) = input_ports(type_) {
+        gate_in.sort_by_key(|(port, _)| ports.iter().position(|other| other == port));
+    }
+    let output = gate_out.ok_or_else(|| {
+        ParseError::at_path(
+            ParseErrorKind::Malformed("cell has no output".to_string()),
+            format!("{}.connections", path),
+        )
+    })?;
+
+    Ok(Gate {
+        inputs: gate_in.into_iter().map(|(_, wire)| wire).collect(),
+        output,
+        type_,
+    })
 }
 
-pub fn parse_yosys_json(
-    json_content: &str,
-) -> (
-    Circuit,
-    BTreeMap<String, Vec<String>>,
-    BTreeMap<String, Vec<String>>,
-) {
-    let mut inputs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+pub fn parse_yosys_json(json_content: &str) -> Result<(Circuit, Ports, Ports), ParseError> {
+    let mut inputs: Ports = BTreeMap::new();
     let mut circuit: Circuit = BTreeMap::new();
-    let mut outputs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut outputs: Ports = BTreeMap::new();
+
+    let parsed_circuit: YosysJson = serde_json::from_str(json_content).map_err(|e| {
+        ParseError::at_line(
+            ParseErrorKind::Malformed(e.to_string()),
+            e.line(),
+            e.column(),
+        )
+    })?;
 
-    let parsed_circuit: YosysJson = serde_json::from_str(json_content).unwrap();
+    let mut uses: Vec<(String, String)> = Vec::new();
+    let mut output_bits: Vec<(String, String)> = Vec::new();
 
-    for (_module_name, modules) in parsed_circuit.modules.iter() {
+    for (module_name, modules) in parsed_circuit.modules.iter() {
+        let module_path = format!("modules.{}", module_name);
         for (port_name, port) in modules.ports.iter() {
-            for bit in port.bits.iter() {
+            let port_path = format!("{}.ports.{}", module_path, port_name);
+            for (i, bit) in port.bits.iter().enumerate() {
                 match port.direction.as_str() {
-                    "input" => handle_port_direction(port_name, bit, &mut inputs),
-                    "output" => handle_port_direction(port_name, bit, &mut outputs),
-                    _ => panic!("Unknown port direction: {}", port.direction),
+                    "input" => {
+                        handle_port_direction(port_name, bit, &mut inputs);
+                        circuit.insert(format!("w_{}", bit), (GateType::INPUT, vec![]));
+                    }
+                    "output" => {
+                        handle_port_direction(port_name, bit, &mut outputs);
+                        output_bits
+                            .push((format!("w_{}", bit), format!("{}.bits.{}", port_path, i)));
+                    }
+                    _ => {
+                        return Err(ParseError::at_path(
+                            ParseErrorKind::UnknownPortDirection(port.direction.clone()),
+                            format!("{}.direction", port_path),
+                        ))
+                    }
                 }
-                circuit.insert(
-                    // format!("{}_{}", port_name.clone(), bit),
-                    format!("w_{}", bit),
-                    // bit.to_string(),
-                    (GateType::INPUT, vec![]),
-                );
             }
         }
 
-        for (_cell_name, cell) in modules.cells.iter() {
-            let gate = build_gate(cell, &inputs, &outputs);
+        for (cell_name, cell) in modules.cells.iter() {
+            let cell_path = format!("{}.cells.{}", module_path, cell_name);
+            let gate = build_gate(cell, &cell_path, &mut uses)?;
             circuit.insert(gate.output, (gate.type_, gate.inputs));
         }
     }
-    (circuit, inputs, outputs)
+
+    let driven: HashSet<&String> = circuit.keys().collect();
+    if let Some((wire, path)) = uses
+        .iter()
+        .chain(output_bits.iter())
+        .find(|(wire, _)| !driven.contains(wire))
+    {
+        return Err(ParseError::at_path(
+            ParseErrorKind::DanglingWire(wire.clone()),
+            path.clone(),
+        ));
+    }
+
+    Ok((circuit, inputs, outputs))
 }
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use crate::{ir::IndexedCircuit, utils::Circuit};
+
+/// Lane patterns for the six lowest inputs of an exhaustive enumeration.
+const LANES: [u64; 6] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+    0xFFFF_0000_FFFF_0000,
+    0xFFFF_FFFF_0000_0000,
+];
+
+/// Word for `input` in batch `batch` of an exhaustive enumeration of all assignments.
+///
+/// Lane `l` of batch `b` holds assignment number `b * 64 + l`, whose bit `i` is the value
+/// of input `i`. Inputs above the sixth are therefore constant within a batch.
+pub fn enumeration_word(input: usize, batch: usize) -> u64 {
+    if input < 6 {
+        LANES[input]
+    } else if (batch >> (input - 6)) & 1 == 1 {
+        u64::MAX
+    } else {
+        0
+    }
+}
+
+/// Mask of the lanes that hold real assignments when enumerating `inputs` inputs.
+pub fn enumeration_mask(inputs: usize) -> u64 {
+    if inputs >= 6 {
+        u64::MAX
+    } else {
+        (1u64 << (1 << inputs)) - 1
+    }
+}
+
+/// Number of 64-lane batches needed to enumerate `inputs` inputs.
+pub fn enumeration_batches(inputs: usize) -> usize {
+    1 << inputs.saturating_sub(6)
+}
+
+/// Evaluates an indexed circuit on 64 input assignments at once.
+///
+/// `inputs` holds one word per entry of `circuit.inputs`, where bit `i` of every word
+/// belongs to lane `i`. Returns one word per wire, indexed by `WireId`.
+pub fn simulate_indexed(circuit: &IndexedCircuit, inputs: &[u64]) -> Vec<u64> {
+    assert_eq!(
+        inputs.len(),
+        circuit.inputs.len(),
+        "Expected one word per input wire"
+    );
+    let mut values = vec![0u64; circuit.num_wires()];
+    for (wire, value) in circuit.inputs.iter().zip(inputs.iter()) {
+        values[wire.index()] = *value;
+    }
+    let mut operands: Vec<u64> = Vec::with_capacity(3);
+    for gate in circuit.gates.iter() {
+        operands.clear();
+        operands.extend(gate.inputs.iter().map(|input| values[input.index()]));
+        values[gate.output.index()] = gate.op.eval_word(&operands);
+    }
+    values
+}
+
+/// Bit-sliced simulation of a string-keyed circuit: every input word carries 64
+/// independent assignments, and every wire's word in the result holds its value in
+/// each lane.
+pub fn simulate_sliced(circuit: &Circuit, inputs: &BTreeMap<String, u64>) -> BTreeMap<String, u64> {
+    let indexed = IndexedCircuit::from_circuit(circuit);
+    let names = indexed.names.as_ref().unwrap();
+    let words: Vec<u64> = indexed
+        .inputs
+        .iter()
+        .map(|wire| {
+            let name = &names[wire.index()];
+            *inputs
+                .get(name)
+                .unwrap_or_else(|| panic!("Missing value for input wire {}", name))
+        })
+        .collect();
+
+    simulate_indexed(&indexed, &words)
+        .into_iter()
+        .zip(names.iter())
+        .map(|(value, name)| (name.clone(), value))
+        .collect()
+}
+
+/// Evaluates a circuit on cleartext bits, e.g. the values produced by `wire_values`.
+/// Returns the value of every wire, so the result can be compared against the output
+/// of `Evaluator::run`.
+pub fn simulate(circuit: &Circuit, inputs: &BTreeMap<String, i32>) -> BTreeMap<String, i32> {
+    let words: BTreeMap<String, u64> = inputs
+        .iter()
+        .map(|(wire, bit)| (wire.clone(), (*bit & 1) as u64))
+        .collect();
+    simulate_sliced(circuit, &words)
+        .into_iter()
+        .map(|(wire, value)| (wire, (value & 1) as i32))
+        .collect()
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::BTreeMap,
     fmt::{Display, Formatter},
     str::FromStr,
 };
@@ -11,7 +11,9 @@ use sha2::Sha256;
 use fernet::Fernet;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+use crate::ir::IndexedCircuit;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Clone, Copy)]
 pub enum GateType {
     AND,
     NOT,
@@ -24,6 +26,13 @@ pub enum GateType {
     XNOR,
     CONST,
     INPUT,
+    /// Constant wires, as Bristol's `EQ` gate produces them.
+    TRUE,
+    FALSE,
+    /// `s ? a : b` over inputs `[s, a, b]`.
+    MUX,
+    /// Majority of three inputs.
+    MAJ,
 }
 
 impl Display for GateType {
@@ -40,11 +49,75 @@ impl Display for GateType {
             GateType::XNOR => write!(f, "XNOR"),
             GateType::CONST => write!(f, "CONST"),
             GateType::INPUT => write!(f, "INPUT"),
+            GateType::TRUE => write!(f, "TRUE"),
+            GateType::FALSE => write!(f, "FALSE"),
+            GateType::MUX => write!(f, "MUX"),
+            GateType::MAJ => write!(f, "MAJ"),
+        }
+    }
+}
+
+impl GateType {
+    /// Number of input wires a gate of this type reads.
+    pub fn arity(&self) -> usize {
+        match self {
+            GateType::INPUT | GateType::TRUE | GateType::FALSE => 0,
+            GateType::NOT | GateType::CONST => 1,
+            GateType::MUX | GateType::MAJ => 3,
+            _ => 2,
+        }
+    }
+
+    /// Whether the gate multiplies its inputs, i.e. is not an affine function of them.
+    /// These are the gates that cost a round in GMW-style protocols.
+    pub fn is_nonlinear(&self) -> bool {
+        !matches!(
+            self,
+            GateType::XOR
+                | GateType::XNOR
+                | GateType::NOT
+                | GateType::CONST
+                | GateType::INPUT
+                | GateType::TRUE
+                | GateType::FALSE
+        )
+    }
+
+    /// Evaluates the gate on 64 independent lanes at once, one bit per lane.
+    /// `CONST` and `INPUT` pass their first input through unchanged.
+    pub fn eval_word(&self, inputs: &[u64]) -> u64 {
+        let a = || inputs[0];
+        let b = || inputs[1];
+        let c = || inputs[2];
+        match self {
+            GateType::AND => a() & b(),
+            GateType::OR => a() | b(),
+            GateType::NOR => !(a() | b()),
+            GateType::ORNOT => a() | !b(),
+            GateType::NAND => !(a() & b()),
+            GateType::ANDNOT => a() & !b(),
+            GateType::XNOR => !(a() ^ b()),
+            GateType::XOR => a() ^ b(),
+            GateType::NOT => !a(),
+            GateType::CONST => a(),
+            GateType::INPUT => a(),
+            GateType::TRUE => u64::MAX,
+            GateType::FALSE => 0,
+            GateType::MUX => (a() & b()) | (!a() & c()),
+            GateType::MAJ => (a() & b()) | (c() & (a() | b())),
         }
     }
+
+    /// Truth table of the gate on a single set of input bits.
+    pub fn eval(&self, inputs: &[bool]) -> bool {
+        let words: Vec<u64> = inputs.iter().map(|&bit| bit as u64).collect();
+        self.eval_word(&words) & 1 == 1
+    }
 }
 
 pub type Circuit = BTreeMap<String, (GateType, Vec<String>)>;
+/// Named groups of wires, e.g. the `a`/`b` inputs or the `out` bits of a circuit.
+pub type Ports = BTreeMap<String, Vec<String>>;
 type HmacSha256 = Hmac<Sha256>;
 
 impl FromStr for GateType {
@@ -61,6 +134,8 @@ impl FromStr for GateType {
             "$_ANDNOT_" => Ok(GateType::ANDNOT),
             "$_ORNOT_" => Ok(GateType::ORNOT),
             "$_XNOR_" => Ok(GateType::XNOR),
+            "$_MUX_" => Ok(GateType::MUX),
+            "$_MAJ_" => Ok(GateType::MAJ),
             _ => Err(()),
         }
     }
@@ -103,52 +178,25 @@ pub fn decrypt(key: String, data: String) -> Result<Vec<u8>, Box<dyn std::error:
     fernet.decrypt(&data).map_err(Into::into)
 }
 
-pub fn bytes_xor(a: &Vec<u8>, b: &Vec<u8>) -> Vec<u8> {
+pub fn bytes_xor(a: &[u8], b: &[u8]) -> Vec<u8> {
     a.iter().zip(b.iter()).map(|(x, y)| x ^ y).collect()
 }
 
+/// Wires of `circuit` in topological order, inputs first, as `IndexedCircuit` numbers
+/// them. Panics if the circuit has a cycle.
 pub fn topo_sort_wires(circuit: &Circuit) -> Vec<String> {
-    let mut sorted = Vec::new();
-    let mut visited = HashSet::new();
-    let mut temp_mark = HashSet::new();
-
-    fn visit(
-        node: &str,
-        circuit: &Circuit,
-        visited: &mut HashSet<String>,
-        temp_mark: &mut HashSet<String>,
-        sorted: &mut Vec<String>,
-    ) -> Result<(), &'static str> {
-        if temp_mark.contains(node) {
-            return Err("Circuit has a cycle");
-        }
-        if !visited.contains(node) {
-            temp_mark.insert(node.to_string());
-            if let Some((_, inputs)) = circuit.get(node) {
-                for input in inputs {
-                    visit(input, circuit, visited, temp_mark, sorted)?;
-                }
-            }
-            temp_mark.remove(node);
-            visited.insert(node.to_string());
-            sorted.push(node.to_string());
-        }
-        Ok(())
-    }
-
-    for node in circuit.keys() {
-        if let Err(e) = visit(node, circuit, &mut visited, &mut temp_mark, &mut sorted) {
-            panic!("{}", e);
-        }
-    }
-    sorted
+    IndexedCircuit::from_circuit(circuit)
+        .names
+        .expect("Lowered circuits keep their names")
 }
 
-pub fn wire_values(input_keys: &Vec<String>, value: i32) -> BTreeMap<String, i32> {
+/// Bits of `value` on `input_keys`, least significant first. Bits past the wires are
+/// dropped; `encoding::Encoding` checks the width and encodes wider values.
+pub fn wire_values(input_keys: &[String], value: i32) -> BTreeMap<String, i32> {
     let bits = format!("{:b}", value).chars().rev().collect::<String>();
     let bits_padded = format!("{:0<width$}", bits, width = input_keys.len());
     input_keys
-        .into_iter()
+        .iter()
         .zip(bits_padded.chars())
         .map(|(key, bit)| (key.to_string(), bit.to_digit(10).unwrap() as i32))
         .collect()
@@ -161,7 +209,9 @@ fn string_to_binary(string: &str) -> String {
         .collect::<String>()
 }
 
-pub fn wire_values_str(input_keys: &Vec<String>, value: String) -> BTreeMap<String, i32> {
+/// The characters of `value` as bytes, most significant bit first. Characters are
+/// truncated to their low byte; `encoding::Encoding` encodes their UTF-8 bytes instead.
+pub fn wire_values_str(input_keys: &[String], value: String) -> BTreeMap<String, i32> {
     let binary_string = string_to_binary(&value);
     let mut bits_str = binary_string.split("").collect::<Vec<&str>>();
     bits_str.pop();
@@ -177,7 +227,7 @@ pub fn wire_values_str(input_keys: &Vec<String>, value: String) -> BTreeMap<Stri
     }
 
     input_keys
-        .into_iter()
+        .iter()
         .zip(bits)
         .map(|(key, bit)| (key.to_string(), bit))
         .collect()
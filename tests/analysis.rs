@@ -0,0 +1,158 @@
+mod common;
+
+use egg::{AstSize, EGraph, Extractor, RecExpr, Runner};
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    optimizer::{
+        constant_rules, optimize_with_config, CircuitAnalysis, CircuitLang, ClassFunction,
+        OptimizerConfig, SaturationMode, Sweeping,
+    },
+    utils::{Circuit, GateType},
+};
+
+use common::{gate, load_bristol_adder, nonlinear_gates};
+
+fn swept() -> EGraph<CircuitLang, CircuitAnalysis> {
+    EGraph::new(CircuitAnalysis::new(Sweeping::TruthTables))
+}
+
+fn add(egraph: &mut EGraph<CircuitLang, CircuitAnalysis>, expr: &str) -> egg::Id {
+    let id = egraph.add_expr(&expr.parse().unwrap());
+    egraph.rebuild();
+    egraph.find(id)
+}
+
+#[test]
+fn complements_fold_to_constants() {
+    let mut egraph = swept();
+    let id = add(&mut egraph, "(and a (not a))");
+    assert_eq!(
+        egraph[id].data.as_ref().and_then(ClassFunction::constant),
+        Some(false)
+    );
+    let id = add(&mut egraph, "(xnor b b)");
+    assert_eq!(
+        egraph[id].data.as_ref().and_then(ClassFunction::constant),
+        Some(true)
+    );
+}
+
+#[test]
+fn tables_ignore_unread_wires() {
+    let mut egraph = swept();
+    // (a & b) | (a & !b) is a
+    let id = add(&mut egraph, "(or (and a b) (and a (not b)))");
+    let function = egraph[id].data.clone().unwrap();
+    assert_eq!(function.support, vec!["a".to_string()]);
+    assert_eq!(id, add(&mut egraph, "a"));
+}
+
+#[test]
+fn classes_with_one_function_merge() {
+    let mut egraph = swept();
+    let xor = add(&mut egraph, "(xor a b)");
+    let sum_of_products = add(&mut egraph, "(or (andnot a b) (andnot b a))");
+    assert_eq!(egraph.find(xor), sum_of_products);
+    // Argument order doesn't matter to the table
+    let nand = add(&mut egraph, "(nand c d)");
+    let swapped = add(&mut egraph, "(nand d c)");
+    assert_eq!(egraph.find(nand), swapped);
+    assert_ne!(sum_of_products, add(&mut egraph, "(xnor a b)"));
+}
+
+#[test]
+fn constants_decide_wide_gates() {
+    let wide = "(xor (xor a b) (xor c (xor d (xor e f))))";
+    let mut egraph = swept();
+    let wide_id = add(&mut egraph, wide);
+    // Six wires are too many for a table
+    assert!(egraph[wide_id].data.is_none());
+    let id = add(&mut egraph, &format!("(andnot {} (or a (not a)))", wide));
+    assert_eq!(
+        egraph[id].data.as_ref().and_then(ClassFunction::constant),
+        Some(false)
+    );
+
+    // The constant input never appears as a `false` node, so only the conditional
+    // rules can drop it
+    let expr: RecExpr<CircuitLang> = format!("(xor {} (and g (not g)))", wide).parse().unwrap();
+    let runner = Runner::default()
+        .with_egraph(swept())
+        .with_expr(&expr)
+        .run(&constant_rules());
+    let (size, best) = Extractor::new(&runner.egraph, AstSize).find_best(runner.roots[0]);
+    assert_eq!(size, 11);
+    assert_eq!(best.to_string(), wide);
+}
+
+#[test]
+fn constant_rules_cover_every_binary_gate() {
+    let rules = constant_rules();
+    // Both inputs of eight gates, each set to either constant
+    assert_eq!(rules.len(), 32);
+    assert!(rules
+        .iter()
+        .any(|rule| rule.name.as_str() == "andnot-1-true"));
+}
+
+#[test]
+fn off_tracks_nothing() {
+    let mut egraph: EGraph<CircuitLang, CircuitAnalysis> = EGraph::default();
+    let xor = egraph.add_expr(&"(xor a b)".parse().unwrap());
+    let other = egraph.add_expr(&"(or (andnot a b) (andnot b a))".parse().unwrap());
+    egraph.rebuild();
+    assert!(egraph[xor].data.is_none());
+    assert_ne!(egraph.find(xor), egraph.find(other));
+}
+
+#[test]
+fn sweeping_keeps_adder_equivalent() {
+    let (circuit, _, outs) = load_bristol_adder();
+    let outputs = outs["out"].clone();
+    for mode in [SaturationMode::PerOutput, SaturationMode::Shared] {
+        let plain = OptimizerConfig {
+            mode,
+            iter_limit: 5,
+            ..Default::default()
+        };
+        let swept = OptimizerConfig {
+            sweeping: Sweeping::TruthTables,
+            ..plain.clone()
+        };
+        let (baseline, _) = optimize_with_config(circuit.clone(), outputs.clone(), &plain).unwrap();
+        let (optimized, _) =
+            optimize_with_config(circuit.clone(), outputs.clone(), &swept).unwrap();
+        assert!(nonlinear_gates(&optimized) <= nonlinear_gates(&baseline));
+        assert_eq!(
+            check_sat(&circuit, &optimized, &outputs),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+}
+
+#[test]
+fn constant_outputs_are_extracted_as_constants() {
+    // `z = a & !a` costs an AND table under classic Yao, the NOT is free
+    let circuit = Circuit::from([
+        ("a".to_string(), gate(GateType::INPUT, &[])),
+        ("na".to_string(), gate(GateType::NOT, &["a"])),
+        ("z".to_string(), gate(GateType::AND, &["a", "na"])),
+    ]);
+    let outputs = vec!["z".to_string()];
+    let config = OptimizerConfig {
+        sweeping: Sweeping::TruthTables,
+        iter_limit: 5,
+        ..Default::default()
+    };
+    let (optimized, report) =
+        optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+
+    assert_eq!(optimized["z"], gate(GateType::FALSE, &[]));
+    assert!(report.original_cost > 4.0);
+    assert!(report.optimized_cost < 1.0);
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}
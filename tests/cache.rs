@@ -0,0 +1,130 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    optimizer::{cache_key, circuit_rules, optimize_with_config, OptimizerConfig, SaturationMode},
+    parser::parse_bristol_fashion,
+    utils::{Circuit, GateType},
+};
+
+use common::gate;
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rs-cache-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn adder() -> (Circuit, Vec<String>) {
+    let contents = fs::read_to_string("./circuits/adder64.txt").unwrap();
+    let (circuit, _, outs) = parse_bristol_fashion(&contents).unwrap();
+    (circuit, outs["out"].clone())
+}
+
+#[test]
+fn second_run_reads_cache() {
+    let dir = cache_dir("hit");
+    let (circuit, outputs) = adder();
+    let config = OptimizerConfig {
+        cache: Some(dir.clone()),
+        ..Default::default()
+    };
+    let (first, report) = optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+    assert!(!report.cache_hit);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let (second, cached) = optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+    assert!(cached.cache_hit);
+    assert_eq!(second, first);
+    assert_eq!(cached.optimized_gate_counts, report.optimized_gate_counts);
+    assert_eq!(
+        check_sat(&circuit, &second, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn constant_outputs_are_cached() {
+    let dir = cache_dir("constant");
+    // `z = a & false` folds to a constant, whose cost must survive the JSON entry
+    let circuit = Circuit::from([
+        ("a".to_string(), gate(GateType::INPUT, &[])),
+        ("f".to_string(), gate(GateType::FALSE, &[])),
+        ("z".to_string(), gate(GateType::AND, &["a", "f"])),
+    ]);
+    let outputs = vec!["z".to_string()];
+    let config = OptimizerConfig {
+        cache: Some(dir.clone()),
+        ..Default::default()
+    };
+    let (first, report) = optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+    assert!(!report.cache_hit);
+
+    let (second, cached) = optimize_with_config(circuit, outputs, &config).unwrap();
+    assert!(cached.cache_hit);
+    assert_eq!(second, first);
+    assert_eq!(cached.outputs.len(), report.outputs.len());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn key_depends_on_circuit_rules_and_config() {
+    let (circuit, outputs) = adder();
+    let rules = circuit_rules();
+    let config = OptimizerConfig::default();
+    let key = cache_key(&circuit, &outputs, &rules, &config);
+    assert_eq!(key.len(), 64);
+    assert_eq!(key, cache_key(&circuit, &outputs, &rules, &config));
+
+    let mut changed = circuit.clone();
+    let (wire, _) = changed
+        .iter()
+        .find(|(_, (op, _))| *op == GateType::AND)
+        .map(|(wire, gate)| (wire.clone(), gate.clone()))
+        .unwrap();
+    changed.get_mut(&wire).unwrap().0 = GateType::OR;
+    assert_ne!(key, cache_key(&changed, &outputs, &rules, &config));
+    assert_ne!(key, cache_key(&circuit, &outputs[1..], &rules, &config));
+    assert_ne!(key, cache_key(&circuit, &outputs, &rules[1..], &config));
+    let shared = OptimizerConfig {
+        mode: SaturationMode::Shared,
+        ..Default::default()
+    };
+    assert_ne!(key, cache_key(&circuit, &outputs, &rules, &shared));
+
+    // Settings that don't change the result share entries
+    let threaded = OptimizerConfig {
+        threads: Some(1),
+        cache: Some(cache_dir("unused")),
+        ..Default::default()
+    };
+    assert_eq!(key, cache_key(&circuit, &outputs, &rules, &threaded));
+}
+
+#[test]
+fn unreadable_entry_is_recomputed() {
+    let dir = cache_dir("corrupt");
+    let (circuit, outputs) = adder();
+    let config = OptimizerConfig {
+        cache: Some(dir.clone()),
+        ..Default::default()
+    };
+    let key = cache_key(&circuit, &outputs, &circuit_rules(), &config);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(format!("{}.json", key)), "not json").unwrap();
+
+    let (optimized, report) =
+        optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+    assert!(!report.cache_hit);
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+    // The broken entry was replaced
+    let (_, report) = optimize_with_config(circuit, outputs, &config).unwrap();
+    assert!(report.cache_hit);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,215 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use fernet::Fernet;
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    evaluator::Evaluator,
+    garbler::Garbler,
+    optimizer::{
+        cleanup, collapse_buffers, fold_constants, optimize, optimize_with_config, CleanupStats,
+        OptimizerConfig, Partition, SaturationMode,
+    },
+    parser::parse_bristol_fashion,
+    simulator::simulate,
+    utils::{wire_values, Circuit, GateType, Ports},
+};
+
+use common::gate;
+
+/// `out_8 = (a & 1) ^ (b ^ 0)` through a buffer, next to a gate nothing reads.
+const BRISTOL_CONSTANTS: &str = "7 9
+2 1 1
+1 1
+
+1 1 1 2 EQ
+2 1 0 2 3 AND
+1 1 0 4 EQ
+2 1 1 4 5 XOR
+1 1 3 6 EQW
+2 1 0 1 7 AND
+2 1 6 5 8 XOR
+";
+
+#[test]
+fn cleanup_folds_bristol_constants() {
+    let (original, _, outs) = parse_bristol_fashion(BRISTOL_CONSTANTS).unwrap();
+    let mut circuit = original.clone();
+    let stats = cleanup(&mut circuit, &outs["out"]);
+
+    assert_eq!(
+        stats,
+        CleanupStats {
+            folded_gates: 2,
+            collapsed_buffers: 3,
+            dead_gates: 3,
+        }
+    );
+    let expected: Circuit = [
+        ("a_0".to_string(), gate(GateType::INPUT, &[])),
+        ("b_1".to_string(), gate(GateType::INPUT, &[])),
+        ("out_8".to_string(), gate(GateType::XOR, &["a_0", "b_1"])),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(circuit, expected);
+    assert_eq!(
+        check_sat(&original, &circuit, &outs["out"]),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn fold_constants_leaves_at_most_one_wire() {
+    let cases = [
+        (
+            gate(GateType::AND, &["x", "t"]),
+            gate(GateType::CONST, &["x"]),
+        ),
+        (gate(GateType::AND, &["x", "f"]), gate(GateType::FALSE, &[])),
+        (gate(GateType::OR, &["t", "x"]), gate(GateType::TRUE, &[])),
+        (
+            gate(GateType::XNOR, &["x", "f"]),
+            gate(GateType::NOT, &["x"]),
+        ),
+        (gate(GateType::XOR, &["x", "x"]), gate(GateType::FALSE, &[])),
+        (
+            gate(GateType::NAND, &["x", "x"]),
+            gate(GateType::NOT, &["x"]),
+        ),
+        (gate(GateType::NOT, &["f"]), gate(GateType::TRUE, &[])),
+        (
+            gate(GateType::AND, &["x", "y"]),
+            gate(GateType::AND, &["x", "y"]),
+        ),
+    ];
+    for (before, after) in cases {
+        let mut circuit: Circuit = [
+            ("x".to_string(), gate(GateType::INPUT, &[])),
+            ("y".to_string(), gate(GateType::INPUT, &[])),
+            ("t".to_string(), gate(GateType::TRUE, &[])),
+            ("f".to_string(), gate(GateType::FALSE, &[])),
+            ("g".to_string(), before.clone()),
+        ]
+        .into_iter()
+        .collect();
+        let folded = fold_constants(&mut circuit);
+        assert_eq!(circuit["g"], after, "folding {:?}", before);
+        assert_eq!(folded, (before != after) as usize);
+    }
+}
+
+#[test]
+fn optimize_reports_cleanup() {
+    let (circuit, _, outs) = parse_bristol_fashion(BRISTOL_CONSTANTS).unwrap();
+    let config = OptimizerConfig {
+        mode: SaturationMode::Shared,
+        ..Default::default()
+    };
+    let (optimized, report) =
+        optimize_with_config(circuit.clone(), outs["out"].clone(), &config).unwrap();
+
+    assert_eq!(report.original_gates, circuit.len());
+    assert_eq!(report.cleanup_before.folded_gates, 2);
+    assert_eq!(report.cleanup_before.dead_gates, 3);
+    assert_eq!(report.cleanup_after, CleanupStats::default());
+    assert_eq!(optimized.len(), 3);
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outs["out"]),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn constant_outputs_can_be_garbled() {
+    let circuit: Circuit = [
+        ("a".to_string(), gate(GateType::INPUT, &[])),
+        ("b".to_string(), gate(GateType::INPUT, &[])),
+        ("na".to_string(), gate(GateType::NOT, &["a"])),
+        ("zero".to_string(), gate(GateType::XOR, &["a", "a"])),
+        ("one".to_string(), gate(GateType::OR, &["a", "na"])),
+        ("copy".to_string(), gate(GateType::CONST, &["b"])),
+    ]
+    .into_iter()
+    .collect();
+    let outputs: Vec<String> = ["zero", "one", "copy"].map(String::from).to_vec();
+    let (optimized, _) =
+        optimize_with_config(circuit.clone(), outputs.clone(), &Default::default()).unwrap();
+    assert_eq!(optimized["zero"].0, GateType::FALSE);
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+
+    let ins: Ports = [("in".to_string(), vec!["a".to_string(), "b".to_string()])].into();
+    let outs: Ports = [("out".to_string(), outputs.clone())].into();
+    let garbler = Garbler::new(Fernet::generate_key(), optimized.clone(), ins, outs);
+    let (wire_to_keys, garbled_gates) = garbler.build();
+    for value in 0..4 {
+        let inputs = wire_values(&["a".to_string(), "b".to_string()], value);
+        let labels: BTreeMap<String, String> = inputs
+            .iter()
+            .map(|(wire, bit)| {
+                let keys = &wire_to_keys[wire];
+                let label = if *bit == 0 { &keys.0 } else { &keys.1 };
+                (wire.clone(), label.clone())
+            })
+            .collect();
+        let mut evaluator = Evaluator::new(
+            optimized.clone(),
+            outputs.clone(),
+            wire_to_keys.clone(),
+            garbled_gates.clone(),
+        );
+        let garbled = evaluator.run(vec![labels]);
+        let expected = simulate(&circuit, &inputs);
+        for wire in outputs.iter() {
+            assert_eq!(garbled[wire], expected[wire], "mismatch on {}", wire);
+        }
+    }
+}
+
+#[test]
+fn outputs_may_be_inputs() {
+    let circuit = Circuit::from([
+        ("a".to_string(), gate(GateType::INPUT, &[])),
+        ("b".to_string(), gate(GateType::INPUT, &[])),
+        ("o".to_string(), gate(GateType::AND, &["a", "b"])),
+    ]);
+    let outputs = vec!["o".to_string(), "a".to_string()];
+    let configs = [
+        OptimizerConfig::default(),
+        OptimizerConfig {
+            mode: SaturationMode::Shared,
+            ..Default::default()
+        },
+        OptimizerConfig {
+            partition: Partition::Gates(1),
+            ..Default::default()
+        },
+    ];
+    for config in configs {
+        let (optimized, _) =
+            optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+        assert_eq!(optimized["a"], gate(GateType::INPUT, &[]));
+        assert_eq!(
+            check_sat(&circuit, &optimized, &outputs),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+    assert_eq!(
+        optimize(circuit.clone(), outputs.clone())["a"],
+        circuit["a"]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Circuit has a cycle of buffers")]
+fn buffer_cycles_are_rejected() {
+    let mut circuit = Circuit::from([
+        ("x".to_string(), gate(GateType::CONST, &["y"])),
+        ("y".to_string(), gate(GateType::CONST, &["x"])),
+    ]);
+    collapse_buffers(&mut circuit, &["x".to_string()]);
+}
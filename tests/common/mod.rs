@@ -0,0 +1,49 @@
+// Every test crate compiles its own copy and uses only some of the helpers
+#![allow(dead_code)]
+
+use std::fs::read_to_string;
+
+use rs::{
+    parser::parse_bristol_fashion,
+    utils::{Circuit, GateType, Ports},
+};
+
+/// A gate reading `inputs`, as a `Circuit` stores it.
+pub fn gate(op: GateType, inputs: &[&str]) -> (GateType, Vec<String>) {
+    (op, inputs.iter().map(|input| input.to_string()).collect())
+}
+
+/// Adds `wire = op(inputs)` to `circuit`.
+pub fn add_gate(circuit: &mut Circuit, wire: &str, op: GateType, inputs: &[&str]) {
+    circuit.insert(wire.to_string(), gate(op, inputs));
+}
+
+/// Gates that need a table under free-XOR, where XOR, XNOR, NOT and buffers are free.
+pub fn non_free_gates(circuit: &Circuit) -> usize {
+    circuit
+        .values()
+        .filter(|(op, _)| {
+            !matches!(
+                op,
+                GateType::XOR
+                    | GateType::XNOR
+                    | GateType::NOT
+                    | GateType::CONST
+                    | GateType::INPUT
+                    | GateType::TRUE
+                    | GateType::FALSE
+            )
+        })
+        .count()
+}
+
+/// Gates that cost a round in GMW-style protocols, see `GateType::is_nonlinear`.
+pub fn nonlinear_gates(circuit: &Circuit) -> usize {
+    circuit.values().filter(|(op, _)| op.is_nonlinear()).count()
+}
+
+/// The bundled 64-bit Bristol adder with its input and output ports.
+pub fn load_bristol_adder() -> (Circuit, Ports, Ports) {
+    let contents = read_to_string("./circuits/adder64.txt").unwrap();
+    parse_bristol_fashion(&contents).unwrap()
+}
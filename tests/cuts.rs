@@ -0,0 +1,91 @@
+mod common;
+
+use std::fs::read_to_string;
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    optimizer::{
+        npn_canonical, optimize, rewrite_cuts, McDatabase, NpnTransform, XagImpl, MC_DATABASE,
+    },
+    parser::{parse_bristol_fashion, parse_yosys_json},
+    utils::GateType,
+};
+
+use common::nonlinear_gates;
+
+#[test]
+fn database_covers_every_npn_class() {
+    assert_eq!(MC_DATABASE.len(), 222);
+    for &(table, ands, output) in MC_DATABASE {
+        let implementation = XagImpl {
+            ands: ands.to_vec(),
+            output,
+        };
+        assert_eq!(implementation.eval(), table);
+        assert_eq!(npn_canonical(table).0, table);
+        // Every four-input function has multiplicative complexity at most 3
+        assert!(implementation.and_count() <= 3);
+    }
+    assert_eq!(McDatabase::new().classes(), 222);
+}
+
+#[test]
+fn lookup_undoes_the_npn_transform() {
+    let mut database = McDatabase::new();
+    for table in (0..=u16::MAX).step_by(257) {
+        let implementation = database.lookup(table).clone();
+        assert_eq!(implementation.eval(), table, "table {:#06x}", table);
+
+        let transform = NpnTransform {
+            permutation: [2, 0, 3, 1],
+            negations: 0b0110,
+            output: true,
+        };
+        assert_eq!(
+            npn_canonical(transform.apply(table)).0,
+            npn_canonical(table).0
+        );
+    }
+    // AND4 needs three ANDs, XOR4 none
+    assert_eq!(database.lookup(0x8000).and_count(), 3);
+    assert_eq!(database.lookup(0x6996).and_count(), 0);
+}
+
+#[test]
+fn cut_rewriting_keeps_bristol_adder_optimal() {
+    let (circuit, _, outs) =
+        parse_bristol_fashion(&read_to_string("./circuits/adder64.txt").unwrap()).unwrap();
+    let outputs = &outs["out"];
+    let rewritten = rewrite_cuts(&circuit, outputs);
+
+    assert_eq!(nonlinear_gates(&circuit), 63);
+    assert_eq!(nonlinear_gates(&rewritten), 63);
+    // No round can improve on it, so the adder comes back as it was
+    assert_eq!(rewritten, circuit);
+    assert_eq!(
+        check_sat(&circuit, &rewritten, outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn cut_rewriting_beats_saturation_on_yosys_adder() {
+    let (circuit, _, outs) =
+        parse_yosys_json(&read_to_string("./circuits/synth_add64.json").unwrap()).unwrap();
+    let outputs: Vec<String> = outs.values().flatten().cloned().collect();
+    let rewritten = rewrite_cuts(&circuit, &outputs);
+    let saturated = optimize(circuit.clone(), outputs.clone());
+
+    // A 64-bit adder needs 63 ANDs, the carry out of the top bit is dropped
+    assert_eq!(nonlinear_gates(&rewritten), 63);
+    assert!(nonlinear_gates(&rewritten) < nonlinear_gates(&saturated));
+    assert!(rewritten.values().all(|(op, _)| matches!(
+        op,
+        GateType::INPUT | GateType::AND | GateType::XOR | GateType::NOT | GateType::CONST
+    )));
+    assert!(outputs.iter().all(|output| rewritten.contains_key(output)));
+    assert_eq!(
+        check_sat(&circuit, &rewritten, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}
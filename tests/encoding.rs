@@ -0,0 +1,189 @@
+use std::fs::read_to_string;
+
+use num_bigint::{BigInt, BigUint};
+use rs::{
+    encoding::{BitOrder, Encoding, EncodingError, Endianness, Value, ValueKind},
+    parser::parse_bristol_fashion,
+    simulator::simulate,
+    utils::{wire_values, wire_values_str},
+};
+
+fn wires(n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("w_{}", i)).collect()
+}
+
+/// The encoded bits in wire order.
+fn bits(encoding: Encoding, n: usize, value: impl Into<Value>) -> String {
+    let wires = wires(n);
+    let values = encoding.encode(&wires, &value.into()).unwrap();
+    wires.iter().map(|wire| values[wire].to_string()).collect()
+}
+
+fn round_trip(encoding: Encoding, n: usize, value: Value, kind: ValueKind) -> Value {
+    let wires = wires(n);
+    let values = encoding.encode(&wires, &value).unwrap();
+    encoding.decode(&wires, &values, kind).unwrap()
+}
+
+const LAYOUTS: [Encoding; 4] = [
+    Encoding {
+        bit_order: BitOrder::LsbFirst,
+        endianness: Endianness::Little,
+    },
+    Encoding {
+        bit_order: BitOrder::MsbFirst,
+        endianness: Endianness::Little,
+    },
+    Encoding {
+        bit_order: BitOrder::LsbFirst,
+        endianness: Endianness::Big,
+    },
+    Encoding {
+        bit_order: BitOrder::MsbFirst,
+        endianness: Endianness::Big,
+    },
+];
+
+#[test]
+fn default_layout_matches_wire_values() {
+    for value in [0, 1, 6, 999, i32::MAX] {
+        let wires = wires(40);
+        let encoded = Encoding::default()
+            .encode(&wires, &Value::from(value as u64))
+            .unwrap();
+        assert_eq!(encoded, wire_values(&wires, value));
+    }
+    // Any width works when nothing is split into bytes
+    assert_eq!(bits(Encoding::default(), 3, 6u64), "011");
+}
+
+#[test]
+fn layouts_order_bytes_and_bits() {
+    let [_, msb_first, big_endian, network] = LAYOUTS;
+    assert_eq!(bits(Encoding::default(), 16, 0x0102u64), "0100000010000000");
+    assert_eq!(bits(msb_first, 16, 0x0102u64), "0000001000000001");
+    assert_eq!(bits(big_endian, 16, 0x0102u64), "1000000001000000");
+    assert_eq!(bits(network, 16, 0x0102u64), "0000000100000010");
+}
+
+#[test]
+fn wide_integers_round_trip() {
+    let sha_block: BigUint = (BigUint::from(1u32) << 511u32) + 0xdead_beefu32;
+    for encoding in LAYOUTS {
+        let value = Value::Unsigned(sha_block.clone());
+        assert_eq!(
+            round_trip(encoding, 512, value.clone(), ValueKind::Unsigned),
+            value
+        );
+        let value = Value::from(u64::MAX);
+        assert_eq!(
+            round_trip(encoding, 64, value.clone(), ValueKind::Unsigned),
+            value
+        );
+        for signed in [i64::MIN, -1, 0, 77, i64::MAX] {
+            let value = Value::from(signed);
+            assert_eq!(
+                round_trip(encoding, 64, value.clone(), ValueKind::Signed),
+                value
+            );
+        }
+    }
+}
+
+#[test]
+fn values_must_fit_their_wires() {
+    let encoding = Encoding::default();
+    let too_wide = |value: Value| encoding.encode(&wires(8), &value).unwrap_err();
+    assert_eq!(
+        too_wide(Value::from(256u64)),
+        EncodingError::TooWide { bits: 9, wires: 8 }
+    );
+    assert_eq!(
+        too_wide(Value::from(128i64)),
+        EncodingError::TooWide { bits: 9, wires: 8 }
+    );
+    assert_eq!(
+        too_wide(Value::from(-129i64)),
+        EncodingError::TooWide { bits: 9, wires: 8 }
+    );
+    assert_eq!(
+        too_wide(Value::from("ab")),
+        EncodingError::TooWide { bits: 16, wires: 8 }
+    );
+    assert_eq!(bits(encoding, 8, -128i64), "00000001");
+    assert_eq!(bits(encoding, 8, -1i64), "11111111");
+
+    let big_endian = LAYOUTS[2];
+    assert_eq!(
+        big_endian.encode(&wires(12), &Value::from(1u64)),
+        Err(EncodingError::PartialByte { wires: 12 })
+    );
+}
+
+#[test]
+fn byte_strings_keep_utf8() {
+    let msb_first = LAYOUTS[1];
+    let wires = wires(64);
+    // ASCII matches the old encoder, padded with zeros
+    assert_eq!(
+        msb_first.encode(&wires, &Value::from("abc")).unwrap(),
+        wire_values_str(&wires, "abc".to_string())
+    );
+    // Endianness only applies to integers
+    assert_eq!(
+        LAYOUTS[3].encode(&wires, &Value::from("abc")),
+        msb_first.encode(&wires, &Value::from("abc"))
+    );
+
+    let encoded = msb_first.encode(&wires, &Value::from("é")).unwrap();
+    let decoded = msb_first
+        .decode(&wires, &encoded, ValueKind::Bytes)
+        .unwrap();
+    let mut expected = "é".as_bytes().to_vec();
+    expected.resize(8, 0);
+    assert_eq!(decoded.into_bytes(), Some(expected));
+}
+
+#[test]
+fn decoding_needs_every_wire() {
+    let wires = wires(4);
+    let mut values = Encoding::default()
+        .encode(&wires, &Value::from(5u64))
+        .unwrap();
+    values.remove("w_2");
+    assert_eq!(
+        Encoding::default().decode(&wires, &values, ValueKind::Unsigned),
+        Err(EncodingError::MissingWire("w_2".to_string()))
+    );
+}
+
+#[test]
+fn adder_adds_64_bit_operands() {
+    let contents = read_to_string("./circuits/adder64.txt").unwrap();
+    let (circuit, ins, outs) = parse_bristol_fashion(&contents).unwrap();
+    let encoding = Encoding::default();
+    let (a, b) = (u64::MAX - 5, 1u64 << 40);
+    let mut inputs = encoding.encode(&ins["a"], &Value::from(a)).unwrap();
+    inputs.extend(encoding.encode(&ins["b"], &Value::from(b)).unwrap());
+    let values = simulate(&circuit, &inputs);
+
+    assert_eq!(
+        encoding.decode(&outs["out"], &values, ValueKind::Unsigned),
+        Ok(Value::from(a.wrapping_add(b)))
+    );
+    // The same bits read as a signed sum
+    assert_eq!(
+        encoding.decode(&outs["out"], &values, ValueKind::Signed),
+        Ok(Value::Signed(BigInt::from(a.wrapping_add(b) as i64)))
+    );
+}
+
+#[test]
+fn accessors_match_the_variant() {
+    assert_eq!(Value::from(7u64).into_unsigned(), Some(BigUint::from(7u32)));
+    assert_eq!(Value::from(-7i64).into_signed(), Some(BigInt::from(-7)));
+    assert_eq!(Value::from("hi").into_bytes(), Some(b"hi".to_vec()));
+    assert_eq!(Value::from(7u64).into_signed(), None);
+    assert_eq!(Value::from(-7i64).into_bytes(), None);
+    assert_eq!(Value::from("hi").into_unsigned(), None);
+}
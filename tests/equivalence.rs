@@ -0,0 +1,199 @@
+use std::fs::read_to_string;
+
+use rs::{
+    equivalence::{
+        check_exhaustive, check_sat, Equivalence, EquivalenceError, Lit, SatResult, Solver,
+    },
+    optimizer::optimize,
+    parser::{parse_bristol_fashion, parse_yosys_json},
+    simulator::simulate,
+    utils::{Circuit, GateType},
+};
+
+fn circuit(gates: &[(&str, GateType, &[&str])]) -> Circuit {
+    gates
+        .iter()
+        .map(|(wire, op, inputs)| {
+            (
+                wire.to_string(),
+                (*op, inputs.iter().map(|i| i.to_string()).collect()),
+            )
+        })
+        .collect()
+}
+
+fn xor_circuit() -> Circuit {
+    circuit(&[
+        ("a", GateType::INPUT, &[]),
+        ("b", GateType::INPUT, &[]),
+        ("out", GateType::XOR, &["a", "b"]),
+    ])
+}
+
+/// (a | b) & !(a & b)
+fn xor_from_and_or() -> Circuit {
+    circuit(&[
+        ("a", GateType::INPUT, &[]),
+        ("b", GateType::INPUT, &[]),
+        ("t0", GateType::OR, &["a", "b"]),
+        ("t1", GateType::NAND, &["a", "b"]),
+        ("out", GateType::AND, &["t0", "t1"]),
+    ])
+}
+
+fn or_circuit() -> Circuit {
+    circuit(&[
+        ("a", GateType::INPUT, &[]),
+        ("b", GateType::INPUT, &[]),
+        ("out", GateType::OR, &["a", "b"]),
+    ])
+}
+
+fn outputs() -> Vec<String> {
+    vec!["out".to_string()]
+}
+
+#[test]
+fn sat_solver_finds_model() {
+    let mut solver = Solver::new();
+    let a = solver.new_lit();
+    let b = solver.new_lit();
+    let c = solver.new_lit();
+    solver.add_clause(&[a, b]);
+    solver.add_clause(&[!a, c]);
+    solver.add_clause(&[!c, !b]);
+    solver.add_clause(&[!a, b, !c]);
+    match solver.solve() {
+        SatResult::Sat(model) => {
+            let v = |l: Lit| model[l.var()] ^ l.is_negated();
+            assert!(v(a) || v(b));
+            assert!(!v(a) || v(c));
+            assert!(!v(c) || !v(b));
+            assert!(!v(a) || v(b) || !v(c));
+        }
+        SatResult::Unsat => panic!("formula is satisfiable"),
+    }
+}
+
+#[test]
+fn sat_solver_proves_pigeonhole_unsat() {
+    // 5 pigeons in 4 holes.
+    let (pigeons, holes) = (5, 4);
+    let mut solver = Solver::new();
+    let p: Vec<Vec<Lit>> = (0..pigeons)
+        .map(|_| (0..holes).map(|_| solver.new_lit()).collect())
+        .collect();
+    for row in p.iter() {
+        solver.add_clause(row);
+    }
+    for h in 0..holes {
+        let column: Vec<Lit> = p.iter().map(|row| row[h]).collect();
+        for (i, &a) in column.iter().enumerate() {
+            for &b in column[i + 1..].iter() {
+                solver.add_clause(&[!a, !b]);
+            }
+        }
+    }
+    assert_eq!(solver.solve(), SatResult::Unsat);
+}
+
+#[test]
+fn sat_solver_respects_assumptions() {
+    let mut solver = Solver::new();
+    let a = solver.new_lit();
+    let b = solver.new_lit();
+    solver.add_clause(&[!a, b]);
+    assert_eq!(solver.solve_with_assumptions(&[a, !b]), SatResult::Unsat);
+    assert!(matches!(
+        solver.solve_with_assumptions(&[a]),
+        SatResult::Sat(_)
+    ));
+}
+
+#[test]
+fn equivalent_circuits() {
+    assert_eq!(
+        check_exhaustive(&xor_circuit(), &xor_from_and_or(), &outputs()),
+        Ok(Equivalence::Equivalent)
+    );
+    assert_eq!(
+        check_sat(&xor_circuit(), &xor_from_and_or(), &outputs()),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn counterexamples_distinguish_circuits() {
+    for result in [
+        check_exhaustive(&xor_circuit(), &or_circuit(), &outputs()),
+        check_sat(&xor_circuit(), &or_circuit(), &outputs()),
+    ] {
+        let Ok(Equivalence::Counterexample(inputs)) = result else {
+            panic!("expected a counterexample, got {:?}", result);
+        };
+        assert_eq!(inputs["a"] & inputs["b"], 1);
+        assert_ne!(
+            simulate(&xor_circuit(), &inputs)["out"],
+            simulate(&or_circuit(), &inputs)["out"]
+        );
+    }
+}
+
+#[test]
+fn missing_output_is_an_error() {
+    let result = check_sat(&xor_circuit(), &or_circuit(), &["nope".to_string()]);
+    assert_eq!(
+        result,
+        Err(EquivalenceError::MissingOutput("nope".to_string()))
+    );
+}
+
+#[test]
+fn exhaustive_refuses_wide_circuits() {
+    let contents = read_to_string("./circuits/adder64.txt").unwrap();
+    let (circuit, _, outs) = parse_bristol_fashion(&contents).unwrap();
+    assert!(matches!(
+        check_exhaustive(&circuit, &circuit, &outs["out"]),
+        Err(EquivalenceError::TooManyInputs { found: 128, .. })
+    ));
+}
+
+#[test]
+fn optimized_adders_are_equivalent() {
+    let contents = read_to_string("./circuits/adder64.txt").unwrap();
+    let (circuit, _, outs) = parse_bristol_fashion(&contents).unwrap();
+    let optimized = optimize(circuit.clone(), outs["out"].clone());
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outs["out"]),
+        Ok(Equivalence::Equivalent)
+    );
+
+    let contents = read_to_string("./circuits/synth_add64.json").unwrap();
+    let (circuit, _, outs) = parse_yosys_json(&contents).unwrap();
+    let optimized = optimize(circuit.clone(), outs["sum"].clone());
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outs["sum"]),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn sat_finds_bugs_in_adders() {
+    let contents = read_to_string("./circuits/adder64.txt").unwrap();
+    let (circuit, _, outs) = parse_bristol_fashion(&contents).unwrap();
+    let mut broken = circuit.clone();
+    // Flip the operation of one gate in the middle of the carry chain.
+    let (wire, (_, inputs)) = broken
+        .iter()
+        .find(|(_, (op, _))| *op == GateType::AND)
+        .map(|(w, g)| (w.clone(), g.clone()))
+        .unwrap();
+    broken.insert(wire, (GateType::OR, inputs));
+
+    let Ok(Equivalence::Counterexample(inputs)) = check_sat(&circuit, &broken, &outs["out"]) else {
+        panic!("expected a counterexample");
+    };
+    let expected = simulate(&circuit, &inputs);
+    let actual = simulate(&broken, &inputs);
+    assert!(outs["out"].iter().any(|w| expected[w] != actual[w]));
+}
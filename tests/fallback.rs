@@ -0,0 +1,138 @@
+mod common;
+
+use std::fs::read_to_string;
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    garbler::GarblingScheme,
+    optimizer::{
+        circuit_cost, keep_best, optimize_with_config, synthesize_rules, HalfGates, KeepBest, Kept,
+        OptimizerConfig, RuleSet, SynthesisConfig,
+    },
+    parser::parse_bristol_fashion,
+    utils::{Circuit, GateType},
+};
+
+use common::gate;
+
+/// Two implementations of `x = a & b` and `y = a ^ b`, the second one cheaper.
+fn circuits() -> (Circuit, Circuit) {
+    let original = Circuit::from([
+        ("a".to_string(), gate(GateType::INPUT, &[])),
+        ("b".to_string(), gate(GateType::INPUT, &[])),
+        ("t".to_string(), gate(GateType::AND, &["a", "b"])),
+        ("x".to_string(), gate(GateType::CONST, &["t"])),
+        ("u".to_string(), gate(GateType::OR, &["a", "b"])),
+        ("y".to_string(), gate(GateType::ANDNOT, &["u", "t"])),
+    ]);
+    let optimized = Circuit::from([
+        ("a".to_string(), gate(GateType::INPUT, &[])),
+        ("b".to_string(), gate(GateType::INPUT, &[])),
+        ("t".to_string(), gate(GateType::NAND, &["a", "b"])),
+        ("v".to_string(), gate(GateType::NOR, &["t", "t"])),
+        ("x".to_string(), gate(GateType::NOT, &["t"])),
+        ("y".to_string(), gate(GateType::XOR, &["a", "b"])),
+        ("z".to_string(), gate(GateType::AND, &["v", "v"])),
+    ]);
+    (original, optimized)
+}
+
+#[test]
+fn circuit_cost_counts_live_gates_once() {
+    let (original, optimized) = circuits();
+    let outputs = ["x".to_string(), "y".to_string()];
+    // AND, OR and ANDNOT at two ciphertexts each, plus the free buffer
+    assert!((circuit_cost(&original, &outputs, &HalfGates) - 6.0).abs() < 1e-3);
+    // NAND only, the NOR and the AND reading it are dead
+    assert!((circuit_cost(&optimized, &outputs, &HalfGates) - 2.0).abs() < 1e-3);
+}
+
+#[test]
+fn keep_best_picks_cheaper_circuit() {
+    let (original, optimized) = circuits();
+    let outputs = ["x".to_string(), "y".to_string()];
+    let (kept, which, cost) = keep_best(
+        &optimized,
+        original.clone(),
+        &outputs,
+        KeepBest::Circuit,
+        &HalfGates,
+    );
+    assert_eq!(which, Kept::Original);
+    assert_eq!(kept, optimized);
+    assert!((cost - 2.0).abs() < 1e-3);
+
+    let (kept, which, _) = keep_best(
+        &optimized,
+        original.clone(),
+        &outputs,
+        KeepBest::Off,
+        &HalfGates,
+    );
+    assert_eq!(which, Kept::Optimized);
+    assert_eq!(kept, original);
+}
+
+#[test]
+fn keep_best_mixes_cones_per_output() {
+    let (mut original, optimized) = circuits();
+    // Make `x` cheaper in the original: a plain buffer of an input
+    original.insert("x".to_string(), gate(GateType::CONST, &["a"]));
+    let outputs = ["x".to_string(), "y".to_string()];
+    let (mixed, which, cost) = keep_best(
+        &original,
+        optimized.clone(),
+        &outputs,
+        KeepBest::PerOutput,
+        &HalfGates,
+    );
+    assert_eq!(
+        which,
+        Kept::Mixed {
+            original_outputs: vec!["x".to_string()]
+        }
+    );
+    assert_eq!(mixed["x"], gate(GateType::CONST, &["a"]));
+    assert_eq!(mixed["y"], gate(GateType::XOR, &["a", "b"]));
+    assert!(cost < 1e-3);
+    assert!(!mixed.contains_key("t"));
+}
+
+#[test]
+fn optimize_never_returns_worse_circuit() {
+    let (circuit, _, outs) =
+        parse_bristol_fashion(&read_to_string("./circuits/adder64.txt").unwrap()).unwrap();
+    let outputs = &outs["out"];
+    // Tree extraction with these rules duplicates most of the carry chain
+    let rules = synthesize_rules(&SynthesisConfig {
+        max_size: 4,
+        ..Default::default()
+    });
+    let model = GarblingScheme::HalfGates.cost_model();
+    let original_cost = circuit_cost(&circuit, outputs, model.as_ref());
+    for keep_best in [KeepBest::Off, KeepBest::Circuit, KeepBest::PerOutput] {
+        let config = OptimizerConfig {
+            rules: RuleSet::Only(rules.clone()),
+            scheme: GarblingScheme::HalfGates,
+            keep_best,
+            iter_limit: 3,
+            node_limit: 1_000,
+            ..Default::default()
+        };
+        let (optimized, report) =
+            optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+        assert_eq!(
+            check_sat(&circuit, &optimized, outputs),
+            Ok(Equivalence::Equivalent)
+        );
+        let cost = circuit_cost(&optimized, outputs, model.as_ref());
+        assert!((report.optimized_cost - cost).abs() < 1e-9);
+        if keep_best == KeepBest::Off {
+            assert_eq!(report.kept, Kept::Optimized);
+            assert!(cost > original_cost);
+        } else {
+            assert_ne!(report.kept, Kept::Optimized);
+            assert!(cost <= report.original_cost);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use rs::{
+    ir::{IndexedCircuit, WireId},
+    utils::{Circuit, GateType},
+};
+
+use common::{gate, load_bristol_adder};
+
+#[test]
+fn bristol_adder_round_trips() {
+    let (circuit, _, _) = load_bristol_adder();
+    let indexed = IndexedCircuit::from_circuit(&circuit);
+    assert_eq!(indexed.num_wires(), circuit.len());
+    assert_eq!(indexed.to_circuit(), circuit);
+    assert_eq!(Circuit::from(&IndexedCircuit::from(&circuit)), circuit);
+}
+
+#[test]
+fn gates_are_topologically_ordered() {
+    let (circuit, _, _) = load_bristol_adder();
+    let indexed = IndexedCircuit::from_circuit(&circuit);
+    for (i, input) in indexed.inputs.iter().enumerate() {
+        assert_eq!(*input, WireId(i as u32));
+    }
+    for (i, gate) in indexed.gates.iter().enumerate() {
+        assert_eq!(gate.output.index(), indexed.inputs.len() + i);
+        assert!(gate.inputs.iter().all(|input| *input < gate.output));
+        assert_eq!(gate.inputs.len(), gate.op.arity());
+    }
+}
+
+#[test]
+fn undefined_wires_become_inputs() {
+    let circuit = Circuit::from([
+        ("x".to_string(), gate(GateType::AND, &["a", "b"])),
+        ("y".to_string(), gate(GateType::XOR, &["x", "a"])),
+    ]);
+    let indexed = IndexedCircuit::from_circuit(&circuit);
+    let inputs: Vec<String> = indexed
+        .inputs
+        .iter()
+        .map(|&input| indexed.name(input))
+        .collect();
+    assert_eq!(inputs, ["a", "b"]);
+    assert_eq!(indexed.gates.len(), 2);
+
+    let mut expected = circuit.clone();
+    expected.insert("a".to_string(), gate(GateType::INPUT, &[]));
+    expected.insert("b".to_string(), gate(GateType::INPUT, &[]));
+    assert_eq!(indexed.to_circuit(), expected);
+}
+
+#[test]
+#[should_panic(expected = "Circuit has a cycle")]
+fn cycles_are_rejected() {
+    let circuit = Circuit::from([
+        ("a".to_string(), gate(GateType::INPUT, &[])),
+        ("x".to_string(), gate(GateType::AND, &["a", "y"])),
+        ("y".to_string(), gate(GateType::NOT, &["x"])),
+    ]);
+    IndexedCircuit::from_circuit(&circuit);
+}
+
+#[test]
+fn unnamed_wires_are_numbered() {
+    let circuit = Circuit::from([
+        ("a".to_string(), gate(GateType::INPUT, &[])),
+        ("b".to_string(), gate(GateType::INPUT, &[])),
+        ("x".to_string(), gate(GateType::OR, &["a", "b"])),
+    ]);
+    let indexed = IndexedCircuit::from_circuit(&circuit).without_names();
+    assert!(indexed.names.is_none());
+    assert!(indexed.name_table().is_none());
+    assert_eq!(indexed.name(WireId(2)), "w_2");
+    assert_eq!(
+        indexed.to_circuit(),
+        Circuit::from([
+            ("w_0".to_string(), gate(GateType::INPUT, &[])),
+            ("w_1".to_string(), gate(GateType::INPUT, &[])),
+            ("w_2".to_string(), gate(GateType::OR, &["w_0", "w_1"])),
+        ])
+    );
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::fs::read_to_string;
+
+use egg::{AstSize, EGraph, Extractor};
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    optimizer::{circuit_to_egraph, circuit_to_expr, expr_to_circuit, CircuitLang},
+    parser::parse_bristol_fashion,
+    utils::{Circuit, GateType},
+};
+
+use common::add_gate;
+
+/// One gate of every type, reading each other through a buffer.
+fn every_gate() -> (Circuit, Vec<String>) {
+    let mut circuit = Circuit::new();
+    add_gate(&mut circuit, "a", GateType::INPUT, &[]);
+    add_gate(&mut circuit, "b", GateType::INPUT, &[]);
+    add_gate(&mut circuit, "one", GateType::TRUE, &[]);
+    add_gate(&mut circuit, "zero", GateType::FALSE, &[]);
+    add_gate(&mut circuit, "buf", GateType::CONST, &["b"]);
+    let binary = [
+        ("and", GateType::AND),
+        ("or", GateType::OR),
+        ("xor", GateType::XOR),
+        ("nand", GateType::NAND),
+        ("nor", GateType::NOR),
+        ("xnor", GateType::XNOR),
+        ("andnot", GateType::ANDNOT),
+        ("ornot", GateType::ORNOT),
+    ];
+    let mut last = "buf".to_string();
+    for (wire, op) in binary {
+        add_gate(&mut circuit, wire, op, &["a", &last]);
+        last = wire.to_string();
+    }
+    add_gate(&mut circuit, "not", GateType::NOT, &[&last]);
+    add_gate(&mut circuit, "with_one", GateType::AND, &["not", "one"]);
+    add_gate(&mut circuit, "out", GateType::OR, &["with_one", "zero"]);
+    (circuit, vec!["out".to_string()])
+}
+
+#[test]
+fn every_gate_type_round_trips() {
+    let (circuit, outputs) = every_gate();
+    let expr = circuit_to_expr(&circuit, "out");
+    // Inputs and buffers have no node of their own
+    let wires = expr
+        .as_ref()
+        .iter()
+        .filter(|node| matches!(node, CircuitLang::Wire(_)))
+        .count();
+    assert_eq!(wires, 2);
+    assert_eq!(expr.as_ref().len(), circuit.len() - 1);
+
+    let lifted = expr_to_circuit(&expr, "out");
+    assert_eq!(lifted.len(), expr.as_ref().len());
+    assert_eq!(
+        check_sat(&circuit, &lifted, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn wire_outputs_become_buffers() {
+    let (circuit, _) = every_gate();
+    let lifted = expr_to_circuit(&circuit_to_expr(&circuit, "buf"), "copy");
+    assert_eq!(lifted["copy"], (GateType::CONST, vec!["b".to_string()]));
+
+    // Outputs the circuit doesn't define are inputs
+    let lifted = expr_to_circuit(&circuit_to_expr(&circuit, "c"), "copy");
+    assert_eq!(lifted["copy"], (GateType::CONST, vec!["c".to_string()]));
+
+    // An input that is its own output needs no buffer, which would read itself
+    let lifted = expr_to_circuit(&circuit_to_expr(&circuit, "a"), "a");
+    assert_eq!(
+        lifted,
+        Circuit::from([("a".to_string(), (GateType::INPUT, vec![]))])
+    );
+}
+
+#[test]
+fn egraph_holds_shared_gates_once() {
+    let contents = read_to_string("./circuits/adder64.txt").unwrap();
+    let (circuit, _, outs) = parse_bristol_fashion(&contents).unwrap();
+    let outputs = &outs["out"];
+    let mut egraph: EGraph<CircuitLang, ()> = EGraph::default();
+    let roots = circuit_to_egraph(&circuit, outputs, &mut egraph);
+
+    assert_eq!(roots.len(), outputs.len());
+    // The carry chain is shared by every later output, yet each gate is one node
+    assert!(egraph.total_number_of_nodes() <= circuit.len());
+    let extractor = Extractor::new(&egraph, AstSize);
+    for (output, root) in outputs.iter().zip(roots) {
+        let (_, expr) = extractor.find_best(root);
+        let lifted = expr_to_circuit(&expr, output);
+        assert_eq!(
+            check_sat(&circuit, &lifted, std::slice::from_ref(output)),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+}
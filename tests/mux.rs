@@ -0,0 +1,155 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use fernet::Fernet;
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    evaluator::Evaluator,
+    garbler::Garbler,
+    optimizer::{
+        check_rules, circuit_rules, optimize_with_config, to_xag, ClassicYao, CostModel, HalfGates,
+        OptimizerConfig,
+    },
+    utils::{Circuit, GateType, Ports},
+};
+
+use common::{add_gate, nonlinear_gates};
+
+/// Inputs `s`, `a` and `b`, read by `mux` and `maj` gates.
+fn mux_and_maj() -> (Circuit, Vec<String>) {
+    let mut circuit = Circuit::new();
+    for input in ["s", "a", "b"] {
+        add_gate(&mut circuit, input, GateType::INPUT, &[]);
+    }
+    add_gate(&mut circuit, "mux", GateType::MUX, &["s", "a", "b"]);
+    add_gate(&mut circuit, "maj", GateType::MAJ, &["s", "a", "b"]);
+    (circuit, vec!["mux".to_string(), "maj".to_string()])
+}
+
+#[test]
+fn mux_selects_and_maj_votes() {
+    for row in 0..8 {
+        let [s, a, b] = [row & 1 == 1, row & 2 == 2, row & 4 == 4];
+        assert_eq!(GateType::MUX.eval(&[s, a, b]), if s { a } else { b });
+        assert_eq!(
+            GateType::MAJ.eval(&[s, a, b]),
+            [s, a, b].iter().filter(|&&bit| bit).count() >= 2
+        );
+    }
+    assert_eq!(GateType::MUX.arity(), 3);
+    assert!(GateType::MAJ.is_nonlinear());
+}
+
+#[test]
+fn sat_encoding_matches_one_and_forms() {
+    let (circuit, outputs) = mux_and_maj();
+    let mut lowered = circuit.clone();
+    // b ^ (s & (a ^ b))
+    add_gate(&mut lowered, "ab", GateType::XOR, &["a", "b"]);
+    add_gate(&mut lowered, "taken", GateType::AND, &["s", "ab"]);
+    add_gate(&mut lowered, "mux", GateType::XOR, &["b", "taken"]);
+    // s ^ ((s ^ a) & (s ^ b))
+    add_gate(&mut lowered, "sa", GateType::XOR, &["s", "a"]);
+    add_gate(&mut lowered, "sb", GateType::XOR, &["s", "b"]);
+    add_gate(&mut lowered, "both", GateType::AND, &["sa", "sb"]);
+    add_gate(&mut lowered, "maj", GateType::XOR, &["s", "both"]);
+    assert_eq!(nonlinear_gates(&lowered), 2);
+    assert_eq!(
+        check_sat(&circuit, &lowered, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn xag_lowers_to_one_and_each() {
+    let expr = "(mux s (maj a b c) d)".parse().unwrap();
+    let xag = to_xag(&expr);
+    let ands = xag
+        .as_ref()
+        .iter()
+        .filter(|node| node.gate_type() == Some(GateType::AND))
+        .count();
+    assert_eq!(ands, 2);
+    assert!(xag
+        .as_ref()
+        .iter()
+        .all(|node| matches!(node.gate_type(), None | Some(GateType::AND | GateType::XOR))));
+}
+
+#[test]
+fn rules_discover_and_lower_mux_and_maj() {
+    check_rules(&circuit_rules()).unwrap();
+
+    let mut circuit = Circuit::new();
+    for input in ["s", "a", "b", "c"] {
+        add_gate(&mut circuit, input, GateType::INPUT, &[]);
+    }
+    // (s & a) | (b & !s)
+    add_gate(&mut circuit, "sa", GateType::AND, &["s", "a"]);
+    add_gate(&mut circuit, "bs", GateType::ANDNOT, &["b", "s"]);
+    add_gate(&mut circuit, "mux", GateType::OR, &["sa", "bs"]);
+    // (a & b) | (c & (a | b))
+    add_gate(&mut circuit, "ab", GateType::AND, &["a", "b"]);
+    add_gate(&mut circuit, "either", GateType::OR, &["a", "b"]);
+    add_gate(&mut circuit, "carry", GateType::AND, &["c", "either"]);
+    add_gate(&mut circuit, "maj", GateType::OR, &["ab", "carry"]);
+    let outputs = vec!["mux".to_string(), "maj".to_string()];
+    assert_eq!(nonlinear_gates(&circuit), 7);
+
+    let (optimized, _) = optimize_with_config(
+        circuit.clone(),
+        outputs.clone(),
+        &OptimizerConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(nonlinear_gates(&optimized), 2);
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn three_input_gates_cost_eight_rows_or_one_and() {
+    assert_eq!(ClassicYao { free_xor: true }.gate_cost(GateType::MUX), 8.0);
+    assert_eq!(
+        HalfGates.gate_cost(GateType::MAJ),
+        HalfGates.gate_cost(GateType::AND)
+    );
+}
+
+#[test]
+fn garbled_mux_and_maj_evaluate() {
+    let (circuit, outputs) = mux_and_maj();
+    let ins: Ports = BTreeMap::from([(
+        "in".to_string(),
+        vec!["s".to_string(), "a".to_string(), "b".to_string()],
+    )]);
+    let outs: Ports = BTreeMap::from([("out".to_string(), outputs.clone())]);
+    let garbler = Garbler::new(Fernet::generate_key(), circuit.clone(), ins, outs);
+    let (wire_to_keys, gates) = garbler.build();
+    assert_eq!(gates["mux"].table.len(), 8);
+
+    for row in 0..8 {
+        let bits = [row & 1 == 1, row & 2 == 2, row & 4 == 4];
+        let labels: BTreeMap<String, String> = ["s", "a", "b"]
+            .iter()
+            .zip(bits)
+            .map(|(wire, bit)| {
+                let keys = &wire_to_keys[*wire];
+                let label = if bit { &keys.1 } else { &keys.0 };
+                (wire.to_string(), label.clone())
+            })
+            .collect();
+        let mut evaluator = Evaluator::new(
+            circuit.clone(),
+            outputs.clone(),
+            wire_to_keys.clone(),
+            gates.clone(),
+        );
+        let result = evaluator.run(vec![labels]);
+        assert_eq!(result["mux"] == 1, GateType::MUX.eval(&bits));
+        assert_eq!(result["maj"] == 1, GateType::MAJ.eval(&bits));
+    }
+}
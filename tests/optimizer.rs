@@ -0,0 +1,476 @@
+mod common;
+
+use std::{fs::read_to_string, time::Duration};
+
+use egg::{rewrite, EGraph, Extractor, RecExpr};
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    garbler::{Garbler, GarblingScheme},
+    ir::IndexedCircuit,
+    optimizer::{
+        optimize_with_config, CircuitLang, ClassicYao, CostModel, DagExtractor, Extraction,
+        GarbleCost, Grr3, HalfGates, OptimizationReport, OptimizerConfig, RuleSet, SaturationMode,
+        Scheduler, StopReason, ThreeHalves,
+    },
+    parser::parse_yosys_json,
+    utils::{Circuit, GateType},
+};
+
+use common::{gate, load_bristol_adder, non_free_gates};
+
+#[test]
+fn config_limits_are_reported() {
+    let (circuit, _, outs) = load_bristol_adder();
+    let config = OptimizerConfig {
+        iter_limit: 1,
+        node_limit: 1_000_000,
+        time_limit: Duration::from_secs(60),
+        threads: Some(1),
+        ..Default::default()
+    };
+    let (optimized, report) =
+        optimize_with_config(circuit.clone(), outs["out"].clone(), &config).unwrap();
+
+    assert_eq!(report.outputs.len(), 64);
+    assert_eq!(report.original_gates, circuit.len());
+    assert_eq!(report.optimized_gates, optimized.len());
+    for (stats, output) in report.outputs.iter().zip(outs["out"].iter()) {
+        assert_eq!(&stats.output, output);
+        assert!(stats.iterations <= 1);
+        assert!(stats.best_cost <= stats.initial_cost);
+        assert!(matches!(
+            stats.stop_reason,
+            Some(StopReason::IterationLimit(1)) | Some(StopReason::Saturated)
+        ));
+    }
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outs["out"]),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn report_counts_gates_and_times_phases() {
+    let (circuit, _, outs) = load_bristol_adder();
+    for mode in [SaturationMode::PerOutput, SaturationMode::Shared] {
+        let config = OptimizerConfig {
+            mode,
+            ..Default::default()
+        };
+        let (optimized, report) =
+            optimize_with_config(circuit.clone(), outs["out"].clone(), &config).unwrap();
+
+        assert_eq!(report.original_gate_counts[&GateType::INPUT], 128);
+        assert_eq!(
+            report.original_gate_counts.values().sum::<usize>(),
+            circuit.len()
+        );
+        for (op, count) in report.optimized_gate_counts.iter() {
+            assert_eq!(
+                optimized.values().filter(|(gate, _)| gate == op).count(),
+                *count
+            );
+        }
+        assert_eq!(report.original_nonfree, non_free_gates(&circuit));
+        assert_eq!(report.optimized_nonfree, non_free_gates(&optimized));
+
+        let phases = report.phases;
+        assert!(phases.saturate > Duration::ZERO);
+        assert!(phases.total >= phases.traverse + phases.rebuild + phases.cleanup);
+    }
+}
+
+#[test]
+fn report_round_trips_through_json() {
+    let (circuit, _, outs) = load_bristol_adder();
+    let (_, report) =
+        optimize_with_config(circuit, outs["out"].clone(), &OptimizerConfig::default()).unwrap();
+    let json = report.to_json();
+    assert!(json.contains("\"XOR\""));
+    // Costs may be off in the last digit after parsing, everything else is exact
+    let parsed = OptimizationReport::from_json(&json).unwrap();
+    assert_eq!(parsed.optimized_gate_counts, report.optimized_gate_counts);
+    assert_eq!(parsed.kept, report.kept);
+    assert_eq!(parsed.phases, report.phases);
+    assert_eq!(parsed.outputs.len(), report.outputs.len());
+    assert_eq!(parsed.outputs[1].stop_reason, report.outputs[1].stop_reason);
+}
+
+#[test]
+fn report_with_constant_output_round_trips_through_json() {
+    // `z = a & false` folds to a constant before saturation
+    let circuit = Circuit::from([
+        ("a".to_string(), gate(GateType::INPUT, &[])),
+        ("f".to_string(), gate(GateType::FALSE, &[])),
+        ("z".to_string(), gate(GateType::AND, &["a", "f"])),
+    ]);
+    let (_, report) =
+        optimize_with_config(circuit, vec!["z".to_string()], &OptimizerConfig::default()).unwrap();
+    assert!(report.outputs[0].initial_cost.is_finite());
+    assert!(report.outputs[0].best_cost.is_finite());
+
+    let parsed = OptimizationReport::from_json(&report.to_json()).unwrap();
+    assert_eq!(parsed.outputs.len(), 1);
+    assert!((parsed.outputs[0].best_cost - report.outputs[0].best_cost).abs() < 1e-9);
+    assert!((parsed.optimized_cost - report.optimized_cost).abs() < 1e-9);
+}
+
+#[test]
+fn simple_scheduler_with_node_limit() {
+    let (circuit, _, outs) = load_bristol_adder();
+    let config = OptimizerConfig {
+        node_limit: 200,
+        scheduler: Scheduler::Simple,
+        rules: RuleSet::Extended(vec![]),
+        ..Default::default()
+    };
+    let (optimized, report) =
+        optimize_with_config(circuit.clone(), outs["out"].clone(), &config).unwrap();
+
+    assert!(report
+        .outputs
+        .iter()
+        .any(|stats| matches!(stats.stop_reason, Some(StopReason::NodeLimit(_)))));
+    assert!(report.outputs_hitting_limits() > 0);
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outs["out"]),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn shared_egraph_keeps_adders_equivalent() {
+    let shared = OptimizerConfig {
+        mode: SaturationMode::Shared,
+        node_limit: 100_000,
+        ..Default::default()
+    };
+    let per_output = OptimizerConfig {
+        node_limit: 100_000,
+        ..Default::default()
+    };
+    let bristol = load_bristol_adder();
+    let yosys = parse_yosys_json(&read_to_string("./circuits/synth_add64.json").unwrap()).unwrap();
+    for (circuit, _, outs) in [bristol, yosys] {
+        let outputs: Vec<String> = outs.values().flatten().cloned().collect();
+        let (optimized, report) =
+            optimize_with_config(circuit.clone(), outputs.clone(), &shared).unwrap();
+        let (_, baseline) =
+            optimize_with_config(circuit.clone(), outputs.clone(), &per_output).unwrap();
+
+        assert_eq!(report.outputs.len(), outputs.len());
+        assert!(report.optimized_gates <= baseline.optimized_gates);
+        assert_eq!(
+            check_sat(&circuit, &optimized, &outputs),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+}
+
+#[test]
+fn shared_egraph_emits_common_logic_once() {
+    // Both outputs read their own copy of a & b; the shared mode must build it only once.
+    let mut circuit = Circuit::new();
+    for input in ["a", "b", "c"] {
+        circuit.insert(input.to_string(), (GateType::INPUT, vec![]));
+    }
+    let gates: [(&str, GateType, [&str; 2]); 4] = [
+        ("ab", GateType::AND, ["a", "b"]),
+        ("ab2", GateType::AND, ["a", "b"]),
+        ("x", GateType::XOR, ["ab", "c"]),
+        ("y", GateType::OR, ["ab2", "c"]),
+    ];
+    for (wire, op, inputs) in gates {
+        circuit.insert(
+            wire.to_string(),
+            (op, inputs.iter().map(|i| i.to_string()).collect()),
+        );
+    }
+    let outputs = vec!["x".to_string(), "y".to_string()];
+    let config = OptimizerConfig {
+        mode: SaturationMode::Shared,
+        ..Default::default()
+    };
+    let (optimized, _) = optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+
+    let ands = optimized
+        .values()
+        .filter(|(op, _)| *op == GateType::AND)
+        .count();
+    assert_eq!(ands, 1);
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}
+
+#[test]
+fn dag_extraction_reuses_shared_gates() {
+    // t can be computed as s ^ c, reusing the AND gate s that is needed anyway, or as
+    // c & d, which is cheaper as a tree but adds a second AND gate.
+    let mut egraph: EGraph<CircuitLang, ()> = EGraph::default();
+    let s = egraph.add_expr(&"(and a b)".parse::<RecExpr<CircuitLang>>().unwrap());
+    let t = egraph.add_expr(&"(xor (and a b) c)".parse::<RecExpr<CircuitLang>>().unwrap());
+    let alternative = egraph.add_expr(&"(and c d)".parse::<RecExpr<CircuitLang>>().unwrap());
+    egraph.union(t, alternative);
+    egraph.rebuild();
+
+    let model = Garbler::SCHEME.cost_model();
+    let tree = Extractor::new(&egraph, GarbleCost(model.as_ref()));
+    assert!(matches!(tree.find_best_node(t), CircuitLang::And(_)));
+
+    let mut dag = DagExtractor::new(&egraph, model.as_ref());
+    dag.refine(&[s, t]);
+    assert!(matches!(dag.find_best_node(t), CircuitLang::Xor(_)));
+    // One AND table, not two
+    assert!(dag.dag_cost(&[s, t]) < 5.0);
+    assert_eq!(dag.find_best(t).1.to_string(), "(xor (and a b) c)");
+}
+
+#[test]
+fn dag_extraction_keeps_adders_equivalent() {
+    let bristol = load_bristol_adder();
+    let yosys = parse_yosys_json(&read_to_string("./circuits/synth_add64.json").unwrap()).unwrap();
+    for (circuit, _, outs) in [bristol, yosys] {
+        let outputs: Vec<String> = outs.values().flatten().cloned().collect();
+        for mode in [SaturationMode::PerOutput, SaturationMode::Shared] {
+            let tree = OptimizerConfig {
+                mode,
+                node_limit: 100_000,
+                ..Default::default()
+            };
+            let dag = OptimizerConfig {
+                extraction: Extraction::Dag,
+                ..tree.clone()
+            };
+            let (optimized, report) =
+                optimize_with_config(circuit.clone(), outputs.clone(), &dag).unwrap();
+            let (baseline, _) =
+                optimize_with_config(circuit.clone(), outputs.clone(), &tree).unwrap();
+
+            assert!(non_free_gates(&optimized) <= non_free_gates(&circuit));
+            // Cones extracted one at a time need not agree on the gates they share, so
+            // only a shared extraction is compared with the tree extraction as a whole
+            if mode == SaturationMode::Shared {
+                assert!(non_free_gates(&optimized) <= non_free_gates(&baseline));
+            }
+            // A shared extraction may make one cone more expensive to save gates overall
+            if mode == SaturationMode::PerOutput {
+                for stats in report.outputs.iter() {
+                    // Sums of free-gate costs differ in the last bits depending on order
+                    assert!(stats.best_cost <= stats.initial_cost + 1e-9);
+                }
+            }
+            assert_eq!(
+                check_sat(&circuit, &optimized, &outputs),
+                Ok(Equivalence::Equivalent)
+            );
+        }
+    }
+}
+
+#[test]
+fn cost_models_count_ciphertexts() {
+    let classic = ClassicYao { free_xor: false };
+    assert_eq!(classic.gate_cost(GateType::XOR), 4.0);
+    assert_eq!(classic.gate_cost(GateType::NOT), 2.0);
+
+    let garbler = Garbler::SCHEME.cost_model();
+    let free_xor_models: [(&dyn CostModel, f64); 4] = [
+        (garbler.as_ref(), 4.0),
+        (&Grr3, 3.0),
+        (&HalfGates, 2.0),
+        (&ThreeHalves, 1.5),
+    ];
+    for (model, and_cost) in free_xor_models {
+        for op in [
+            GateType::XOR,
+            GateType::XNOR,
+            GateType::NOT,
+            GateType::CONST,
+        ] {
+            assert_eq!(model.gate_cost(op), 0.0);
+        }
+        for op in [
+            GateType::AND,
+            GateType::OR,
+            GateType::NAND,
+            GateType::ANDNOT,
+        ] {
+            assert_eq!(model.gate_cost(op), and_cost);
+        }
+    }
+    assert_eq!(OptimizerConfig::default().scheme, Garbler::SCHEME);
+}
+
+#[test]
+fn every_scheme_keeps_adder_equivalent() {
+    let (circuit, _, outs) = load_bristol_adder();
+    for scheme in [
+        GarblingScheme::Classic { free_xor: false },
+        GarblingScheme::Grr3,
+        GarblingScheme::HalfGates,
+        GarblingScheme::ThreeHalves,
+    ] {
+        let config = OptimizerConfig {
+            mode: SaturationMode::Shared,
+            extraction: Extraction::Dag,
+            scheme,
+            ..Default::default()
+        };
+        let (optimized, _) =
+            optimize_with_config(circuit.clone(), outs["out"].clone(), &config).unwrap();
+        assert!(non_free_gates(&optimized) <= non_free_gates(&circuit));
+        assert_eq!(
+            check_sat(&circuit, &optimized, &outs["out"]),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+}
+
+/// `x0 & x1 & ... & x7` as a chain, seven ANDs deep.
+fn and_chain() -> (Circuit, Vec<String>) {
+    let mut circuit = Circuit::new();
+    for i in 0..8 {
+        circuit.insert(format!("x{i}"), (GateType::INPUT, vec![]));
+    }
+    let mut last = "x0".to_string();
+    for i in 1..8 {
+        let wire = format!("c{i}");
+        circuit.insert(wire.clone(), (GateType::AND, vec![last, format!("x{i}")]));
+        last = wire;
+    }
+    (circuit, vec![last])
+}
+
+fn and_rebalancing_rules() -> RuleSet {
+    RuleSet::Extended(vec![
+        rewrite!("and-comm"; "(and ?a ?b)" => "(and ?b ?a)"),
+        rewrite!("and-assoc"; "(and (and ?a ?b) ?c)" => "(and ?a (and ?b ?c))"),
+    ])
+}
+
+#[test]
+fn and_depth_counts_nonlinear_gates() {
+    let mut circuit = Circuit::new();
+    for input in ["a", "b", "c", "d"] {
+        circuit.insert(input.to_string(), (GateType::INPUT, vec![]));
+    }
+    let gates: [(&str, GateType, &[&str]); 4] = [
+        ("ab", GateType::AND, &["a", "b"]),
+        ("x", GateType::XOR, &["ab", "c"]),
+        ("n", GateType::NOT, &["x"]),
+        ("y", GateType::NOR, &["n", "d"]),
+    ];
+    for (wire, op, inputs) in gates {
+        circuit.insert(
+            wire.to_string(),
+            (op, inputs.iter().map(|i| i.to_string()).collect()),
+        );
+    }
+    assert_eq!(IndexedCircuit::from_circuit(&circuit).and_depth(), 2);
+    assert_eq!(IndexedCircuit::from_circuit(&and_chain().0).and_depth(), 7);
+}
+
+#[test]
+fn depth_extraction_balances_and_chain() {
+    let (circuit, outputs) = and_chain();
+    for mode in [SaturationMode::PerOutput, SaturationMode::Shared] {
+        for (extraction, depth) in [
+            (Extraction::MinDepth, 3),
+            (Extraction::BoundedDepth(5), 5),
+            // Below the minimum the bound falls back to it
+            (Extraction::BoundedDepth(1), 3),
+        ] {
+            let config = OptimizerConfig {
+                mode,
+                extraction,
+                rules: and_rebalancing_rules(),
+                node_limit: 100_000,
+                ..Default::default()
+            };
+            let (optimized, report) =
+                optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+
+            assert_eq!(report.original_depth, 7);
+            assert_eq!(
+                report.optimized_depth,
+                IndexedCircuit::from_circuit(&optimized).and_depth()
+            );
+            assert!(report.optimized_depth <= depth);
+            // Rebalancing needs no extra gates
+            assert_eq!(non_free_gates(&optimized), 7);
+            assert_eq!(
+                check_sat(&circuit, &optimized, &outputs),
+                Ok(Equivalence::Equivalent)
+            );
+        }
+    }
+}
+
+#[test]
+fn min_depth_extraction_keeps_adders_equivalent() {
+    let bristol = load_bristol_adder();
+    let yosys = parse_yosys_json(&read_to_string("./circuits/synth_add64.json").unwrap()).unwrap();
+    for (circuit, _, outs) in [bristol, yosys] {
+        let outputs: Vec<String> = outs.values().flatten().cloned().collect();
+        let config = OptimizerConfig {
+            mode: SaturationMode::Shared,
+            extraction: Extraction::MinDepth,
+            node_limit: 100_000,
+            ..Default::default()
+        };
+        let (optimized, report) =
+            optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+
+        assert_eq!(
+            report.original_depth,
+            IndexedCircuit::from_circuit(&circuit).and_depth()
+        );
+        assert!(report.optimized_depth <= report.original_depth);
+        assert_eq!(
+            check_sat(&circuit, &optimized, &outputs),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+}
+
+#[test]
+fn deep_not_chain_does_not_overflow_stack() {
+    // An odd chain of NOTs into an AND, much deeper than a recursive walk of the cone
+    // survives on the small stack below
+    let depth = 4_001;
+    let mut circuit = Circuit::new();
+    circuit.insert("a".to_string(), (GateType::INPUT, vec![]));
+    circuit.insert("b".to_string(), (GateType::INPUT, vec![]));
+    let mut last = "a".to_string();
+    for i in 0..depth {
+        let wire = format!("n{i}");
+        circuit.insert(wire.clone(), (GateType::NOT, vec![last]));
+        last = wire;
+    }
+    circuit.insert(
+        "out".to_string(),
+        (GateType::AND, vec![last, "b".to_string()]),
+    );
+    let outputs = vec!["out".to_string()];
+
+    let optimized = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn({
+            let (circuit, outputs) = (circuit.clone(), outputs.clone());
+            move || {
+                optimize_with_config(circuit, outputs, &OptimizerConfig::default())
+                    .unwrap()
+                    .0
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}
@@ -0,0 +1,225 @@
+use std::fs::read_to_string;
+
+use rs::{
+    parser::{parse_bristol_fashion, parse_yosys_json, Location, ParseErrorKind},
+    utils::GateType,
+};
+
+const BRISTOL_AND: &str = "1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n";
+
+fn yosys_json(cell_type: &str, connections: &str, directions: &str) -> String {
+    format!(
+        r#"{{
+  "creator": "test",
+  "modules": {{
+    "m": {{
+      "ports": {{
+        "a": {{ "direction": "input", "bits": [ 2 ] }},
+        "b": {{ "direction": "input", "bits": [ 3 ] }},
+        "y": {{ "direction": "output", "bits": [ 4 ] }}
+      }},
+      "cells": {{
+        "g": {{
+          "type": "{}",
+          "port_directions": {{ {} }},
+          "connections": {{ {} }}
+        }}
+      }}
+    }}
+  }}
+}}"#,
+        cell_type, directions, connections
+    )
+}
+
+#[test]
+fn bristol_parses_bundled_adder() {
+    let contents = read_to_string("./circuits/adder64.txt").unwrap();
+    let (circuit, ins, outs) = parse_bristol_fashion(&contents).unwrap();
+    assert_eq!(ins["a"].len(), 64);
+    assert_eq!(ins["b"].len(), 64);
+    assert_eq!(outs["out"].len(), 64);
+    assert_eq!(circuit.len(), 376 + 128);
+}
+
+#[test]
+fn bristol_parses_minimal_circuit() {
+    let (circuit, ins, outs) = parse_bristol_fashion(BRISTOL_AND).unwrap();
+    assert_eq!(ins["a"], vec!["a_0"]);
+    assert_eq!(ins["b"], vec!["b_1"]);
+    assert_eq!(outs["out"], vec!["out_2"]);
+    assert_eq!(circuit["out_2"].1, vec!["a_0", "b_1"]);
+}
+
+#[test]
+fn bristol_parses_constants() {
+    let contents = "3 5\n2 1 1\n1 1\n\n1 1 1 2 EQ\n1 1 0 3 EQW\n2 1 2 3 4 AND\n";
+    let (circuit, _, _) = parse_bristol_fashion(contents).unwrap();
+    assert_eq!(circuit["2"], (GateType::TRUE, vec![]));
+    assert_eq!(circuit["3"], (GateType::CONST, vec!["a_0".to_string()]));
+
+    let err = parse_bristol_fashion("1 3\n2 1 1\n1 1\n\n1 1 2 2 EQ\n").unwrap_err();
+    assert!(matches!(err.kind, ParseErrorKind::Malformed(_)));
+    assert_eq!(err.location, Location::Line { line: 5, column: 5 });
+}
+
+#[test]
+fn bristol_parses_three_input_gates() {
+    let contents = "2 6\n2 2 1\n1 2\n\n3 1 0 1 2 4 MUX\n3 1 0 1 2 5 MAJ\n";
+    let (circuit, _, _) = parse_bristol_fashion(contents).unwrap();
+    let inputs = vec!["a_0".to_string(), "a_1".to_string(), "b_2".to_string()];
+    assert_eq!(circuit["out_4"], (GateType::MUX, inputs.clone()));
+    assert_eq!(circuit["out_5"], (GateType::MAJ, inputs));
+}
+
+#[test]
+fn bristol_truncated_header() {
+    let err = parse_bristol_fashion("").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Truncated("header"));
+
+    let err = parse_bristol_fashion("1\n").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Truncated("header wire count"));
+    assert_eq!(err.location, Location::Line { line: 1, column: 2 });
+
+    let err = parse_bristol_fashion("1 3\n2 1 1\n").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Truncated("output line"));
+    assert_eq!(err.location, Location::Line { line: 3, column: 1 });
+}
+
+#[test]
+fn bristol_truncated_gates() {
+    let err = parse_bristol_fashion("2 4\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Truncated("gates"));
+
+    let err = parse_bristol_fashion("1 3\n2 1 1\n1 1\n\n2 1 0 1 2\n").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Truncated("gate type"));
+}
+
+#[test]
+fn bristol_invalid_number() {
+    let err = parse_bristol_fashion("1 x\n").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::InvalidNumber("x".to_string()));
+    assert_eq!(err.location, Location::Line { line: 1, column: 3 });
+}
+
+#[test]
+fn bristol_unknown_gate() {
+    let err = parse_bristol_fashion("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 NAND\n").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::UnknownGate("NAND".to_string()));
+    assert_eq!(
+        err.location,
+        Location::Line {
+            line: 5,
+            column: 11
+        }
+    );
+}
+
+#[test]
+fn bristol_wrong_arity() {
+    let err = parse_bristol_fashion("1 3\n2 1 1\n1 1\n\n1 1 0 2 AND\n").unwrap_err();
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::WrongArity {
+            gate: "AND".to_string(),
+            expected: 2,
+            found: 1,
+        }
+    );
+
+    let err = parse_bristol_fashion("1 3\n2 1 1\n1 1\n\n2 1 0 2 AND\n").unwrap_err();
+    assert!(matches!(err.kind, ParseErrorKind::WrongArity { .. }));
+}
+
+#[test]
+fn bristol_dangling_wire() {
+    let err = parse_bristol_fashion("1 5\n2 1 1\n1 1\n\n2 1 0 3 4 AND\n").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::DanglingWire("3".to_string()));
+    assert_eq!(err.location, Location::Line { line: 5, column: 7 });
+}
+
+#[test]
+fn yosys_parses_bundled_adder() {
+    let contents = read_to_string("./circuits/synth_add64.json").unwrap();
+    let (_circuit, ins, outs) = parse_yosys_json(&contents).unwrap();
+    assert_eq!(ins["a"].len(), 64);
+    assert_eq!(outs["sum"].len(), 64);
+}
+
+#[test]
+fn yosys_truncated_json() {
+    let contents = yosys_json(
+        "$_AND_",
+        r#""A": [2], "B": [3], "Y": [4]"#,
+        r#""A": "input", "B": "input", "Y": "output""#,
+    );
+    let err = parse_yosys_json(&contents[..contents.len() / 2]).unwrap_err();
+    assert!(matches!(err.kind, ParseErrorKind::Malformed(_)));
+    assert!(matches!(err.location, Location::Line { .. }));
+}
+
+#[test]
+fn yosys_unknown_gate() {
+    let contents = yosys_json(
+        "$_FOO_",
+        r#""A": [2], "B": [3], "Y": [4]"#,
+        r#""A": "input", "B": "input", "Y": "output""#,
+    );
+    let err = parse_yosys_json(&contents).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::UnknownGate("$_FOO_".to_string()));
+    assert_eq!(
+        err.location,
+        Location::JsonPath("modules.m.cells.g.type".to_string())
+    );
+}
+
+#[test]
+fn yosys_wrong_arity() {
+    let contents = yosys_json(
+        "$_AND_",
+        r#""A": [2], "Y": [4]"#,
+        r#""A": "input", "Y": "output""#,
+    );
+    let err = parse_yosys_json(&contents).unwrap_err();
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::WrongArity {
+            gate: "$_AND_".to_string(),
+            expected: 2,
+            found: 1,
+        }
+    );
+}
+
+#[test]
+fn yosys_mux_reads_select_first() {
+    // `$_MUX_` is `S ? B : A`, while `MUX` reads `[s, a, b]`
+    let contents = yosys_json(
+        "$_MUX_",
+        r#""A": [2], "B": [2], "S": [3], "Y": [4]"#,
+        r#""A": "input", "B": "input", "S": "input", "Y": "output""#,
+    );
+    let (circuit, _, _) = parse_yosys_json(&contents).unwrap();
+    assert_eq!(
+        circuit["w_4"],
+        (
+            GateType::MUX,
+            vec!["w_3".to_string(), "w_2".to_string(), "w_2".to_string()]
+        )
+    );
+}
+
+#[test]
+fn yosys_dangling_wire() {
+    let contents = yosys_json(
+        "$_AND_",
+        r#""A": [2], "B": [9], "Y": [4]"#,
+        r#""A": "input", "B": "input", "Y": "output""#,
+    );
+    let err = parse_yosys_json(&contents).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::DanglingWire("w_9".to_string()));
+    assert_eq!(
+        err.location,
+        Location::JsonPath("modules.m.cells.g.connections.B".to_string())
+    );
+}
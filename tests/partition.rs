@@ -0,0 +1,85 @@
+use std::{collections::HashSet, fs::read_to_string};
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    optimizer::{
+        optimize_with_config, partition_circuit, OptimizerConfig, Partition, SaturationMode,
+    },
+    parser::{parse_bristol_fashion, parse_yosys_json},
+    utils::GateType,
+};
+
+#[test]
+fn windows_cover_every_gate_once() {
+    let (circuit, _, outs) =
+        parse_bristol_fashion(&read_to_string("./circuits/adder64.txt").unwrap()).unwrap();
+    let outputs = &outs["out"];
+    let gates: HashSet<&String> = circuit
+        .iter()
+        .filter(|(_, (op, _))| *op != GateType::INPUT)
+        .map(|(wire, _)| wire)
+        .collect();
+
+    for partition in [
+        Partition::None,
+        Partition::Levels(8),
+        Partition::Gates(100),
+        Partition::Cones(100),
+    ] {
+        let windows = partition_circuit(&circuit, outputs, partition);
+        let mut seen: HashSet<&String> = HashSet::new();
+        let mut available: HashSet<&String> = circuit
+            .iter()
+            .filter(|(_, (op, _))| *op == GateType::INPUT)
+            .map(|(wire, _)| wire)
+            .collect();
+        for window in windows.iter() {
+            if let Partition::Gates(budget) | Partition::Cones(budget) = partition {
+                assert!(window.gates.len() <= budget);
+            }
+            // Windows only read inputs and earlier windows' outputs
+            assert!(window.inputs.iter().all(|input| available.contains(input)));
+            assert!(window.gates.iter().all(|gate| seen.insert(gate)));
+            available.extend(window.outputs.iter());
+        }
+        assert_eq!(seen, gates, "{:?}", partition);
+        assert!(outputs.iter().all(|output| available.contains(output)));
+    }
+    assert_eq!(
+        partition_circuit(&circuit, outputs, Partition::None).len(),
+        1
+    );
+    assert_eq!(
+        partition_circuit(&circuit, outputs, Partition::Gates(100)).len(),
+        gates.len().div_ceil(100)
+    );
+}
+
+#[test]
+fn partitioned_optimization_is_equivalent() {
+    let (circuit, _, outs) =
+        parse_yosys_json(&read_to_string("./circuits/synth_add64.json").unwrap()).unwrap();
+    let outputs: Vec<String> = outs.values().flatten().cloned().collect();
+
+    for partition in [
+        Partition::Levels(4),
+        Partition::Gates(64),
+        Partition::Cones(64),
+    ] {
+        let config = OptimizerConfig {
+            mode: SaturationMode::Shared,
+            partition,
+            ..Default::default()
+        };
+        let (optimized, report) =
+            optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+        assert!(report.windows > 1);
+        assert!(outputs.iter().all(|output| optimized.contains_key(output)));
+        assert_eq!(
+            check_sat(&circuit, &optimized, &outputs),
+            Ok(Equivalence::Equivalent),
+            "{:?}",
+            partition
+        );
+    }
+}
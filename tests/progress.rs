@@ -0,0 +1,91 @@
+use std::{fs::read_to_string, sync::Arc, sync::Mutex};
+
+use rs::{
+    optimizer::{
+        optimize_with_config, OptimizerConfig, Partition, Phase, Progress, SaturationMode,
+    },
+    parser::parse_bristol_fashion,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Started(Phase, usize),
+    Done(Phase),
+    Finished(Phase),
+}
+
+#[derive(Debug, Default)]
+struct Recorder {
+    events: Mutex<Vec<Event>>,
+}
+
+impl Progress for Recorder {
+    fn phase_started(&self, phase: Phase, steps: usize) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(Event::Started(phase, steps));
+    }
+
+    fn output_done(&self, phase: Phase) {
+        self.events.lock().unwrap().push(Event::Done(phase));
+    }
+
+    fn phase_finished(&self, phase: Phase) {
+        self.events.lock().unwrap().push(Event::Finished(phase));
+    }
+}
+
+/// The events of a phase that ran `steps` steps, in order.
+fn phase(phase: Phase, steps: usize) -> Vec<Event> {
+    let mut events = vec![Event::Started(phase, steps)];
+    events.extend(std::iter::repeat_n(Event::Done(phase), steps));
+    events.push(Event::Finished(phase));
+    events
+}
+
+fn run(config: OptimizerConfig) -> Vec<Event> {
+    let (circuit, _, outs) =
+        parse_bristol_fashion(&read_to_string("./circuits/adder64.txt").unwrap()).unwrap();
+    let recorder = Arc::new(Recorder::default());
+    let config = OptimizerConfig {
+        progress: recorder.clone(),
+        ..config
+    };
+    optimize_with_config(circuit, outs["out"].clone(), &config).unwrap();
+    let events = recorder.events.lock().unwrap().clone();
+    events
+}
+
+#[test]
+fn per_output_reports_every_phase() {
+    let expected: Vec<Event> = [Phase::Traverse, Phase::Saturate, Phase::Rebuild]
+        .into_iter()
+        .flat_map(|p| phase(p, 64))
+        .collect();
+    assert_eq!(run(OptimizerConfig::default()), expected);
+}
+
+#[test]
+fn shared_reports_outputs_together() {
+    let expected: Vec<Event> = [Phase::Traverse, Phase::Saturate]
+        .into_iter()
+        .flat_map(|p| phase(p, 64))
+        .collect();
+    let config = OptimizerConfig {
+        mode: SaturationMode::Shared,
+        ..Default::default()
+    };
+    assert_eq!(run(config), expected);
+}
+
+#[test]
+fn windows_report_only_windows() {
+    let config = OptimizerConfig {
+        mode: SaturationMode::Shared,
+        partition: Partition::Gates(200),
+        ..Default::default()
+    };
+    // 376 gates in windows of 200
+    assert_eq!(run(config), phase(Phase::Windows, 2));
+}
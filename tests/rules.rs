@@ -0,0 +1,80 @@
+use std::collections::BTreeSet;
+
+use egg::rewrite;
+use rs::{
+    optimizer::{
+        check_rule, circuit_rules, optimize_with_rules, xag_lift_rules, xag_rules, CircuitLang,
+        RuleError,
+    },
+    utils::{Circuit, GateType},
+};
+
+#[test]
+fn builtin_rules_are_sound() {
+    for rule in circuit_rules() {
+        assert_eq!(check_rule(&rule), Ok(()));
+    }
+}
+
+#[test]
+fn xag_rules_are_sound() {
+    for rule in xag_rules().iter().chain(xag_lift_rules().iter()) {
+        assert_eq!(check_rule(rule), Ok(()));
+    }
+}
+
+#[test]
+fn builtin_rules_are_unique() {
+    let mut seen = BTreeSet::new();
+    for rule in circuit_rules() {
+        let key = format!(
+            "{} => {}",
+            rule.searcher.get_pattern_ast().unwrap(),
+            rule.applier.get_pattern_ast().unwrap()
+        );
+        assert!(
+            seen.insert(key.clone()),
+            "duplicate rule {}: {}",
+            rule.name,
+            key
+        );
+    }
+}
+
+#[test]
+fn unsound_rules_report_counterexample() {
+    let rule: egg::Rewrite<CircuitLang, ()> =
+        rewrite!("bad-absorption"; "(and ?a (or ?b ?c))" => "?a");
+    let Err(RuleError::Unsound {
+        rule,
+        counterexample,
+    }) = check_rule(&rule)
+    else {
+        panic!("rule should be unsound");
+    };
+    assert_eq!(rule, "bad-absorption");
+    assert!(counterexample["?a"]);
+    assert!(!counterexample["?b"] && !counterexample["?c"]);
+}
+
+#[test]
+fn optimize_refuses_unsound_rules() {
+    let circuit: Circuit = [
+        ("a".to_string(), (GateType::INPUT, vec![])),
+        ("b".to_string(), (GateType::INPUT, vec![])),
+        (
+            "out".to_string(),
+            (GateType::OR, vec!["a".to_string(), "b".to_string()]),
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut rules = circuit_rules();
+    rules.push(rewrite!("or-to-xor"; "(or ?a ?b)" => "(xor ?a ?b)"));
+    let result = optimize_with_rules(circuit.clone(), vec!["out".to_string()], &rules);
+    assert!(matches!(result, Err(RuleError::Unsound { .. })));
+
+    let result = optimize_with_rules(circuit, vec!["out".to_string()], &circuit_rules());
+    assert!(result.is_ok());
+}
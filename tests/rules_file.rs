@@ -0,0 +1,145 @@
+use std::{collections::BTreeMap, fs::read_to_string};
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    optimizer::{
+        format_rules, load_rules, optimize_with_config, parse_rules, synthesize_rules,
+        OptimizerConfig, RuleError, RuleSet, RulesFileError, SynthesisConfig,
+    },
+    parser::{parse_yosys_json, Location, ParseErrorKind},
+};
+
+fn error_line(source: &str) -> usize {
+    match parse_rules(source) {
+        Err(RulesFileError::Parse(error)) => match error.location {
+            Location::Line { line, .. } => line,
+            location => panic!("unexpected location {:?}", location),
+        },
+        other => panic!("expected a parse error, got {:?}", other.map(|r| r.len())),
+    }
+}
+
+#[test]
+fn bundled_rules_file_loads() {
+    let rules = load_rules("./rules/xor.rules").unwrap();
+    let names: Vec<String> = rules.iter().map(|rule| rule.name.to_string()).collect();
+    assert!(names.contains(&"or-of-andnots".to_string()));
+    // Bidirectional rules come with their reverse
+    assert!(names.contains(&"xnor-as-not".to_string()));
+    assert!(names.contains(&"xnor-as-not-reverse".to_string()));
+}
+
+#[test]
+fn comments_and_blank_lines_are_skipped() {
+    let source = "# header\n\nxor-commute; (xor ?a ?b) => (xor ?b ?a) # trailing\n";
+    assert_eq!(parse_rules(source).unwrap().len(), 1);
+}
+
+#[test]
+fn malformed_lines_are_reported() {
+    assert_eq!(
+        error_line("ok; (xor ?a ?b) => (xor ?b ?a)\nno-separator"),
+        2
+    );
+    assert_eq!(error_line("no-arrow; (xor ?a ?b)"), 1);
+    assert_eq!(error_line("; (xor ?a ?b) => (xor ?b ?a)"), 1);
+    assert_eq!(error_line("\n\nunknown-op; (frob ?a ?b) => ?a"), 3);
+    assert_eq!(error_line("wire; (and a ?b) => ?b"), 1);
+    assert_eq!(error_line("unbound; (not ?a) => (not ?b)"), 1);
+    assert_eq!(
+        error_line("same; (xor ?a ?b) => (xor ?b ?a)\nsame; (and ?a ?b) => (and ?b ?a)"),
+        2
+    );
+}
+
+#[test]
+fn wrong_arity_is_rejected() {
+    let Err(RulesFileError::Parse(error)) = parse_rules("arity; (not ?a ?b) => ?a") else {
+        panic!("expected a parse error");
+    };
+    assert!(matches!(error.kind, ParseErrorKind::Malformed(_)));
+    assert_eq!(error.location, Location::Line { line: 1, column: 8 });
+}
+
+#[test]
+fn unsound_rules_are_rejected() {
+    let source = "ok; (and ?a ?b) => (and ?b ?a)\nbad; (or ?a ?b) => (xor ?a ?b)";
+    assert_eq!(
+        parse_rules(source).map(|rules| rules.len()),
+        Err(RulesFileError::Unsound {
+            line: 2,
+            error: RuleError::Unsound {
+                rule: "bad".to_string(),
+                counterexample: BTreeMap::from([
+                    ("?a".to_string(), true),
+                    ("?b".to_string(), true)
+                ]),
+            },
+        })
+    );
+    // Both sides of a rule are evaluated on the same assignments, so the two directions of
+    // a bidirectional rule are sound or unsound together, and the forward one is reported
+    assert_eq!(
+        parse_rules("bad; (and ?a ?b) <=> ?a").map(|rules| rules.len()),
+        Err(RulesFileError::Unsound {
+            line: 1,
+            error: RuleError::Unsound {
+                rule: "bad".to_string(),
+                counterexample: BTreeMap::from([
+                    ("?a".to_string(), true),
+                    ("?b".to_string(), false)
+                ]),
+            },
+        })
+    );
+    // A sound rule whose reverse would have to invent a variable is rejected on the reverse
+    let Err(RulesFileError::Parse(error)) = parse_rules("ok; (and ?a (not ?a)) <=> false") else {
+        panic!("expected a parse error");
+    };
+    assert_eq!(
+        error.kind,
+        ParseErrorKind::Malformed("Rewrite ok-reverse refers to unbound var ?a".to_string())
+    );
+    assert_eq!(error.location, Location::Line { line: 1, column: 5 });
+}
+
+#[test]
+fn formatted_rules_parse_back() {
+    let rules = synthesize_rules(&SynthesisConfig {
+        max_size: 4,
+        ..Default::default()
+    });
+    let text = format_rules(&rules);
+    let parsed = parse_rules(&text).unwrap();
+    assert_eq!(text, format_rules(&parsed));
+}
+
+#[test]
+fn missing_file_is_reported() {
+    assert!(matches!(
+        load_rules("./rules/missing.rules"),
+        Err(RulesFileError::Io { .. })
+    ));
+}
+
+#[test]
+fn optimize_with_rules_file() {
+    let (circuit, _, outs) =
+        parse_yosys_json(&read_to_string("./circuits/synth_add64.json").unwrap()).unwrap();
+    let outputs: Vec<String> = outs.values().flatten().cloned().collect();
+    for rules in [
+        RuleSet::Only(load_rules("./rules/xor.rules").unwrap()),
+        RuleSet::Extended(load_rules("./rules/xor.rules").unwrap()),
+    ] {
+        let config = OptimizerConfig {
+            rules,
+            ..Default::default()
+        };
+        let (optimized, _) =
+            optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+        assert_eq!(
+            check_sat(&circuit, &optimized, &outputs),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+}
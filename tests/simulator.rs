@@ -0,0 +1,220 @@
+mod common;
+
+use std::{collections::BTreeMap, fs::read_to_string};
+
+use fernet::Fernet;
+use rand::Rng;
+use rs::{
+    evaluator::Evaluator,
+    garbler::Garbler,
+    optimizer::optimize,
+    parser::parse_yosys_json,
+    simulator::{simulate, simulate_sliced},
+    utils::{wire_values, Circuit, GateType, Ports},
+};
+
+use common::{add_gate, load_bristol_adder};
+
+fn to_u64(values: &BTreeMap<String, i32>, wires: &[String]) -> u64 {
+    wires
+        .iter()
+        .enumerate()
+        .fold(0, |acc, (i, wire)| acc | ((values[wire] as u64) << i))
+}
+
+/// Packs one 64-bit operand per lane into one word per input wire.
+fn pack_lanes(wires: &[String], lanes: &[u64]) -> BTreeMap<String, u64> {
+    wires
+        .iter()
+        .enumerate()
+        .map(|(bit, wire)| {
+            let word = lanes.iter().enumerate().fold(0u64, |acc, (lane, value)| {
+                acc | (((value >> bit) & 1) << lane)
+            });
+            (wire.clone(), word)
+        })
+        .collect()
+}
+
+#[test]
+fn simulates_bristol_adder() {
+    let (circuit, ins, outs) = load_bristol_adder();
+    let mut rng = rand::thread_rng();
+    for _ in 0..16 {
+        let a = rng.gen_range(0..i32::MAX);
+        let b = rng.gen_range(0..i32::MAX);
+        let mut inputs = wire_values(&ins["a"], a);
+        inputs.extend(wire_values(&ins["b"], b));
+        let values = simulate(&circuit, &inputs);
+        assert_eq!(to_u64(&values, &outs["out"]), a as u64 + b as u64);
+    }
+}
+
+#[test]
+fn sliced_simulation_matches_addition() {
+    let (circuit, ins, outs) = load_bristol_adder();
+    let mut rng = rand::thread_rng();
+    let a: Vec<u64> = (0..64).map(|_| rng.gen()).collect();
+    let b: Vec<u64> = (0..64).map(|_| rng.gen()).collect();
+
+    let mut inputs = pack_lanes(&ins["a"], &a);
+    inputs.extend(pack_lanes(&ins["b"], &b));
+    let values = simulate_sliced(&circuit, &inputs);
+
+    for lane in 0..64 {
+        let sum = outs["out"]
+            .iter()
+            .enumerate()
+            .fold(0u64, |acc, (bit, wire)| {
+                acc | (((values[wire] >> lane) & 1) << bit)
+            });
+        assert_eq!(sum, a[lane].wrapping_add(b[lane]));
+    }
+}
+
+#[test]
+fn garbled_evaluation_matches_simulation() {
+    let (circuit, ins, outs) = load_bristol_adder();
+    let garbler = Garbler::new(
+        Fernet::generate_key(),
+        circuit.clone(),
+        ins.clone(),
+        outs.clone(),
+    );
+    let (wire_to_keys, garbled_gates) = garbler.build();
+
+    let mut inputs = wire_values(&ins["a"], 999);
+    inputs.extend(wire_values(&ins["b"], 77));
+    let labels: BTreeMap<String, String> = inputs
+        .iter()
+        .map(|(wire, value)| {
+            let keys = &wire_to_keys[wire];
+            let label = if *value == 0 { &keys.0 } else { &keys.1 };
+            (wire.clone(), label.clone())
+        })
+        .collect();
+
+    let mut evaluator = Evaluator::new(
+        circuit.clone(),
+        outs["out"].clone(),
+        wire_to_keys,
+        garbled_gates,
+    );
+    let garbled = evaluator.run(vec![labels]);
+    let expected = simulate(&circuit, &inputs);
+    for wire in outs["out"].iter() {
+        assert_eq!(garbled[wire], expected[wire], "mismatch on {}", wire);
+    }
+}
+
+#[test]
+fn garbled_circuits_are_indexed_by_wire_id() {
+    let (circuit, ins, outs) = load_bristol_adder();
+    let garbler = Garbler::new(
+        Fernet::generate_key(),
+        circuit.clone(),
+        ins.clone(),
+        outs.clone(),
+    );
+    let indexed = garbler.circuit().clone();
+    let garbled = garbler.garble();
+    assert_eq!(garbled.keys.len(), indexed.num_wires());
+    assert_eq!(garbled.tables.len(), indexed.gates.len());
+
+    let mut inputs = wire_values(&ins["a"], 12345);
+    inputs.extend(wire_values(&ins["b"], 678));
+    let ids = indexed.name_table().unwrap();
+    let labels: Vec<_> = inputs
+        .iter()
+        .map(|(wire, value)| {
+            let id = ids[wire.as_str()];
+            let keys = &garbled.keys[id.index()];
+            (
+                id,
+                if *value == 0 {
+                    keys.0.as_str()
+                } else {
+                    keys.1.as_str()
+                },
+            )
+        })
+        .collect();
+
+    let mut evaluator =
+        Evaluator::from_garbled(indexed.clone(), indexed.inputs.clone(), garbled.clone());
+    let values = evaluator.evaluate(&labels);
+    let expected = simulate(&circuit, &inputs);
+    for (id, name) in indexed.names.as_ref().unwrap().iter().enumerate() {
+        assert_eq!(values[id] as i32, expected[name], "mismatch on {}", name);
+    }
+
+    // Outputs that are input wires decode from their own labels
+    let named_labels = labels
+        .iter()
+        .map(|&(id, label)| (indexed.name(id), label.to_string()))
+        .collect();
+    assert_eq!(evaluator.run(vec![named_labels]), inputs);
+}
+
+#[test]
+fn inversions_are_garbled_without_tables() {
+    let mut circuit = Circuit::new();
+    add_gate(&mut circuit, "a", GateType::INPUT, &[]);
+    add_gate(&mut circuit, "b", GateType::INPUT, &[]);
+    add_gate(&mut circuit, "n", GateType::NOT, &["a"]);
+    add_gate(&mut circuit, "x", GateType::XNOR, &["a", "b"]);
+    add_gate(&mut circuit, "c", GateType::CONST, &["x"]);
+    add_gate(&mut circuit, "y", GateType::AND, &["n", "c"]);
+    let outputs: Vec<String> = ["n", "x", "c", "y"].map(String::from).to_vec();
+    let ins: Ports = [("in".to_string(), vec!["a".to_string(), "b".to_string()])].into();
+    let outs: Ports = [("out".to_string(), outputs.clone())].into();
+
+    let garbler = Garbler::new(Fernet::generate_key(), circuit.clone(), ins, outs);
+    let (wire_to_keys, gates) = garbler.build();
+    for wire in ["n", "x", "c"] {
+        assert!(gates[wire].table.is_empty(), "{} has a table", wire);
+    }
+    assert_eq!(gates["y"].table.len(), 4);
+
+    for row in 0..4 {
+        let inputs = BTreeMap::from([("a".to_string(), row & 1), ("b".to_string(), row >> 1)]);
+        let labels = inputs
+            .iter()
+            .map(|(wire, value)| {
+                let keys = &wire_to_keys[wire];
+                let label = if *value == 0 { &keys.0 } else { &keys.1 };
+                (wire.clone(), label.clone())
+            })
+            .collect();
+        let mut evaluator = Evaluator::new(
+            circuit.clone(),
+            outputs.clone(),
+            wire_to_keys.clone(),
+            gates.clone(),
+        );
+        let result = evaluator.run(vec![labels]);
+        let expected = simulate(&circuit, &inputs);
+        for wire in outputs.iter() {
+            assert_eq!(result[wire], expected[wire], "mismatch on {}", wire);
+        }
+    }
+}
+
+#[test]
+fn optimized_yosys_adder_matches_simulation() {
+    let contents = read_to_string("./circuits/synth_add64.json").unwrap();
+    let (circuit, ins, outs) = parse_yosys_json(&contents).unwrap();
+    let optimized = optimize(circuit.clone(), outs["sum"].clone());
+
+    let mut rng = rand::thread_rng();
+    let a: Vec<u64> = (0..64).map(|_| rng.gen()).collect();
+    let b: Vec<u64> = (0..64).map(|_| rng.gen()).collect();
+    let mut inputs = pack_lanes(&ins["a"], &a);
+    inputs.extend(pack_lanes(&ins["b"], &b));
+
+    let expected = simulate_sliced(&circuit, &inputs);
+    let actual = simulate_sliced(&optimized, &inputs);
+    for wire in outs["sum"].iter() {
+        assert_eq!(actual[wire], expected[wire], "mismatch on {}", wire);
+    }
+}
@@ -0,0 +1,69 @@
+use std::fs::read_to_string;
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    optimizer::{
+        check_rules, optimize_with_config, synthesize_rules, OptimizerConfig, RuleSet,
+        SaturationMode, SynthesisConfig,
+    },
+    parser::parse_yosys_json,
+    utils::GateType,
+};
+
+fn small() -> SynthesisConfig {
+    SynthesisConfig {
+        variables: 2,
+        max_size: 4,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn synthesized_rules_are_sound() {
+    let rules = synthesize_rules(&small());
+    assert!(!rules.is_empty());
+    assert_eq!(check_rules(&rules), Ok(()));
+}
+
+#[test]
+fn synthesized_rules_include_commutativity() {
+    let names: Vec<String> = synthesize_rules(&small())
+        .iter()
+        .map(|rule| rule.name.to_string())
+        .collect();
+    assert!(names.contains(&"(and ?a ?b) => (and ?b ?a)".to_string()));
+    assert!(names.contains(&"(xor ?a ?b) => (xor ?b ?a)".to_string()));
+    // A bare variable would match every e-class
+    assert!(names.iter().all(|name| name.starts_with('(')));
+}
+
+#[test]
+fn synthesized_rules_respect_gate_set() {
+    let config = SynthesisConfig {
+        gates: vec![GateType::AND, GateType::XOR, GateType::NOT],
+        ..small()
+    };
+    for rule in synthesize_rules(&config) {
+        let name = rule.name.to_string();
+        for gate in ["or", "nor", "nand", "xnor", "andnot", "ornot"] {
+            assert!(!name.contains(&format!("({} ", gate)), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn optimize_with_synthesized_rules() {
+    let (circuit, _, outs) =
+        parse_yosys_json(&read_to_string("./circuits/synth_add64.json").unwrap()).unwrap();
+    let outputs: Vec<String> = outs.values().flatten().cloned().collect();
+    let config = OptimizerConfig {
+        rules: RuleSet::Only(synthesize_rules(&small())),
+        mode: SaturationMode::Shared,
+        ..Default::default()
+    };
+    let (optimized, _) = optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}
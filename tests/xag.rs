@@ -0,0 +1,94 @@
+mod common;
+
+use std::fs::read_to_string;
+
+use egg::{Pattern, RecExpr, Rewrite};
+
+use rs::{
+    equivalence::{check_sat, Equivalence},
+    optimizer::{
+        check_rule, optimize_with_config, to_xag, CircuitLang, Extraction, Normalization,
+        OptimizerConfig, SaturationMode,
+    },
+    parser::parse_yosys_json,
+    utils::{Circuit, GateType},
+};
+
+use common::non_free_gates;
+
+#[test]
+fn lowering_keeps_and_xor_not() {
+    for gate in ["and", "or", "xor", "nand", "nor", "xnor", "andnot", "ornot"] {
+        let expr: RecExpr<CircuitLang> = format!("(not ({} a (xor a b)))", gate).parse().unwrap();
+        let xag = to_xag(&expr);
+
+        assert!(xag.as_ref().iter().all(|node| matches!(
+            node,
+            CircuitLang::And(_) | CircuitLang::Xor(_) | CircuitLang::Not(_) | CircuitLang::Wire(_)
+        )));
+        let lowering: Rewrite<CircuitLang, ()> =
+            Rewrite::new(gate, Pattern::from(&expr), Pattern::from(&xag)).unwrap();
+        assert_eq!(check_rule(&lowering), Ok(()));
+    }
+}
+
+#[test]
+fn xag_constants_are_never_extracted() {
+    // y folds to b and z to false; the constants only exist for the rewrites
+    let mut circuit = Circuit::new();
+    for input in ["a", "b"] {
+        circuit.insert(input.to_string(), (GateType::INPUT, vec![]));
+    }
+    let gates: [(&str, GateType, &[&str]); 5] = [
+        ("t", GateType::XOR, &["a", "a"]),
+        ("y", GateType::OR, &["b", "t"]),
+        ("nb", GateType::NOT, &["b"]),
+        ("z", GateType::AND, &["b", "nb"]),
+        ("w", GateType::XNOR, &["z", "a"]),
+    ];
+    for (wire, op, inputs) in gates {
+        circuit.insert(
+            wire.to_string(),
+            (op, inputs.iter().map(|i| i.to_string()).collect()),
+        );
+    }
+    let outputs = vec!["y".to_string(), "z".to_string(), "w".to_string()];
+    for extraction in [Extraction::Tree, Extraction::Dag] {
+        let config = OptimizerConfig {
+            mode: SaturationMode::Shared,
+            normalization: Normalization::Xag,
+            extraction,
+            ..Default::default()
+        };
+        let (optimized, _) =
+            optimize_with_config(circuit.clone(), outputs.clone(), &config).unwrap();
+        assert_eq!(
+            check_sat(&circuit, &optimized, &outputs),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+}
+
+#[test]
+fn xag_normalization_saves_tables_on_yosys_adder() {
+    let (circuit, _, outs) =
+        parse_yosys_json(&read_to_string("./circuits/synth_add64.json").unwrap()).unwrap();
+    let outputs: Vec<String> = outs.values().flatten().cloned().collect();
+    let xag = OptimizerConfig {
+        mode: SaturationMode::Shared,
+        extraction: Extraction::Dag,
+        normalization: Normalization::Xag,
+        ..Default::default()
+    };
+    let (optimized, _) = optimize_with_config(circuit.clone(), outputs.clone(), &xag).unwrap();
+
+    // The lifting pass brings back the gates outside the XAG set
+    assert!(optimized
+        .values()
+        .any(|(op, _)| matches!(op, GateType::NOR | GateType::ANDNOT | GateType::ORNOT)));
+    assert!(non_free_gates(&optimized) < non_free_gates(&circuit));
+    assert_eq!(
+        check_sat(&circuit, &optimized, &outputs),
+        Ok(Equivalence::Equivalent)
+    );
+}